@@ -1,22 +1,39 @@
 use dashmap::DashMap;
-use faber_runtime::{TaskGroup, TaskGroupResult};
+use faber_runtime::TaskGroupResult;
 use sha2::{Digest, Sha256};
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use crate::request::ExecuteRequest;
 
 #[derive(Clone)]
 pub struct ExecutionCache {
     cache: Arc<DashMap<String, TaskGroupResult>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+/// Lookups and size of an [`ExecutionCache`] since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entries: u64,
+    pub hits: u64,
+    pub misses: u64,
 }
 
 impl ExecutionCache {
     pub fn new() -> Self {
         Self {
             cache: Arc::new(DashMap::new()),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
         }
     }
 
-    pub fn generate_hash(task_group: &TaskGroup) -> String {
-        let serialized = serde_json::to_string(task_group).unwrap_or_default();
+    pub fn generate_hash(request: &ExecuteRequest) -> String {
+        let serialized = serde_json::to_string(request).unwrap_or_default();
         let mut hasher = Sha256::new();
         hasher.update(serialized.as_bytes());
         format!("{:x}", hasher.finalize())
@@ -27,7 +44,22 @@ impl ExecutionCache {
     }
 
     pub fn try_from_hash(&self, hash: &String) -> Option<TaskGroupResult> {
-        self.cache.get(hash).map(|entry| entry.clone())
+        let result = self.cache.get(hash).map(|entry| entry.clone());
+        let counter = if result.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.cache.len() as u64,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
     }
 }
 
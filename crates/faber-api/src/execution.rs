@@ -0,0 +1,64 @@
+use faber_runtime::{
+    ContainerPool, CpuAllocator, Environment, ExecutionBackend, MountSpec, NamespaceBackend,
+    WorkspaceTemplate,
+};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+/// Operator-provided execution settings shared by every request.
+#[derive(Debug, Clone)]
+pub struct ExecutionConfig {
+    /// Runs the task groups of requests that do not select a backend.
+    pub backend: Arc<dyn ExecutionBackend>,
+    /// Named backends selectable per request with `backend`.
+    pub backends: HashMap<String, Arc<dyn ExecutionBackend>>,
+    /// Named root filesystems selectable per request with `environment`.
+    pub environments: HashMap<String, Environment>,
+    /// Named starter-file layers selectable per request with `workspace`.
+    pub workspaces: HashMap<String, WorkspaceTemplate>,
+    /// Named extra mounts selectable per request with `mounts`.
+    pub mounts: HashMap<String, MountSpec>,
+    /// Pre-warmed containers used by the backend, reported by `/health`.
+    pub pool: Option<Arc<ContainerPool>>,
+    /// Confines every task with a Landlock ruleset where the kernel allows.
+    pub landlock: bool,
+    /// Exclusive CPUs used by the backend, reported by `/health`.
+    pub cpus: Option<Arc<CpuAllocator>>,
+    /// Largest per-stream output limit a task may ask for with
+    /// `capture.limit_bytes`.
+    pub max_output_limit: usize,
+    /// Longest `termination.grace_period_ms` a task may ask for.
+    pub max_grace_period_ms: u64,
+    /// Streams longer than this are moved to the file store and returned by
+    /// id instead of inline.
+    pub inline_output_limit: usize,
+    /// Where the runtime writes streams longer than `inline_output_limit`
+    /// until they are moved to the file store.
+    pub output_spill_dir: PathBuf,
+    /// Free space `/ready` requires where containers are built and files
+    /// are stored.
+    pub ready_min_free_bytes: u64,
+    /// Share of recent executions that may fail during container setup
+    /// before `/ready` reports the server as not ready.
+    pub ready_max_setup_failure_rate: f64,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            backend: Arc::new(NamespaceBackend::default()),
+            backends: HashMap::new(),
+            environments: HashMap::new(),
+            workspaces: HashMap::new(),
+            mounts: HashMap::new(),
+            pool: None,
+            landlock: false,
+            cpus: None,
+            max_output_limit: 16 * 1024 * 1024,
+            max_grace_period_ms: 5000,
+            inline_output_limit: 1024 * 1024,
+            output_spill_dir: std::env::temp_dir().join("faber-output"),
+            ready_min_free_bytes: 256 * 1024 * 1024,
+            ready_max_setup_failure_rate: 0.5,
+        }
+    }
+}
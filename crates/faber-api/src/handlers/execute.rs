@@ -1,52 +1,306 @@
-use crate::{ExecutionCache, state::AppState};
-use axum::{extract::State, http::StatusCode, response::Json};
-use faber_runtime::{RuntimeBuilder, RuntimeResult, TaskGroup, TaskGroupResult};
+use crate::{ExecuteRequest, ExecutionCache, output::store_spilled_outputs, state::AppState};
+use axum::{
+    extract::State,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::Json,
+};
+use faber_runtime::{
+    ContainerConfigBuilder, ErrorCode, ErrorInfo, ErrorStage, GroupTimings, RuntimeResult,
+    TaskGroupResult,
+};
+use faber_store::FileId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Error envelope returned by `/execute` instead of a task group result.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExecuteErrorResponse {
+    pub error: ExecuteError,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExecuteError {
+    #[serde(flatten)]
+    pub info: ErrorInfo,
+    pub message: String,
+}
+
+/// Body of a successful `/execute`: the bare list of step results, or, for
+/// object requests with `"timings": true`, the results next to the task
+/// group's phase timings.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExecuteResponse {
+    Results(TaskGroupResult),
+    WithTimings {
+        results: TaskGroupResult,
+        timings: GroupTimings,
+    },
+}
+
+impl ExecuteResponse {
+    fn new(results: TaskGroupResult, timings: GroupTimings, with_timings: bool) -> Self {
+        if with_timings {
+            ExecuteResponse::WithTimings { results, timings }
+        } else {
+            ExecuteResponse::Results(results)
+        }
+    }
+
+    pub fn into_results(self) -> TaskGroupResult {
+        match self {
+            ExecuteResponse::Results(results) | ExecuteResponse::WithTimings { results, .. } => {
+                results
+            }
+        }
+    }
+}
+
+type Rejection = (StatusCode, Json<ExecuteErrorResponse>);
+
+/// Rejected submissions are a 400, errors worth retrying a 503 and anything
+/// else a 500.
+fn reject(info: ErrorInfo, message: String) -> Rejection {
+    eprintln!("{}", message);
+    let status = if info.code.is_client_error() {
+        StatusCode::BAD_REQUEST
+    } else if info.retryable {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    (
+        status,
+        Json(ExecuteErrorResponse {
+            error: ExecuteError { info, message },
+        }),
+    )
+}
+
+fn server_timing_header(value: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(HeaderName::from_static("server-timing"), value);
+    }
+    headers
+}
+
+/// Reports the group's phase timings in a `Server-Timing` header, so they
+/// are available even when the body is the bare list of step results.
+fn server_timing(timings: &GroupTimings) -> HeaderMap {
+    let value = [
+        ("queue", timings.queue_usec),
+        ("container_setup", timings.container_setup_usec),
+        ("steps", timings.steps_usec),
+        ("container_cleanup", timings.cleanup_usec),
+        ("execution", timings.total_usec),
+    ]
+    .iter()
+    .map(|(phase, usec)| format!("{};dur={:.3}", phase, *usec as f64 / 1000.0))
+    .collect::<Vec<_>>()
+    .join(", ");
+    server_timing_header(&value)
+}
+
+/// Marks a result served from the cache; nothing ran, so there are no phase
+/// timings, only the lookup.
+fn cache_hit_server_timing(lookup: Duration) -> HeaderMap {
+    server_timing_header(&format!(
+        "cache;desc=hit;dur={:.3}",
+        lookup.as_micros() as f64 / 1000.0
+    ))
+}
+
+fn invalid_request(message: String) -> Rejection {
+    reject(
+        ErrorInfo::new(ErrorCode::InvalidRequest, ErrorStage::Validation),
+        message,
+    )
+}
 
 pub async fn execute(
     State(app_state): State<AppState>,
-    Json(task_group): Json<TaskGroup>,
-) -> Result<Json<TaskGroupResult>, StatusCode> {
-    if task_group.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    Json(mut request): Json<ExecuteRequest>,
+) -> Result<(HeaderMap, Json<ExecuteResponse>), Rejection> {
+    if request.tasks.is_empty() {
+        return Err(invalid_request("The task group is empty".to_string()));
     }
 
-    let task_hash = ExecutionCache::generate_hash(&task_group);
+    let mut container_config = ContainerConfigBuilder::new()
+        .with_landlock(app_state.execution.landlock)
+        .with_output_spill(
+            &app_state.execution.output_spill_dir,
+            app_state.execution.inline_output_limit,
+        );
+    if let Some(name) = &request.environment {
+        let Some(environment) = app_state.execution.environments.get(name) else {
+            return Err(invalid_request(format!(
+                "Unknown execution environment: {}",
+                name
+            )));
+        };
+        container_config = container_config.with_environment(environment);
+    }
+    if let Some(name) = &request.workspace {
+        let Some(template) = app_state.execution.workspaces.get(name) else {
+            return Err(invalid_request(format!(
+                "Unknown workspace template: {}",
+                name
+            )));
+        };
+        container_config = container_config.with_workspace_template(template);
+    }
+    for name in &request.mounts {
+        let Some(mount) = app_state.execution.mounts.get(name) else {
+            return Err(invalid_request(format!("Unknown mount: {}", name)));
+        };
+        container_config = container_config.with_mount(mount.clone());
+    }
+
+    if let Some(limit) = request.requested_output_limit()
+        && limit > app_state.execution.max_output_limit
+    {
+        return Err(invalid_request(format!(
+            "Requested output limit {} exceeds the maximum of {}",
+            limit, app_state.execution.max_output_limit
+        )));
+    }
+
+    for termination in request.termination_policies() {
+        if termination.grace_period_ms > app_state.execution.max_grace_period_ms {
+            return Err(invalid_request(format!(
+                "Requested grace period of {}ms exceeds the maximum of {}ms",
+                termination.grace_period_ms, app_state.execution.max_grace_period_ms
+            )));
+        }
+        if let Err(e) = termination.signal() {
+            return Err(reject(e.info(), e.to_string()));
+        }
+    }
 
-    if app_state.cache_enabled {
-        if let Some(cached_result) = app_state.cache.try_from_hash(&task_hash) {
-            return Ok(Json(cached_result));
+    let backend = match &request.backend {
+        Some(name) => {
+            let Some(backend) = app_state.execution.backends.get(name) else {
+                return Err(invalid_request(format!(
+                    "Unknown execution backend: {}",
+                    name
+                )));
+            };
+            backend.clone()
         }
+        None => app_state.execution.backend.clone(),
+    };
+
+    let task_hash = ExecutionCache::generate_hash(&request);
+
+    let lookup_start = Instant::now();
+    if app_state.cache_enabled
+        && let Some(cached_result) = app_state.cache.try_from_hash(&task_hash)
+    {
+        return Ok((
+            cache_hit_server_timing(lookup_start.elapsed()),
+            Json(ExecuteResponse::new(
+                cached_result,
+                GroupTimings::default(),
+                request.timings,
+            )),
+        ));
     }
 
-    let runtime = RuntimeBuilder::default()
-        .with_task_group(task_group.clone())
-        .build();
+    // Stdin files are bound into the container from the store's disk; stores
+    // that only keep files in memory hand the content over inline instead.
+    let mut inline_stdin = HashMap::new();
+    for file_id in request.stdin_file_ids() {
+        let id = FileId::from(file_id.as_str());
+        match app_state.file_store.local_path(&id).await {
+            Ok(Some(path)) => {
+                container_config = container_config.with_stdin_file(file_id, path);
+            }
+            Ok(None) => {
+                let content = match app_state.file_store.get(&id).await {
+                    Ok(file) => String::from_utf8(file.content).ok(),
+                    Err(_) => None,
+                };
+                let Some(content) = content else {
+                    return Err(invalid_request(format!(
+                        "Stdin file {} cannot be passed inline",
+                        file_id
+                    )));
+                };
+                inline_stdin.insert(file_id, content);
+            }
+            Err(e) => {
+                return Err(invalid_request(format!(
+                    "Unknown stdin file {}: {}",
+                    file_id, e
+                )));
+            }
+        }
+    }
+    for task in request.all_tasks_mut() {
+        if let Some(content) = task
+            .stdin_file_id
+            .as_ref()
+            .and_then(|file_id| inline_stdin.get(file_id))
+            && task.stdin.is_none()
+        {
+            task.stdin = Some(content.clone());
+            task.stdin_file_id = None;
+        }
+    }
 
-    let result = tokio::task::spawn_blocking(move || runtime.execute())
-        .await
-        .map_err(|e| {
-            eprintln!("Spawn blocking error: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    let profiles = request.profiles();
+    let with_timings = request.timings;
+    let in_flight = app_state.metrics.in_flight();
+    let start = Instant::now();
+    let result = backend
+        .execute(request.tasks, container_config.build())
+        .await;
+    app_state
+        .metrics
+        .observe_duration(start.elapsed().as_micros() as u64);
+    drop(in_flight);
 
     match result {
         Ok(runtime_result) => match runtime_result {
-            RuntimeResult::Success(task_group_result) => {
-                if app_state.cache_enabled {
+            RuntimeResult::Success(mut task_group_result, timings) => {
+                app_state
+                    .metrics
+                    .observe_execution(&task_group_result, &timings);
+                app_state
+                    .metrics
+                    .observe_tasks(&profiles, &task_group_result);
+                let stored =
+                    store_spilled_outputs(&mut task_group_result, app_state.file_store.as_ref())
+                        .await;
+                // Stored outputs expire with the file store's TTL, so results
+                // that refer to them are not cached.
+                if app_state.cache_enabled && !stored {
                     app_state
                         .cache
                         .cache_result(task_hash, task_group_result.clone());
                 }
-                Ok(Json(task_group_result))
+                Ok((
+                    server_timing(&timings),
+                    Json(ExecuteResponse::new(
+                        task_group_result,
+                        timings,
+                        with_timings,
+                    )),
+                ))
             }
-            RuntimeResult::ContainerSetupFailed { error } => {
-                eprintln!("Container setup failed: {}", error);
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+            RuntimeResult::ContainerSetupFailed { error, info } => {
+                app_state.metrics.observe_setup_failure(&info);
+                Err(reject(info, format!("Container setup failed: {}", error)))
             }
         },
         Err(e) => {
-            eprintln!("Runtime execution failed: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            let info = e.info();
+            app_state.metrics.observe_setup_failure(&info);
+            Err(reject(info, format!("Runtime execution failed: {}", e)))
         }
     }
 }
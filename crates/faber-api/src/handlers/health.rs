@@ -1,14 +1,32 @@
-use axum::{http::StatusCode, response::Json};
+use axum::{extract::State, http::StatusCode, response::Json};
+use faber_runtime::{CpuAllocatorStats, PoolStats};
 use serde::{Deserialize, Serialize};
 
+use crate::state::AppState;
+
 #[derive(Serialize, Deserialize)]
 pub struct HealthResponse {
     status: String,
+    backend: String,
+    isolated: bool,
+    backends: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool: Option<PoolStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpus: Option<CpuAllocatorStats>,
 }
 
-pub async fn health() -> Result<Json<HealthResponse>, StatusCode> {
+pub async fn health(State(app_state): State<AppState>) -> Result<Json<HealthResponse>, StatusCode> {
+    let mut backends: Vec<String> = app_state.execution.backends.keys().cloned().collect();
+    backends.sort();
+
     let response = HealthResponse {
         status: "ok".to_string(),
+        backend: app_state.execution.backend.name().to_string(),
+        isolated: app_state.execution.backend.is_isolated(),
+        backends,
+        pool: app_state.execution.pool.as_ref().map(|pool| pool.stats()),
+        cpus: app_state.execution.cpus.as_ref().map(|cpus| cpus.stats()),
     };
 
     Ok(Json(response))
@@ -0,0 +1,29 @@
+use axum::{
+    extract::State,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use faber_runtime::Runtime;
+
+use crate::state::AppState;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+pub async fn metrics(State(app_state): State<AppState>) -> impl IntoResponse {
+    let stores = match app_state.file_store.stats().await {
+        Ok(stores) => stores,
+        Err(e) => {
+            eprintln!("Failed to read file store stats: {}", e);
+            Vec::new()
+        }
+    };
+
+    let body = app_state.metrics.render(
+        app_state.cache.stats(),
+        &stores,
+        app_state.execution.pool.as_ref().map(|pool| pool.stats()),
+        Runtime::container_cleanup_failures(),
+    );
+
+    (StatusCode::OK, [(header::CONTENT_TYPE, CONTENT_TYPE)], body)
+}
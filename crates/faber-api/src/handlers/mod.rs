@@ -1,7 +1,11 @@
 mod execute;
 mod file;
 mod health;
+mod metrics;
+mod ready;
 
-pub use execute::execute;
+pub use execute::{ExecuteError, ExecuteErrorResponse, ExecuteResponse, execute};
 pub use file::{delete_file, download_file, list_files, upload_file};
 pub use health::health;
+pub use metrics::metrics;
+pub use ready::{ReadyResponse, ready};
@@ -0,0 +1,74 @@
+use axum::{extract::State, http::StatusCode, response::Json};
+use faber_runtime::{ReadinessCheck, check_free_space};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::Path};
+
+use crate::{metrics::RECENT_WINDOW, state::AppState};
+
+/// Recent executions needed before the setup failure rate can fail `/ready`,
+/// so that one early failure does not take the server out of rotation.
+const MIN_RECENT_EXECUTIONS: u64 = 5;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadyResponse {
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
+
+pub async fn ready(State(app_state): State<AppState>) -> (StatusCode, Json<ReadyResponse>) {
+    let execution = &app_state.execution;
+    let min_free_bytes = execution.ready_min_free_bytes;
+
+    // Every backend a request can select must be usable, each checked once.
+    // The checks read the filesystem, so they run on the blocking pool.
+    let mut checked = HashSet::new();
+    let backends: Vec<_> = std::iter::once(&execution.backend)
+        .chain(execution.backends.values())
+        .filter(|backend| checked.insert(backend.name()))
+        .cloned()
+        .collect();
+    let store_root = app_state.file_store.root_dir().map(Path::to_path_buf);
+    let filesystem_checks = tokio::task::spawn_blocking(move || {
+        let backend_checks: Vec<_> = backends
+            .iter()
+            .flat_map(|backend| backend.readiness(min_free_bytes))
+            .collect();
+        let store_space =
+            store_root.map(|root_dir| check_free_space("store_space", &root_dir, min_free_bytes));
+        (backend_checks, store_space)
+    });
+
+    let store_writable = match app_state.file_store.check_writable().await {
+        Ok(()) => ReadinessCheck::pass("store_writable", "writable"),
+        Err(e) => ReadinessCheck::fail("store_writable", e.to_string()),
+    };
+    let (mut checks, store_space) = filesystem_checks
+        .await
+        .unwrap_or_else(|e| (vec![ReadinessCheck::fail("readiness", e.to_string())], None));
+    checks.push(store_writable);
+    checks.extend(store_space);
+
+    let (failures, total) = app_state.metrics.recent_setup_failures();
+    let detail = format!(
+        "{} of {} executions in the last {}s failed during setup",
+        failures,
+        total,
+        RECENT_WINDOW.as_secs()
+    );
+    let rate = failures as f64 / total.max(1) as f64;
+    checks.push(
+        if total >= MIN_RECENT_EXECUTIONS && rate > execution.ready_max_setup_failure_rate {
+            ReadinessCheck::fail("setup_failure_rate", detail)
+        } else {
+            ReadinessCheck::pass("setup_failure_rate", detail)
+        },
+    );
+
+    let ready = checks.iter().all(|check| check.ready);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(ReadyResponse { ready, checks }))
+}
@@ -1,11 +1,18 @@
 mod cache;
+mod execution;
 pub mod handlers;
+mod metrics;
 mod middleware;
+mod output;
+mod request;
 mod router;
 mod serve;
 mod state;
 
-pub use cache::ExecutionCache;
+pub use cache::{CacheStats, ExecutionCache};
+pub use execution::ExecutionConfig;
+pub use metrics::{HistogramSnapshot, InFlight, Metrics};
+pub use request::ExecuteRequest;
 pub use router::build_router;
 pub use serve::{ServeConfig, serve};
 pub use state::AppState;
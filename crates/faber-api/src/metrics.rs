@@ -0,0 +1,475 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Write,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use faber_runtime::{
+    ErrorInfo, ExecutionStepResult, GroupTimings, PoolStats, SandboxProfile, TaskGroupResult,
+    TaskResult,
+};
+use faber_store::StoreStats;
+use serde::Serialize;
+
+use crate::cache::CacheStats;
+
+/// Upper bounds of the phase duration buckets, in microseconds. Durations
+/// above the last bound are only counted in the total.
+pub const PHASE_BUCKETS_USEC: [u64; 15] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 5_000_000, 30_000_000,
+];
+
+/// Phases of a task group, from [`GroupTimings`].
+const GROUP_PHASES: [&str; 4] = ["container_setup", "steps", "container_cleanup", "execution"];
+
+/// Phases of a single task, from [`faber_runtime::TaskTimings`].
+const TASK_PHASES: [&str; 5] = ["cgroup_setup", "file_setup", "spawn", "run", "task_cleanup"];
+
+/// How far back the recent setup failure rate looks.
+pub const RECENT_WINDOW: Duration = Duration::from_secs(300);
+
+/// Most executions remembered for the recent setup failure rate.
+const RECENT_CAPACITY: usize = 1024;
+
+/// A cumulative histogram of durations over [`PHASE_BUCKETS_USEC`].
+#[derive(Debug, Default)]
+pub struct Histogram {
+    buckets: [AtomicU64; PHASE_BUCKETS_USEC.len()],
+    count: AtomicU64,
+    sum_usec: AtomicU64,
+}
+
+/// A point-in-time copy of a [`Histogram`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    /// Each bucket's upper bound in microseconds with the number of
+    /// observations at or below it.
+    pub buckets: Vec<(u64, u64)>,
+    pub count: u64,
+    pub sum_usec: u64,
+}
+
+impl Histogram {
+    pub fn observe(&self, usec: u64) {
+        for (bound, bucket) in PHASE_BUCKETS_USEC.iter().zip(&self.buckets) {
+            if usec <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_usec.fetch_add(usec, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        HistogramSnapshot {
+            buckets: PHASE_BUCKETS_USEC
+                .iter()
+                .zip(&self.buckets)
+                .map(|(bound, bucket)| (*bound, bucket.load(Ordering::Relaxed)))
+                .collect(),
+            count: self.count.load(Ordering::Relaxed),
+            sum_usec: self.sum_usec.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Execution counters and latency histograms, recorded for every task group
+/// the API runs and rendered in the Prometheus text format.
+#[derive(Debug)]
+pub struct Metrics {
+    phases: Vec<(&'static str, Histogram)>,
+    queue_wait: Histogram,
+    execution_duration: Histogram,
+    /// Tasks by outcome and sandbox profile.
+    tasks: Mutex<BTreeMap<(String, String), u64>>,
+    /// Task groups whose container could not be set up, by error code.
+    setup_failures: Mutex<BTreeMap<String, u64>>,
+    task_cleanup_failures: AtomicU64,
+    in_flight: AtomicU64,
+    /// When recent executions finished and whether their setup failed,
+    /// oldest first.
+    recent: Mutex<VecDeque<(Instant, bool)>>,
+}
+
+/// Counts an execution as in flight until dropped.
+pub struct InFlight<'a>(&'a AtomicU64);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            phases: GROUP_PHASES
+                .into_iter()
+                .chain(TASK_PHASES)
+                .map(|phase| (phase, Histogram::default()))
+                .collect(),
+            queue_wait: Histogram::default(),
+            execution_duration: Histogram::default(),
+            tasks: Mutex::default(),
+            setup_failures: Mutex::default(),
+            task_cleanup_failures: AtomicU64::new(0),
+            in_flight: AtomicU64::new(0),
+            recent: Mutex::default(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Records the phases of a finished task group and of each of its tasks
+    /// that ran. Tasks that failed before running have no timings.
+    pub fn observe_execution(&self, results: &TaskGroupResult, timings: &GroupTimings) {
+        self.observe_recent(false);
+        self.queue_wait.observe(timings.queue_usec);
+        self.observe_phases(
+            GROUP_PHASES,
+            [
+                timings.container_setup_usec,
+                timings.steps_usec,
+                timings.cleanup_usec,
+                timings.total_usec,
+            ],
+        );
+        for step in results {
+            let task_results = match step {
+                ExecutionStepResult::Single(task_result) => std::slice::from_ref(task_result),
+                ExecutionStepResult::Parallel(task_results) => task_results.as_slice(),
+            };
+            for task_result in task_results {
+                let TaskResult::Completed { stats, .. } = task_result else {
+                    continue;
+                };
+                let timings = stats.timings;
+                self.observe_phases(
+                    TASK_PHASES,
+                    [
+                        timings.cgroup_setup_usec,
+                        timings.file_setup_usec,
+                        timings.spawn_usec,
+                        timings.run_usec,
+                        timings.cleanup_usec,
+                    ],
+                );
+            }
+        }
+    }
+
+    /// Counts each task by its outcome and the sandbox profile it ran under.
+    /// `profiles` holds the profile of every task in submission order.
+    pub fn observe_tasks(&self, profiles: &[SandboxProfile], results: &TaskGroupResult) {
+        let task_results = results.iter().flat_map(|step| match step {
+            ExecutionStepResult::Single(task_result) => std::slice::from_ref(task_result),
+            ExecutionStepResult::Parallel(task_results) => task_results.as_slice(),
+        });
+        let mut tasks = self.tasks.lock().unwrap();
+        for (task_result, profile) in task_results.zip(profiles) {
+            let stats = task_result.stats();
+            // Tasks that failed before running had nothing to clean up.
+            if matches!(task_result, TaskResult::Completed { .. }) && !stats.cleanup_succeeded {
+                self.task_cleanup_failures.fetch_add(1, Ordering::Relaxed);
+            }
+            *tasks
+                .entry((label(&stats.outcome), label(profile)))
+                .or_default() += 1;
+        }
+    }
+
+    pub fn observe_setup_failure(&self, info: &ErrorInfo) {
+        *self
+            .setup_failures
+            .lock()
+            .unwrap()
+            .entry(label(&info.code))
+            .or_default() += 1;
+        // Rejected submissions say nothing about the host.
+        if !info.code.is_client_error() {
+            self.observe_recent(true);
+        }
+    }
+
+    fn observe_recent(&self, setup_failed: bool) {
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back((Instant::now(), setup_failed));
+    }
+
+    /// Executions that finished within [`RECENT_WINDOW`], and how many of
+    /// them failed while setting up their container.
+    pub fn recent_setup_failures(&self) -> (u64, u64) {
+        let mut recent = self.recent.lock().unwrap();
+        while recent
+            .front()
+            .is_some_and(|(finished, _)| finished.elapsed() > RECENT_WINDOW)
+        {
+            recent.pop_front();
+        }
+        let failures = recent.iter().filter(|(_, failed)| *failed).count();
+        (failures as u64, recent.len() as u64)
+    }
+
+    /// Records how long the backend took to run a task group, including any
+    /// time spent queued.
+    pub fn observe_duration(&self, usec: u64) {
+        self.execution_duration.observe(usec);
+    }
+
+    pub fn in_flight(&self) -> InFlight<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlight(&self.in_flight)
+    }
+
+    fn observe_phases<const N: usize>(&self, phases: [&str; N], durations: [u64; N]) {
+        for (phase, usec) in phases.into_iter().zip(durations) {
+            if let Some((_, histogram)) = self.phases.iter().find(|(name, _)| *name == phase) {
+                histogram.observe(usec);
+            }
+        }
+    }
+
+    /// The duration histogram of every phase, group phases first.
+    pub fn phase_durations(&self) -> impl Iterator<Item = (&'static str, HistogramSnapshot)> {
+        self.phases
+            .iter()
+            .map(|(phase, histogram)| (*phase, histogram.snapshot()))
+    }
+
+    /// Renders every metric in the Prometheus text exposition format, along
+    /// with the cache, store, pool and runtime figures kept elsewhere. The
+    /// pool counters are left out when no pool is configured.
+    pub fn render(
+        &self,
+        cache: CacheStats,
+        stores: &[StoreStats],
+        pool: Option<PoolStats>,
+        container_cleanup_failures: u64,
+    ) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "faber_task_executions_total",
+            "counter",
+            "Tasks run, by outcome and sandbox profile.",
+        );
+        for ((outcome, profile), count) in self.tasks.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "faber_task_executions_total{{outcome=\"{outcome}\",profile=\"{profile}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out,
+            "faber_container_setup_failures_total",
+            "counter",
+            "Task groups whose container could not be set up, by error code.",
+        );
+        for (code, count) in self.setup_failures.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "faber_container_setup_failures_total{{code=\"{code}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out,
+            "faber_cleanup_failures_total",
+            "counter",
+            "Task cgroups and containers that could not be torn down.",
+        );
+        let task_cleanup_failures = self.task_cleanup_failures.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "faber_cleanup_failures_total{{scope=\"task\"}} {task_cleanup_failures}"
+        );
+        let _ = writeln!(
+            out,
+            "faber_cleanup_failures_total{{scope=\"container\"}} {container_cleanup_failures}"
+        );
+
+        header(
+            &mut out,
+            "faber_executions_in_flight",
+            "gauge",
+            "Task groups currently queued or running.",
+        );
+        let _ = writeln!(
+            out,
+            "faber_executions_in_flight {}",
+            self.in_flight.load(Ordering::Relaxed)
+        );
+
+        header(
+            &mut out,
+            "faber_queue_wait_seconds",
+            "histogram",
+            "Time task groups waited for CPUs before starting.",
+        );
+        histogram(
+            &mut out,
+            "faber_queue_wait_seconds",
+            "",
+            &self.queue_wait.snapshot(),
+        );
+
+        header(
+            &mut out,
+            "faber_execution_duration_seconds",
+            "histogram",
+            "Time the backend took to run a task group, including queueing.",
+        );
+        histogram(
+            &mut out,
+            "faber_execution_duration_seconds",
+            "",
+            &self.execution_duration.snapshot(),
+        );
+
+        header(
+            &mut out,
+            "faber_execution_phase_duration_seconds",
+            "histogram",
+            "Time spent in each phase of a task group or task.",
+        );
+        for (phase, snapshot) in self.phase_durations() {
+            histogram(
+                &mut out,
+                "faber_execution_phase_duration_seconds",
+                &format!("phase=\"{phase}\""),
+                &snapshot,
+            );
+        }
+
+        header(
+            &mut out,
+            "faber_cache_hits_total",
+            "counter",
+            "Executions answered from the result cache.",
+        );
+        let _ = writeln!(out, "faber_cache_hits_total {}", cache.hits);
+        header(
+            &mut out,
+            "faber_cache_misses_total",
+            "counter",
+            "Executions not found in the result cache.",
+        );
+        let _ = writeln!(out, "faber_cache_misses_total {}", cache.misses);
+        header(
+            &mut out,
+            "faber_cache_entries",
+            "gauge",
+            "Results held by the result cache.",
+        );
+        let _ = writeln!(out, "faber_cache_entries {}", cache.entries);
+
+        header(
+            &mut out,
+            "faber_store_entries",
+            "gauge",
+            "Files held by the file store, per backend.",
+        );
+        for store in stores {
+            let _ = writeln!(
+                out,
+                "faber_store_entries{{backend=\"{}\"}} {}",
+                store.backend, store.entries
+            );
+        }
+        header(
+            &mut out,
+            "faber_store_bytes",
+            "gauge",
+            "Bytes held by the file store, per backend.",
+        );
+        for store in stores {
+            let _ = writeln!(
+                out,
+                "faber_store_bytes{{backend=\"{}\"}} {}",
+                store.backend, store.bytes
+            );
+        }
+
+        if let Some(pool) = pool {
+            header(
+                &mut out,
+                "faber_pool_hits_total",
+                "counter",
+                "Executions that claimed a pre-warmed container.",
+            );
+            let _ = writeln!(out, "faber_pool_hits_total {}", pool.hits);
+            header(
+                &mut out,
+                "faber_pool_misses_total",
+                "counter",
+                "Executions that found no pre-warmed container.",
+            );
+            let _ = writeln!(out, "faber_pool_misses_total {}", pool.misses);
+            header(
+                &mut out,
+                "faber_pool_spawn_failures_total",
+                "counter",
+                "Pre-warmed containers that failed during setup.",
+            );
+            let _ = writeln!(
+                out,
+                "faber_pool_spawn_failures_total {}",
+                pool.spawn_failures
+            );
+        }
+
+        out
+    }
+}
+
+/// The serialized name of a unit enum variant, used as a label value.
+fn label(value: &impl Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Writes a histogram's cumulative buckets, sum and count, converting
+/// microseconds to seconds. `labels` are prepended to each bucket's `le`.
+fn histogram(out: &mut String, name: &str, labels: &str, snapshot: &HistogramSnapshot) {
+    let separator = if labels.is_empty() { "" } else { "," };
+    for (bound, count) in &snapshot.buckets {
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels}{separator}le=\"{}\"}} {count}",
+            seconds(*bound)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {}",
+        snapshot.count
+    );
+    let labels = if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{labels}}}")
+    };
+    let _ = writeln!(out, "{name}_sum{labels} {}", seconds(snapshot.sum_usec));
+    let _ = writeln!(out, "{name}_count{labels} {}", snapshot.count);
+}
+
+fn seconds(usec: u64) -> f64 {
+    usec as f64 / 1_000_000.0
+}
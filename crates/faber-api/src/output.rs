@@ -0,0 +1,87 @@
+use bytes::Bytes;
+use faber_runtime::{
+    ExecutionStepResult, SpilledOutput, StoredOutput, TaskGroupResult, TaskResult,
+};
+use faber_store::{FileMetadata, FileStore};
+
+/// Moves stdout and stderr the runtime spilled to files into `store`,
+/// leaving a [`StoredOutput`] in the task's stats. Output the store rejects
+/// is returned inline instead. Returns whether any output was stored.
+pub(crate) async fn store_spilled_outputs(
+    results: &mut TaskGroupResult,
+    store: &dyn FileStore,
+) -> bool {
+    let mut stored_any = false;
+    for step in results.iter_mut() {
+        let task_results = match step {
+            ExecutionStepResult::Single(task_result) => std::slice::from_mut(task_result),
+            ExecutionStepResult::Parallel(task_results) => task_results.as_mut_slice(),
+        };
+        for task_result in task_results {
+            let TaskResult::Completed {
+                stdout,
+                stderr,
+                stats,
+                ..
+            } = task_result
+            else {
+                continue;
+            };
+            for (output, spill, file, name) in [
+                (
+                    stdout,
+                    stats.stdout_spill.take(),
+                    &mut stats.stdout_file,
+                    "stdout",
+                ),
+                (
+                    stderr,
+                    stats.stderr_spill.take(),
+                    &mut stats.stderr_file,
+                    "stderr",
+                ),
+            ] {
+                if let Some(spill) = spill {
+                    *file = store_output(store, spill, output, name).await;
+                    stored_any |= file.is_some();
+                }
+            }
+        }
+    }
+    stored_any
+}
+
+async fn store_output(
+    store: &dyn FileStore,
+    spill: SpilledOutput,
+    output: &mut String,
+    name: &str,
+) -> Option<StoredOutput> {
+    let content = tokio::fs::read(&spill.path).await;
+    if let Err(e) = tokio::fs::remove_file(&spill.path).await {
+        eprintln!("Failed to remove spilled task {}: {}", name, e);
+    }
+    let content = match content {
+        Ok(content) => Bytes::from(content),
+        Err(e) => {
+            eprintln!("Failed to read spilled task {}: {}", name, e);
+            return None;
+        }
+    };
+
+    let metadata = FileMetadata::new(spill.size)
+        .with_filename(name)
+        .with_content_type("application/octet-stream");
+    match store.put(content.clone(), metadata).await {
+        Ok(upload) => Some(StoredOutput {
+            file_id: upload.file_id.0,
+            size: spill.size,
+            digest: spill.digest,
+        }),
+        Err(e) => {
+            eprintln!("Failed to store task {}: {}", name, e);
+            *output = String::from_utf8_lossy(&content).into_owned();
+            None
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use faber_runtime::{ExecutionStep, SandboxProfile, Task, TaskGroup, TerminationPolicy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Body of `POST /execute`.
+///
+/// Accepts either a bare task group array or an object carrying the task group
+/// together with per-request execution options.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecuteRequest {
+    pub tasks: TaskGroup,
+    pub environment: Option<String>,
+    pub workspace: Option<String>,
+    pub mounts: Vec<String>,
+    pub backend: Option<String>,
+    /// Whether to return the task group's phase timings in the response
+    /// body. Not part of the cache key.
+    #[serde(skip_serializing)]
+    pub timings: bool,
+}
+
+impl ExecuteRequest {
+    fn all_tasks(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.iter().flat_map(|step| match step {
+            ExecutionStep::Single(task) => std::slice::from_ref(task),
+            ExecutionStep::Parallel(tasks) => tasks.as_slice(),
+        })
+    }
+
+    pub(crate) fn all_tasks_mut(&mut self) -> impl Iterator<Item = &mut Task> {
+        self.tasks.iter_mut().flat_map(|step| match step {
+            ExecutionStep::Single(task) => std::slice::from_mut(task),
+            ExecutionStep::Parallel(tasks) => tasks.as_mut_slice(),
+        })
+    }
+
+    /// The largest output limit any task asks for with `capture.limit_bytes`.
+    pub(crate) fn requested_output_limit(&self) -> Option<usize> {
+        self.all_tasks()
+            .filter_map(|task| task.capture?.limit_bytes)
+            .max()
+    }
+
+    /// The termination policy of every task that sets one.
+    pub(crate) fn termination_policies(&self) -> impl Iterator<Item = &TerminationPolicy> {
+        self.all_tasks()
+            .filter_map(|task| task.termination.as_ref())
+    }
+
+    /// The sandbox profile of every task, in the order results are returned.
+    pub(crate) fn profiles(&self) -> Vec<SandboxProfile> {
+        self.all_tasks()
+            .map(|task| task.sandbox_profile.unwrap_or_default())
+            .collect()
+    }
+
+    /// The stored files tasks read as stdin.
+    pub(crate) fn stdin_file_ids(&self) -> BTreeSet<String> {
+        self.all_tasks()
+            .filter_map(|task| task.stdin_file_id.clone())
+            .collect()
+    }
+}
+
+impl From<TaskGroup> for ExecuteRequest {
+    fn from(tasks: TaskGroup) -> Self {
+        Self {
+            tasks,
+            environment: None,
+            workspace: None,
+            mounts: Vec::new(),
+            backend: None,
+            timings: false,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ExecuteRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct ExecuteRequestObject {
+            tasks: TaskGroup,
+            #[serde(default)]
+            environment: Option<String>,
+            #[serde(default)]
+            workspace: Option<String>,
+            #[serde(default)]
+            mounts: Vec<String>,
+            #[serde(default)]
+            backend: Option<String>,
+            #[serde(default)]
+            timings: bool,
+        }
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+
+        match value {
+            serde_json::Value::Array(_) => {
+                let tasks = TaskGroup::deserialize(value).map_err(Error::custom)?;
+                Ok(ExecuteRequest::from(tasks))
+            }
+            serde_json::Value::Object(_) => {
+                let request = ExecuteRequestObject::deserialize(value).map_err(Error::custom)?;
+                Ok(ExecuteRequest {
+                    tasks: request.tasks,
+                    environment: request.environment,
+                    workspace: request.workspace,
+                    mounts: request.mounts,
+                    backend: request.backend,
+                    timings: request.timings,
+                })
+            }
+            _ => Err(Error::custom(
+                "Execute request must be either a task group array or an object with `tasks`",
+            )),
+        }
+    }
+}
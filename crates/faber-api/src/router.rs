@@ -2,17 +2,23 @@ use axum::{Router, middleware, routing::get, routing::post};
 use faber_store::FileStore;
 use std::sync::Arc;
 
-use crate::{handlers, middleware::api_key_middleware, state::AppState};
+use crate::{
+    execution::ExecutionConfig, handlers, middleware::api_key_middleware, state::AppState,
+};
 
 pub fn build_router(
     api_key: String,
     cache_enabled: bool,
     file_store: Arc<dyn FileStore>,
+    execution_config: ExecutionConfig,
 ) -> Router {
-    let state = AppState::new(api_key, cache_enabled, file_store);
+    let state =
+        AppState::new(api_key, cache_enabled, file_store).with_execution_config(execution_config);
 
     let public_routes = Router::new()
         .route("/health", get(handlers::health))
+        .route("/metrics", get(handlers::metrics))
+        .route("/ready", get(handlers::ready))
         .with_state(state.clone());
 
     let protected_routes = Router::new()
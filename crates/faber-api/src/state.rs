@@ -1,4 +1,4 @@
-use crate::cache::ExecutionCache;
+use crate::{cache::ExecutionCache, execution::ExecutionConfig, metrics::Metrics};
 use faber_store::FileStore;
 use std::sync::Arc;
 
@@ -6,6 +6,8 @@ use std::sync::Arc;
 pub struct AppState {
     pub cache: ExecutionCache,
     pub file_store: Arc<dyn FileStore>,
+    pub execution: Arc<ExecutionConfig>,
+    pub metrics: Arc<Metrics>,
     pub api_key: String,
     pub cache_enabled: bool,
 }
@@ -15,8 +17,15 @@ impl AppState {
         Self {
             cache: ExecutionCache::new(),
             file_store,
+            execution: Arc::new(ExecutionConfig::default()),
+            metrics: Arc::new(Metrics::default()),
             api_key,
             cache_enabled,
         }
     }
+
+    pub fn with_execution_config(mut self, execution: ExecutionConfig) -> Self {
+        self.execution = Arc::new(execution);
+        self
+    }
 }
@@ -1,5 +1,5 @@
 use axum::{Json, extract::State};
-use faber_api::{AppState, handlers::execute};
+use faber_api::{AppState, ExecuteRequest, handlers::execute};
 use faber_runtime::{ExecutionStep, Task};
 use faber_store::{StoreConfig, create_store};
 use std::{path::PathBuf, time::Duration};
@@ -47,11 +47,14 @@ async fn aborting_an_api_request_still_cleans_the_detached_runtime() {
         files: None,
         working_dir: None,
         sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
     };
 
     let request = tokio::spawn(execute(
         State(state),
-        Json(vec![ExecutionStep::Single(task)]),
+        Json(ExecuteRequest::from(vec![ExecutionStep::Single(task)])),
     ));
 
     let mut observed_execution = false;
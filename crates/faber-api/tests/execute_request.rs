@@ -0,0 +1,93 @@
+use faber_api::{ExecuteRequest, ExecutionCache};
+use faber_runtime::ExecutionStep;
+
+#[test]
+fn bare_task_group_arrays_are_still_accepted() {
+    let request: ExecuteRequest =
+        serde_json::from_str(r#"[{"cmd": "/bin/echo", "args": ["hi"]}]"#).unwrap();
+
+    assert_eq!(request.tasks.len(), 1);
+    assert!(matches!(request.tasks[0], ExecutionStep::Single(_)));
+    assert_eq!(request.environment, None);
+}
+
+#[test]
+fn object_requests_select_an_environment() {
+    let request: ExecuteRequest = serde_json::from_str(
+        r#"{"environment": "gcc-13", "tasks": [[{"cmd": "/bin/true"}, {"cmd": "/bin/false"}]]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(request.environment.as_deref(), Some("gcc-13"));
+    assert!(matches!(&request.tasks[0], ExecutionStep::Parallel(tasks) if tasks.len() == 2));
+}
+
+#[test]
+fn object_requests_reject_unknown_fields() {
+    let result = serde_json::from_str::<ExecuteRequest>(
+        r#"{"enviroment": "gcc-13", "tasks": [{"cmd": "/bin/true"}]}"#,
+    );
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn cache_keys_depend_on_the_environment() {
+    let tasks: ExecuteRequest = serde_json::from_str(r#"[{"cmd": "/bin/true"}]"#).unwrap();
+    let pinned = ExecuteRequest {
+        environment: Some("gcc-13".to_string()),
+        ..tasks.clone()
+    };
+
+    assert_ne!(
+        ExecutionCache::generate_hash(&tasks),
+        ExecutionCache::generate_hash(&pinned)
+    );
+}
+
+#[test]
+fn asking_for_timings_keeps_the_cache_key() {
+    let tasks: ExecuteRequest = serde_json::from_str(r#"[{"cmd": "/bin/true"}]"#).unwrap();
+    let timed: ExecuteRequest =
+        serde_json::from_str(r#"{"timings": true, "tasks": [{"cmd": "/bin/true"}]}"#).unwrap();
+
+    assert!(timed.timings);
+    assert_eq!(
+        ExecutionCache::generate_hash(&tasks),
+        ExecutionCache::generate_hash(&timed)
+    );
+}
+
+#[test]
+fn object_requests_select_a_workspace() {
+    let request: ExecuteRequest = serde_json::from_str(
+        r#"{"workspace": "starter", "tasks": [{"cmd": "/bin/cat", "args": ["main.c"]}]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(request.workspace.as_deref(), Some("starter"));
+    assert_eq!(request.environment, None);
+}
+
+#[test]
+fn object_requests_select_mounts_by_name() {
+    let request: ExecuteRequest = serde_json::from_str(
+        r#"{"mounts": ["toolchains", "scratch"], "tasks": [{"cmd": "/bin/true"}]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(request.mounts, ["toolchains", "scratch"]);
+    assert!(ExecuteRequest::from(request.tasks).mounts.is_empty());
+}
+
+#[test]
+fn object_requests_select_a_backend_and_key_the_cache_by_it() {
+    let request: ExecuteRequest =
+        serde_json::from_str(r#"{"backend": "gvisor", "tasks": [{"cmd": "/bin/true"}]}"#).unwrap();
+
+    assert_eq!(request.backend.as_deref(), Some("gvisor"));
+    assert_ne!(
+        ExecutionCache::generate_hash(&request),
+        ExecutionCache::generate_hash(&ExecuteRequest::from(request.tasks.clone()))
+    );
+}
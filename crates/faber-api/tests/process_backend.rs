@@ -0,0 +1,682 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use bytes::Bytes;
+use faber_api::{
+    AppState, CacheStats, ExecuteRequest, ExecutionCache, ExecutionConfig, Metrics,
+    handlers::{ExecuteErrorResponse, ExecuteResponse, ReadyResponse, execute, metrics, ready},
+};
+use faber_runtime::{
+    ErrorCode, ErrorInfo, ErrorStage, ExecutionStepResult, MountSpec, OutputStream, PoolStats,
+    ProcessBackend, Task, TaskGroupResult, TaskOutcome, TaskResult,
+};
+use faber_store::{FileId, FileMetadata, StoreConfig, create_store};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+fn state_with(backend: ProcessBackend, cache_enabled: bool) -> AppState {
+    let mut execution = ExecutionConfig {
+        backend: Arc::new(backend),
+        ..Default::default()
+    };
+    execution.mounts.insert(
+        "scratch".to_string(),
+        MountSpec::tmpfs("/scratch", "8M").unwrap(),
+    );
+    AppState::new(
+        "test-key".to_string(),
+        cache_enabled,
+        create_store(StoreConfig::default()),
+    )
+    .with_execution_config(execution)
+}
+
+fn shell_task(script: &str) -> Task {
+    Task {
+        cmd: "/bin/sh".to_string(),
+        args: Some(vec!["-c".to_string(), script.to_string()]),
+        env: None,
+        stdin: None,
+        files: None,
+        working_dir: None,
+        sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
+    }
+}
+
+async fn run_with_envelope(
+    state: &AppState,
+    request: &str,
+) -> Result<TaskGroupResult, (StatusCode, ExecuteErrorResponse)> {
+    let request: ExecuteRequest = serde_json::from_str(request).unwrap();
+    execute(State(state.clone()), Json(request))
+        .await
+        .map(|(_, Json(response))| response.into_results())
+        .map_err(|(status, Json(envelope))| (status, envelope))
+}
+
+async fn run(state: &AppState, request: &str) -> Result<TaskGroupResult, StatusCode> {
+    run_with_envelope(state, request)
+        .await
+        .map_err(|(status, _)| status)
+}
+
+fn completed(step: &ExecutionStepResult) -> (&str, i32, &TaskOutcome) {
+    match step {
+        ExecutionStepResult::Single(TaskResult::Completed {
+            stdout,
+            exit_code,
+            stats,
+            ..
+        }) => (stdout, *exit_code, &stats.outcome),
+        other => panic!("Expected a completed single task, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn steps_share_a_workspace_and_see_files_stdin_and_env() {
+    let state = state_with(ProcessBackend::default(), false);
+    let mut write = shell_task("cat > saved.txt; cat input.txt; echo \" $GREETING\"");
+    write.stdin = Some("from stdin".to_string());
+    write.files = Some(HashMap::from([(
+        "input.txt".to_string(),
+        "from a file".to_string(),
+    )]));
+    write.env = Some(HashMap::from([(
+        "GREETING".to_string(),
+        "hello".to_string(),
+    )]));
+    let request = serde_json::to_string(&vec![
+        faber_runtime::ExecutionStep::Single(write),
+        faber_runtime::ExecutionStep::Single(shell_task("cat saved.txt")),
+    ])
+    .unwrap();
+
+    let result = run(&state, &request).await.unwrap();
+
+    assert_eq!(
+        completed(&result[0]),
+        ("from a file hello\n", 0, &TaskOutcome::Exited)
+    );
+    assert_eq!(
+        completed(&result[1]),
+        ("from stdin", 0, &TaskOutcome::Exited)
+    );
+}
+
+#[tokio::test]
+async fn task_files_are_not_written_through_planted_symlinks() {
+    let state = state_with(ProcessBackend::default(), false);
+    let outside = std::env::temp_dir().join(format!("faber-planted-{}", std::process::id()));
+    let _ = std::fs::remove_file(&outside);
+
+    let mut overwrite = shell_task("cat main.c");
+    overwrite.files = Some(HashMap::from([(
+        "main.c".to_string(),
+        "int main;".to_string(),
+    )]));
+    let request = serde_json::to_string(&vec![
+        faber_runtime::ExecutionStep::Single(shell_task(&format!(
+            "ln -s {} main.c",
+            outside.display()
+        ))),
+        faber_runtime::ExecutionStep::Single(overwrite),
+    ])
+    .unwrap();
+
+    let result = run(&state, &request).await.unwrap();
+
+    assert_eq!(completed(&result[0]).1, 0);
+    assert!(
+        matches!(
+            &result[1],
+            ExecutionStepResult::Single(TaskResult::Failed { .. })
+        ),
+        "{:?}",
+        result[1]
+    );
+    assert!(!outside.exists());
+}
+
+#[tokio::test]
+async fn parallel_results_keep_request_order() {
+    let state = state_with(ProcessBackend::default(), false);
+
+    let result = run(
+        &state,
+        r#"[[{"cmd": "/bin/sh", "args": ["-c", "sleep 0.2; echo slow"]},
+             {"cmd": "/bin/echo", "args": ["fast"]}]]"#,
+    )
+    .await
+    .unwrap();
+
+    let ExecutionStepResult::Parallel(tasks) = &result[0] else {
+        panic!("Expected a parallel step, got {:?}", result[0]);
+    };
+    let stdout: Vec<_> = tasks
+        .iter()
+        .map(|task| match task {
+            TaskResult::Completed { stdout, .. } => stdout.as_str(),
+            other => panic!("Expected a completed task, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(stdout, ["slow\n", "fast\n"]);
+}
+
+#[tokio::test]
+async fn timeouts_and_output_limits_kill_the_task() {
+    let state = state_with(
+        ProcessBackend::default()
+            .with_timeout(Duration::from_millis(200))
+            .with_output_limit(16),
+        false,
+    );
+
+    let started = std::time::Instant::now();
+    let result = run(
+        &state,
+        r#"[{"cmd": "/bin/sh", "args": ["-c", "sleep 30 & wait"]},
+            {"cmd": "/bin/sh", "args": ["-c", "while :; do echo spam; done"]}]"#,
+    )
+    .await
+    .unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(completed(&result[0]).2, &TaskOutcome::TimedOut);
+    let (stdout, _, outcome) = completed(&result[1]);
+    assert_eq!(stdout.len(), 16);
+    assert_eq!(outcome, &TaskOutcome::OutputLimit);
+}
+
+#[tokio::test]
+async fn head_and_tail_capture_keeps_the_task_running_and_merges_streams() {
+    let state = state_with(ProcessBackend::default().with_output_limit(16), false);
+
+    let result = run(
+        &state,
+        r#"[{"cmd": "/bin/sh",
+             "args": ["-c", "echo start >&2; seq 1000; echo done >&2"],
+             "capture": {"retain": "head_and_tail", "keep_running": true, "merged": true}}]"#,
+    )
+    .await
+    .unwrap();
+
+    let ExecutionStepResult::Single(TaskResult::Completed {
+        stdout,
+        stderr,
+        exit_code,
+        stats,
+    }) = &result[0]
+    else {
+        panic!("Expected a completed single task, got {:?}", result[0]);
+    };
+    assert_eq!(*exit_code, 0);
+    assert_eq!(stats.outcome, TaskOutcome::Exited);
+    assert!(stdout.starts_with("1\n2\n3\n4\n"), "{stdout}");
+    assert!(
+        stdout.ends_with("\n[... 3877 bytes elided ...]\n99\n1000\n"),
+        "{stdout}"
+    );
+    assert_eq!(stderr, "start\ndone\n");
+    assert!(stats.stdout_truncated);
+    assert!(!stats.stderr_truncated);
+    assert_eq!(stats.stdout_elided_bytes, 3893 - 16);
+
+    let merged = stats.merged_output.as_ref().unwrap();
+    assert!(merged.elided_bytes > 0);
+    let first = merged.chunks.first().unwrap();
+    let last = merged.chunks.last().unwrap();
+    assert_eq!(first.stream, OutputStream::Stderr);
+    assert_eq!(first.data, "start\n");
+    assert_eq!(last.stream, OutputStream::Stderr);
+    assert_eq!(last.data, "done\n");
+    assert!(first.elapsed_ms <= last.elapsed_ms);
+}
+
+#[tokio::test]
+async fn head_and_tail_capture_runs_to_completion_without_keep_running() {
+    let state = state_with(ProcessBackend::default().with_output_limit(16), false);
+
+    let result = run(
+        &state,
+        r#"[{"cmd": "/bin/sh",
+             "args": ["-c", "seq 1000"],
+             "capture": {"retain": "head_and_tail"}}]"#,
+    )
+    .await
+    .unwrap();
+
+    let (stdout, exit_code, outcome) = completed(&result[0]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(outcome, &TaskOutcome::Exited);
+    assert!(stdout.ends_with("\n99\n1000\n"), "{stdout}");
+}
+
+#[tokio::test]
+async fn large_outputs_are_moved_to_the_file_store() {
+    let spill_dir = tempfile::tempdir().unwrap();
+    let mut state = state_with(ProcessBackend::default().with_output_limit(16), true);
+    let mut execution = (*state.execution).clone();
+    execution.max_output_limit = 64 * 1024;
+    execution.inline_output_limit = 1024;
+    execution.output_spill_dir = spill_dir.path().to_path_buf();
+    state = state.with_execution_config(execution);
+    let request = r#"[{"cmd": "/bin/sh",
+                       "args": ["-c", "seq 10000; printf '\\377\\376'; echo small >&2"],
+                       "capture": {"limit_bytes": 65536}}]"#;
+
+    let result = run(&state, request).await.unwrap();
+
+    let ExecutionStepResult::Single(TaskResult::Completed {
+        stdout,
+        stderr,
+        stats,
+        ..
+    }) = &result[0]
+    else {
+        panic!("Expected a completed single task, got {:?}", result[0]);
+    };
+    assert_eq!(stats.outcome, TaskOutcome::Exited);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "small\n");
+    assert!(stats.stderr_file.is_none());
+    let stored = stats.stdout_file.as_ref().expect("stdout was not stored");
+    assert_eq!(stored.size, 48896);
+    assert_eq!(stored.digest, format!("sha256:{}", stored.file_id));
+
+    let file = state
+        .file_store
+        .get(&FileId(stored.file_id.clone()))
+        .await
+        .unwrap();
+    let mut expected: Vec<u8> = (1..=10000)
+        .flat_map(|n| format!("{n}\n").into_bytes())
+        .collect();
+    expected.extend_from_slice(b"\xff\xfe");
+    assert_eq!(file.content, expected);
+    assert_eq!(std::fs::read_dir(spill_dir.path()).unwrap().count(), 0);
+
+    // Stored outputs expire, so the result is not cached.
+    let hash = ExecutionCache::generate_hash(&serde_json::from_str(request).unwrap());
+    assert!(state.cache.try_from_hash(&hash).is_none());
+}
+
+async fn put_stdin(state: &AppState, content: &'static str) -> String {
+    let upload = state
+        .file_store
+        .put(
+            Bytes::from_static(content.as_bytes()),
+            FileMetadata::new(content.len() as u64),
+        )
+        .await
+        .unwrap();
+    upload.file_id.0
+}
+
+#[tokio::test]
+async fn stdin_is_read_from_stored_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = create_store(
+        StoreConfig::builder()
+            .filesystem(dir.path().to_string_lossy())
+            .build(),
+    );
+    let state = AppState::new("test-key".to_string(), false, store).with_execution_config(
+        ExecutionConfig {
+            backend: Arc::new(ProcessBackend::default()),
+            ..Default::default()
+        },
+    );
+    let file_id = put_stdin(&state, "line one\nline two\n").await;
+    let request = format!(r#"[{{"cmd": "/bin/cat", "stdin_file_id": "{file_id}"}}]"#);
+
+    let result = run(&state, &request).await.unwrap();
+
+    let (stdout, exit_code, outcome) = completed(&result[0]);
+    assert_eq!(stdout, "line one\nline two\n");
+    assert_eq!(exit_code, 0);
+    assert_eq!(*outcome, TaskOutcome::Exited);
+}
+
+#[tokio::test]
+async fn readiness_checks_the_store_and_recent_setup_failures() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = create_store(
+        StoreConfig::builder()
+            .filesystem(dir.path().to_string_lossy())
+            .build(),
+    );
+    let state = AppState::new("test-key".to_string(), false, store).with_execution_config(
+        ExecutionConfig {
+            backend: Arc::new(ProcessBackend::default()),
+            ready_min_free_bytes: 0,
+            ..Default::default()
+        },
+    );
+
+    let (status, Json(report)) = ready(State(state.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(report.ready);
+    let names: Vec<_> = report
+        .checks
+        .iter()
+        .map(|check| check.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        ["store_writable", "store_space", "setup_failure_rate"]
+    );
+
+    let info = ErrorInfo::new(ErrorCode::Cgroup, ErrorStage::ContainerSetup);
+    for _ in 0..5 {
+        state.metrics.observe_setup_failure(&info);
+    }
+    let (status, Json(report)) = ready(State(state)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(!report.ready);
+    let failed: Vec<_> = report
+        .checks
+        .iter()
+        .filter(|check| !check.ready)
+        .map(|check| check.name.as_str())
+        .collect();
+    assert_eq!(failed, ["setup_failure_rate"]);
+}
+
+#[tokio::test]
+async fn readiness_fails_without_free_space_for_the_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = create_store(
+        StoreConfig::builder()
+            .filesystem(dir.path().to_string_lossy())
+            .build(),
+    );
+    let state = AppState::new("test-key".to_string(), false, store).with_execution_config(
+        ExecutionConfig {
+            backend: Arc::new(ProcessBackend::default()),
+            ready_min_free_bytes: u64::MAX,
+            ..Default::default()
+        },
+    );
+
+    let (status, Json(report)): (_, Json<ReadyResponse>) = ready(State(state)).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let store_space = report
+        .checks
+        .iter()
+        .find(|check| check.name == "store_space")
+        .unwrap();
+    assert!(!store_space.ready, "{store_space:?}");
+}
+
+#[tokio::test]
+async fn memory_stores_pass_stdin_files_inline() {
+    let state = state_with(ProcessBackend::default(), false);
+    let file_id = put_stdin(&state, "from memory").await;
+    let request = format!(r#"[{{"cmd": "/bin/cat", "stdin_file_id": "{file_id}"}}]"#);
+
+    let result = run(&state, &request).await.unwrap();
+
+    assert_eq!(completed(&result[0]).0, "from memory");
+}
+
+#[tokio::test]
+async fn unknown_or_conflicting_stdin_files_are_rejected() {
+    let state = state_with(ProcessBackend::default(), false);
+    let missing = format!(
+        r#"[{{"cmd": "/bin/cat", "stdin_file_id": "{}"}}]"#,
+        "0".repeat(64)
+    );
+    assert_eq!(
+        run(&state, &missing).await.unwrap_err(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let file_id = put_stdin(&state, "stored").await;
+    let both =
+        format!(r#"[{{"cmd": "/bin/cat", "stdin": "inline", "stdin_file_id": "{file_id}"}}]"#);
+    let result = run(&state, &both).await.unwrap();
+    let ExecutionStepResult::Single(TaskResult::Failed { info, .. }) = &result[0] else {
+        panic!("Expected the task to fail, got {:?}", result[0]);
+    };
+    assert_eq!(info.code, ErrorCode::InvalidTask);
+    assert_eq!(info.stage, ErrorStage::Validation);
+    assert!(!info.retryable);
+}
+
+#[tokio::test]
+async fn output_limits_above_the_maximum_are_rejected() {
+    let state = state_with(ProcessBackend::default(), false);
+
+    let result = run(
+        &state,
+        r#"[{"cmd": "/bin/true", "capture": {"limit_bytes": 33554432}}]"#,
+    )
+    .await;
+
+    assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn unbounded_or_ineffective_termination_policies_are_rejected() {
+    let state = state_with(ProcessBackend::default(), false);
+
+    let long_grace = run(
+        &state,
+        r#"[{"cmd": "/bin/true", "termination": {"grace_period_ms": 3600000}}]"#,
+    )
+    .await;
+    let (status, envelope) = run_with_envelope(
+        &state,
+        r#"[{"cmd": "/bin/true", "termination": {"signal": 19}}]"#,
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(long_grace.unwrap_err(), StatusCode::BAD_REQUEST);
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(envelope.error.info.code, ErrorCode::InvalidTask);
+    run(
+        &state,
+        r#"[{"cmd": "/bin/true", "termination": {"signal": 2, "grace_period_ms": 500}}]"#,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn phase_timings_are_reported_and_recorded() {
+    let state = state_with(ProcessBackend::default(), false);
+    let request: ExecuteRequest = serde_json::from_str(
+        r#"{"timings": true, "tasks": [{"cmd": "/bin/sh", "args": ["-c", "sleep 0.05"], "files": {"a.txt": "a"}}]}"#,
+    )
+    .unwrap();
+
+    let (headers, Json(response)) = execute(State(state.clone()), Json(request)).await.unwrap();
+    let ExecuteResponse::WithTimings {
+        results: result,
+        timings,
+    } = response
+    else {
+        panic!("Expected the group timings in the body, got {response:?}");
+    };
+    assert!(timings.steps_usec >= 50_000);
+    assert!(timings.total_usec >= timings.steps_usec);
+
+    let ExecutionStepResult::Single(TaskResult::Completed { stats, .. }) = &result[0] else {
+        panic!("Expected a completed single task, got {:?}", result[0]);
+    };
+    assert!(stats.timings.file_setup_usec > 0);
+    assert!(stats.timings.spawn_usec > 0);
+    assert!(stats.timings.run_usec >= 50_000);
+
+    let server_timing = headers["server-timing"].to_str().unwrap();
+    for phase in ["container_setup", "steps", "container_cleanup", "execution"] {
+        assert!(
+            server_timing.contains(&format!("{phase};dur=")),
+            "{phase} missing from {server_timing}"
+        );
+    }
+
+    let phases: HashMap<_, _> = state.metrics.phase_durations().collect();
+    assert_eq!(phases["execution"].count, 1);
+    assert_eq!(phases["run"].count, 1);
+    assert!(phases["run"].sum_usec >= 50_000);
+    let (_, below_50ms) = phases["run"]
+        .buckets
+        .iter()
+        .find(|(bound, _)| *bound == 50_000)
+        .unwrap();
+    assert_eq!(*below_50ms, 0);
+}
+
+#[tokio::test]
+async fn cached_results_skip_the_backend() {
+    let state = state_with(ProcessBackend::default(), true);
+    let request = r#"[{"cmd": "/bin/sh", "args": ["-c", "echo $$"]}]"#;
+
+    let first = run(&state, request).await.unwrap();
+    let second = run(&state, request).await.unwrap();
+
+    assert_eq!(completed(&first[0]).0, completed(&second[0]).0);
+
+    let request: ExecuteRequest = serde_json::from_str(request).unwrap();
+    let (headers, _) = execute(State(state.clone()), Json(request)).await.unwrap();
+    let server_timing = headers["server-timing"].to_str().unwrap();
+    assert!(
+        server_timing.starts_with("cache;desc=hit;dur="),
+        "cache hit not reported in {server_timing}"
+    );
+}
+
+#[tokio::test]
+async fn metrics_are_exposed_in_prometheus_format() {
+    let state = state_with(ProcessBackend::default(), true);
+    let request =
+        r#"[{"cmd": "/bin/sh", "args": ["-c", "exit 3"], "sandbox_profile": "native_v1"}]"#;
+    run(&state, request).await.unwrap();
+    run(&state, request).await.unwrap();
+    state
+        .file_store
+        .put(Bytes::from("stored"), FileMetadata::new(6))
+        .await
+        .unwrap();
+
+    let response = metrics(State(state)).await.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+
+    for line in [
+        r#"faber_task_executions_total{outcome="exited",profile="native_v1"} 1"#,
+        r#"faber_cleanup_failures_total{scope="task"} 0"#,
+        "faber_executions_in_flight 0",
+        "faber_execution_duration_seconds_count 1",
+        r#"faber_queue_wait_seconds_bucket{le="+Inf"} 1"#,
+        r#"faber_execution_phase_duration_seconds_count{phase="run"} 1"#,
+        "faber_cache_hits_total 1",
+        "faber_cache_misses_total 1",
+        "faber_cache_entries 1",
+        r#"faber_store_entries{backend="memory"} 1"#,
+        r#"faber_store_bytes{backend="memory"} 6"#,
+    ] {
+        assert!(
+            body.lines().any(|metric| metric == line),
+            "{line} missing from:\n{body}"
+        );
+    }
+    assert!(!body.contains("faber_pool_"), "pool metrics without a pool");
+}
+
+#[test]
+fn pool_counters_are_rendered_when_a_pool_is_configured() {
+    let pool = PoolStats {
+        ready: 2,
+        hits: 5,
+        misses: 3,
+        spawned: 7,
+        spawn_failures: 1,
+    };
+    let body = Metrics::default().render(CacheStats::default(), &[], Some(pool), 0);
+
+    for line in [
+        "# TYPE faber_pool_hits_total counter",
+        "faber_pool_hits_total 5",
+        "faber_pool_misses_total 3",
+        "faber_pool_spawn_failures_total 1",
+    ] {
+        assert!(
+            body.lines().any(|metric| metric == line),
+            "{line} missing from:\n{body}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn container_options_are_rejected_instead_of_ignored() {
+    let state = state_with(ProcessBackend::default(), false);
+
+    let (status, envelope) = run_with_envelope(
+        &state,
+        r#"{"mounts": ["scratch"], "tasks": [{"cmd": "/bin/true"}]}"#,
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(envelope.error.info.code, ErrorCode::InvalidConfiguration);
+    assert!(envelope.error.message.contains("does not support"));
+}
+
+#[tokio::test]
+async fn rejections_are_reported_in_a_json_error_envelope() {
+    let state = state_with(ProcessBackend::default(), false);
+
+    let (status, envelope) = run_with_envelope(
+        &state,
+        r#"{"backend": "missing", "tasks": [{"cmd": "/bin/true"}]}"#,
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        serde_json::to_value(&envelope).unwrap(),
+        serde_json::json!({
+            "error": {
+                "code": "invalid_request",
+                "stage": "validation",
+                "retryable": false,
+                "message": "Unknown execution backend: missing",
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn requests_select_registered_backends_by_name() {
+    let mut state = state_with(ProcessBackend::default(), false);
+    let mut execution = (*state.execution).clone();
+    execution.backends.insert(
+        "process".to_string(),
+        Arc::new(ProcessBackend::default().with_output_limit(2)),
+    );
+    state = state.with_execution_config(execution);
+
+    let result = run(
+        &state,
+        r#"{"backend": "process", "tasks": [{"cmd": "/bin/echo", "args": ["hello"]}]}"#,
+    )
+    .await
+    .unwrap();
+    assert_eq!(completed(&result[0]).0, "he");
+
+    let result = run(
+        &state,
+        r#"{"backend": "gvisor", "tasks": [{"cmd": "/bin/true"}]}"#,
+    )
+    .await;
+    assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,269 @@
+//! End-to-end latency and CPU overhead of task supervision.
+//!
+//! Requires the same privileges as the integration tests (root and a cgroup
+//! v2 hierarchy). Run with:
+//!
+//! ```sh
+//! cargo bench -p faber-runtime --bench supervisor
+//! ```
+//!
+//! `FABER_BENCH_ITERATIONS` overrides the number of executions per scenario.
+//! The reported CPU time covers the runtime's forked processes (container
+//! setup, supervision and the tasks themselves), so scenarios whose task
+//! mostly sleeps isolate the cost of waiting on it.
+//!
+//! The first table needs no privileges: it compares the supervisor's own
+//! CPU time and wakeups for the previous 10 ms `poll` + `waitpid(WNOHANG)`
+//! loop against the pidfd + epoll loop, on plain child processes. Set
+//! `FABER_BENCH_LOOP_ONLY=1` to skip the end-to-end runtime scenarios.
+
+use std::{
+    io::Read,
+    os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use faber_runtime::{
+    ExecutionStep, ExecutionStepResult, RuntimeBuilder, RuntimeResult, Task, TaskResult,
+};
+use nix::{
+    libc,
+    sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags, EpollTimeout},
+};
+
+struct Scenario {
+    name: &'static str,
+    cmd: &'static str,
+    args: &'static [&'static str],
+    parallelism: usize,
+}
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        name: "true",
+        cmd: "/bin/true",
+        args: &[],
+        parallelism: 1,
+    },
+    Scenario {
+        name: "echo",
+        cmd: "/bin/echo",
+        args: &["hello, world"],
+        parallelism: 1,
+    },
+    Scenario {
+        name: "sleep 200ms",
+        cmd: "/bin/sleep",
+        args: &["0.2"],
+        parallelism: 1,
+    },
+    Scenario {
+        name: "8x sleep 200ms",
+        cmd: "/bin/sleep",
+        args: &["0.2"],
+        parallelism: 8,
+    },
+];
+
+fn cpu_time(who: libc::c_int) -> Duration {
+    let mut usage = unsafe { std::mem::zeroed::<libc::rusage>() };
+    unsafe { libc::getrusage(who, &mut usage) };
+    let micros = |time: libc::timeval| time.tv_sec as u64 * 1_000_000 + time.tv_usec as u64;
+    Duration::from_micros(micros(usage.ru_utime) + micros(usage.ru_stime))
+}
+
+fn children_cpu_time() -> Duration {
+    cpu_time(libc::RUSAGE_CHILDREN)
+}
+
+fn spawn_child(scenario: &Scenario) -> Child {
+    Command::new(scenario.cmd)
+        .args(scenario.args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn child")
+}
+
+/// The supervision loop before pidfd and epoll: poll the output pipe with a
+/// 10 ms timeout and check for exit with a non-blocking wait after each poll.
+fn poll_loop(mut child: Child) -> usize {
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut buffer = [0u8; 8192];
+    let (mut open, mut exited, mut wakeups) = (true, false, 0);
+    while open || !exited {
+        let mut poll_fd = libc::pollfd {
+            fd: if open { stdout.as_raw_fd() } else { -1 },
+            events: libc::POLLIN | libc::POLLHUP,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut poll_fd, 1, 10) };
+        wakeups += 1;
+        if poll_fd.revents != 0 {
+            open = stdout.read(&mut buffer).expect("failed to read stdout") > 0;
+        }
+        if !exited {
+            exited = child.try_wait().expect("failed to wait").is_some();
+        }
+    }
+    wakeups
+}
+
+/// The current supervision loop: block in epoll on the output pipe and a
+/// pidfd that becomes readable when the child exits.
+fn epoll_loop(mut child: Child) -> usize {
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, child.id(), 0) };
+    assert!(pidfd >= 0, "pidfd_open failed");
+    let pidfd = unsafe { OwnedFd::from_raw_fd(pidfd as i32) };
+
+    let epoll = Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC).expect("failed to create epoll");
+    epoll
+        .add(&stdout, EpollEvent::new(EpollFlags::EPOLLIN, 0))
+        .expect("failed to watch stdout");
+    epoll
+        .add(pidfd.as_fd(), EpollEvent::new(EpollFlags::EPOLLIN, 1))
+        .expect("failed to watch pidfd");
+
+    let mut buffer = [0u8; 8192];
+    let mut events = [EpollEvent::empty(); 2];
+    let (mut open, mut exited, mut wakeups) = (true, false, 0);
+    while open || !exited {
+        let ready = epoll
+            .wait(&mut events, EpollTimeout::NONE)
+            .expect("epoll_wait failed");
+        wakeups += 1;
+        for event in &events[..ready] {
+            if event.data() == 0 {
+                open = stdout.read(&mut buffer).expect("failed to read stdout") > 0;
+                if !open {
+                    epoll.delete(&stdout).expect("failed to unwatch stdout");
+                }
+            } else {
+                child.wait().expect("failed to wait");
+                exited = true;
+                epoll
+                    .delete(pidfd.as_fd())
+                    .expect("failed to unwatch pidfd");
+            }
+        }
+    }
+    wakeups
+}
+
+fn compare_loops(iterations: usize) {
+    println!(
+        "{:<16} {:<12} {:>10} {:>14} {:>10}",
+        "scenario", "loop", "mean", "cpu/execution", "wakeups"
+    );
+    for scenario in SCENARIOS
+        .iter()
+        .filter(|scenario| scenario.parallelism == 1)
+    {
+        for (name, supervise) in [
+            ("poll 10ms", poll_loop as fn(Child) -> usize),
+            ("pidfd+epoll", epoll_loop),
+        ] {
+            let cpu_before = cpu_time(libc::RUSAGE_SELF);
+            let started = Instant::now();
+            let wakeups: usize = (0..iterations)
+                .map(|_| supervise(spawn_child(scenario)))
+                .sum();
+            let elapsed = started.elapsed();
+            let cpu = cpu_time(libc::RUSAGE_SELF) - cpu_before;
+
+            println!(
+                "{:<16} {:<12} {:>10.2?} {:>14.2?} {:>10.1}",
+                scenario.name,
+                name,
+                elapsed / iterations as u32,
+                cpu / iterations as u32,
+                wakeups as f64 / iterations as f64
+            );
+        }
+    }
+    println!();
+}
+
+fn run(scenario: &Scenario) {
+    let task = Task {
+        cmd: scenario.cmd.to_string(),
+        args: Some(scenario.args.iter().map(|arg| arg.to_string()).collect()),
+        env: None,
+        stdin: None,
+        files: None,
+        working_dir: None,
+        sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
+    };
+    let step = if scenario.parallelism == 1 {
+        ExecutionStep::Single(task)
+    } else {
+        ExecutionStep::Parallel(vec![task; scenario.parallelism])
+    };
+
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![step])
+        .build()
+        .execute()
+        .expect("runtime execution failed");
+    let RuntimeResult::Success(steps, _) = &result else {
+        panic!("unexpected result: {result:?}");
+    };
+    for task_result in steps.iter().flat_map(|step| match step {
+        ExecutionStepResult::Single(task_result) => std::slice::from_ref(task_result),
+        ExecutionStepResult::Parallel(task_results) => task_results.as_slice(),
+    }) {
+        assert!(
+            matches!(task_result, TaskResult::Completed { exit_code: 0, .. }),
+            "task did not complete: {task_result:?}"
+        );
+    }
+}
+
+fn main() {
+    let iterations: usize = std::env::var("FABER_BENCH_ITERATIONS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(50);
+
+    compare_loops(iterations);
+    if std::env::var_os("FABER_BENCH_LOOP_ONLY").is_some() {
+        return;
+    }
+
+    println!(
+        "{:<16} {:>10} {:>10} {:>10} {:>14}",
+        "scenario", "p50", "p99", "mean", "cpu/execution"
+    );
+
+    for scenario in SCENARIOS {
+        // Warm up the cgroup hierarchy and page cache.
+        run(scenario);
+
+        let cpu_before = children_cpu_time();
+        let mut latencies: Vec<Duration> = (0..iterations)
+            .map(|_| {
+                let started = Instant::now();
+                run(scenario);
+                started.elapsed()
+            })
+            .collect();
+        let cpu = children_cpu_time() - cpu_before;
+
+        latencies.sort();
+        let percentile = |p: usize| latencies[(latencies.len() * p / 100).min(latencies.len() - 1)];
+        let mean = latencies.iter().sum::<Duration>() / iterations as u32;
+
+        println!(
+            "{:<16} {:>10.2?} {:>10.2?} {:>10.2?} {:>14.2?}",
+            scenario.name,
+            percentile(50),
+            percentile(99),
+            mean,
+            cpu / iterations as u32
+        );
+    }
+}
@@ -0,0 +1,575 @@
+use std::{
+    fs::{File, create_dir_all, read_link},
+    os::unix::fs::{PermissionsExt, symlink},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+use tokio::process::Command;
+
+use super::{
+    BackendFuture, ExecutionBackend, collect_output, completed_task, failed_task, run_steps,
+    task_stdin,
+};
+use crate::{
+    Runtime,
+    cgroup::CgroupConfig,
+    container::{ContainerConfig, MountKind, MountSpec},
+    prelude::*,
+    readiness::{ReadinessCheck, check_free_space},
+    result::{GroupTimings, RuntimeResult, TaskResult, TaskTimings},
+    runtime::SpillDir,
+    task::{Task, TaskGroup},
+    utils::{elapsed_usec, generate_random_string},
+};
+
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+const TASK_ID: u32 = 65534;
+
+/// Runs each task in its own gVisor sandbox using a locally installed `runsc`.
+///
+/// Tasks see the same filesystem layout as in the namespace runtime: the
+/// configuration's toolchain directories read-only, a private `/tmp`, extra
+/// mounts, and a workspace at the workdir that the steps of a task group
+/// share. Sandboxes have no network. Memory, CPU and PID limits come from the
+/// cgroup configuration; resource usage statistics are not collected.
+#[derive(Debug, Clone)]
+pub struct GvisorBackend {
+    runsc: PathBuf,
+    state_dir: PathBuf,
+    bundle_dir: PathBuf,
+    cgroup: CgroupConfig,
+    timeout: Duration,
+    cpu_time_limit: Duration,
+    output_limit: usize,
+}
+
+impl GvisorBackend {
+    /// Uses the `runsc` binary at `runsc`, which must be run as root.
+    pub fn new(runsc: impl Into<PathBuf>) -> Self {
+        Self {
+            runsc: runsc.into(),
+            state_dir: PathBuf::from("/run/faber/runsc"),
+            bundle_dir: PathBuf::from("/tmp/faber/gvisor"),
+            cgroup: CgroupConfig::default(),
+            timeout: Duration::from_secs(5),
+            cpu_time_limit: Duration::from_secs(5),
+            output_limit: 1024 * 1024,
+        }
+    }
+
+    /// Directory where `runsc` keeps the state of running sandboxes.
+    pub fn with_state_dir(mut self, state_dir: impl Into<PathBuf>) -> Self {
+        self.state_dir = state_dir.into();
+        self
+    }
+
+    /// Directory under which OCI bundles and workspaces are created.
+    pub fn with_bundle_dir(mut self, bundle_dir: impl Into<PathBuf>) -> Self {
+        self.bundle_dir = bundle_dir.into();
+        self
+    }
+
+    pub fn with_cgroup_config(mut self, cgroup_config: CgroupConfig) -> Self {
+        self.cgroup = cgroup_config;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_cpu_time_limit(mut self, cpu_time_limit: Duration) -> Self {
+        self.cpu_time_limit = cpu_time_limit;
+        self
+    }
+
+    pub fn with_output_limit(mut self, output_limit: usize) -> Self {
+        self.output_limit = output_limit;
+        self
+    }
+
+    async fn run_task_group(
+        self,
+        task_group: TaskGroup,
+        container: ContainerConfig,
+    ) -> RuntimeResult {
+        let start = Instant::now();
+        if !container.rootfs_layers.is_empty() || !container.workdir_layers.is_empty() {
+            return RuntimeResult::ContainerSetupFailed {
+                error: "The gVisor backend does not support image layers or workspace templates"
+                    .to_string(),
+                info: ErrorInfo::new(ErrorCode::InvalidConfiguration, ErrorStage::Validation),
+            };
+        }
+        let group_dir = match GroupDir::create(&self.bundle_dir.join(&container.id)) {
+            Ok(group_dir) => group_dir,
+            Err(e) => {
+                return RuntimeResult::ContainerSetupFailed {
+                    error: e.to_string(),
+                    info: e.info().in_stage(ErrorStage::ContainerSetup),
+                };
+            }
+        };
+
+        let container_setup_usec = elapsed_usec(start);
+
+        let container = Arc::new(container);
+        let steps_start = Instant::now();
+        let results = run_steps(task_group, |task| {
+            let backend = self.clone();
+            let container = container.clone();
+            let group_dir = group_dir.path.clone();
+            async move {
+                backend
+                    .run_task(task, &container, &group_dir)
+                    .await
+                    .unwrap_or_else(failed_task)
+            }
+        })
+        .await;
+        let steps_usec = elapsed_usec(steps_start);
+
+        let cleanup_start = Instant::now();
+        drop(group_dir);
+        RuntimeResult::Success(
+            results,
+            GroupTimings {
+                container_setup_usec,
+                steps_usec,
+                cleanup_usec: elapsed_usec(cleanup_start),
+                total_usec: elapsed_usec(start),
+                ..Default::default()
+            },
+        )
+    }
+
+    async fn run_task(
+        &self,
+        task: Task,
+        container: &ContainerConfig,
+        group_dir: &Path,
+    ) -> Result<TaskResult> {
+        let start_time = Instant::now();
+        let mut timings = TaskTimings::default();
+        let workspace = group_dir.join("workspace");
+        let stdin = task_stdin(&task, container)?;
+        let spill = container
+            .output_spill
+            .as_ref()
+            .map(SpillDir::open)
+            .transpose()?;
+
+        for (file_path, file_content) in task.files.clone().unwrap_or_default() {
+            Runtime::write_workspace_file(&workspace, &file_path, file_content.as_bytes())?;
+        }
+
+        let sandbox = Sandbox {
+            runsc: self.runsc.clone(),
+            state_dir: self.state_dir.clone(),
+            id: format!("faber-{}-{}", container.id, generate_random_string(8)),
+            bundle: group_dir.join(generate_random_string(12)),
+            deleted: false,
+        };
+        let rootfs = sandbox.bundle.join("rootfs");
+        create_dir_all(&rootfs).map_err(|e| FaberError::CreateDir {
+            e,
+            details: format!("Failed to create OCI bundle {}", sandbox.bundle.display()),
+        })?;
+        let spec = self.spec(&task, container, &rootfs, &workspace)?;
+        std::fs::write(sandbox.bundle.join("config.json"), spec.to_string()).map_err(|e| {
+            FaberError::WriteFile {
+                e,
+                details: "Failed to write OCI runtime spec".to_string(),
+            }
+        })?;
+        timings.file_setup_usec = elapsed_usec(start_time);
+
+        let spawn_start = Instant::now();
+        let mut child = Command::new(&self.runsc)
+            .arg("--root")
+            .arg(&self.state_dir)
+            .arg("--network=none")
+            .arg("run")
+            .arg("--bundle")
+            .arg(&sandbox.bundle)
+            .arg(&sandbox.id)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| FaberError::Generic {
+                message: format!("Failed to start {}: {e}", self.runsc.display()),
+            })?;
+        timings.spawn_usec = elapsed_usec(spawn_start);
+
+        let run_start = Instant::now();
+        let mut collected = collect_output(
+            &mut child,
+            task.stdin.unwrap_or_default().into_bytes(),
+            self.timeout,
+            (
+                self.output_limit,
+                task.capture.unwrap_or_default(),
+                spill.as_ref(),
+            ),
+            &|| sandbox.kill(),
+        )
+        .await?;
+        timings.run_usec = elapsed_usec(run_start);
+
+        let cleanup_start = Instant::now();
+        sandbox.delete().await;
+        timings.cleanup_usec = elapsed_usec(cleanup_start);
+
+        // `runsc run` reports a task killed by a signal as 128 + signal, like
+        // a shell, which is also how the namespace runtime reports it.
+        if collected.termination_signal.is_none() && (129..=128 + 64).contains(&collected.exit_code)
+        {
+            collected.termination_signal = Some(collected.exit_code - 128);
+        }
+
+        Ok(completed_task(collected, start_time, timings))
+    }
+
+    /// Builds the OCI runtime spec for one task.
+    fn spec(
+        &self,
+        task: &Task,
+        container: &ContainerConfig,
+        rootfs: &Path,
+        workspace: &Path,
+    ) -> Result<Value> {
+        let mut args = vec![task.cmd.clone()];
+        args.extend(task.args.clone().unwrap_or_default());
+
+        let task_env = task.env.clone().unwrap_or_default();
+        let mut env: Vec<String> = task_env
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        if !task_env.contains_key("PATH") {
+            env.push(format!("PATH={DEFAULT_PATH}"));
+        }
+
+        let cwd = match &task.working_dir {
+            Some(working_dir) => container.workdir.join(working_dir),
+            None => container.workdir.clone(),
+        };
+        let cpu_seconds = self.cpu_time_limit.as_secs().max(1);
+
+        Ok(json!({
+            "ociVersion": "1.0.2",
+            "process": {
+                "terminal": false,
+                "user": { "uid": TASK_ID, "gid": TASK_ID },
+                "args": args,
+                "env": env,
+                "cwd": cwd,
+                "noNewPrivileges": true,
+                "rlimits": [
+                    { "type": "RLIMIT_CPU", "soft": cpu_seconds, "hard": cpu_seconds },
+                ],
+            },
+            "root": { "path": rootfs, "readonly": true },
+            "hostname": container.hostname,
+            "mounts": Self::mounts(container, rootfs, workspace)?,
+            "linux": {
+                "namespaces": [
+                    { "type": "pid" },
+                    { "type": "network" },
+                    { "type": "ipc" },
+                    { "type": "uts" },
+                    { "type": "mount" },
+                ],
+                "resources": self.resources(),
+            },
+        }))
+    }
+
+    /// Lays out the same mounts as the namespace runtime, creating their
+    /// mount points in the otherwise empty bundle root.
+    fn mounts(container: &ContainerConfig, rootfs: &Path, workspace: &Path) -> Result<Vec<Value>> {
+        let mut mounts = vec![
+            json!({ "destination": "/proc", "type": "proc", "source": "proc" }),
+            json!({
+                "destination": "/dev",
+                "type": "tmpfs",
+                "source": "tmpfs",
+                "options": ["nosuid", "strictatime", "mode=755", "size=65536k"],
+            }),
+            json!({
+                "destination": "/tmp",
+                "type": "tmpfs",
+                "source": "tmpfs",
+                "options": ["nosuid", "nodev", format!("size={}", container.tmpdir_size)],
+            }),
+            json!({
+                "destination": container.workdir,
+                "type": "bind",
+                "source": workspace,
+                "options": ["rbind", "rw", "nosuid", "nodev"],
+            }),
+        ];
+        for destination in ["/proc", "/dev", "/tmp"] {
+            Self::mount_point(rootfs, Path::new(destination), false)?;
+        }
+        Self::mount_point(rootfs, &container.workdir, false)?;
+
+        for path in &container.bind_mounts_ro {
+            let relative_path = path.strip_prefix("/").unwrap_or(path);
+            let source = container.rootfs.join(relative_path);
+
+            // Merged-/usr environments ship /bin, /lib, ... as symlinks.
+            if container.rootfs != Path::new("/")
+                && let Ok(link) = read_link(&source)
+            {
+                symlink(&link, rootfs.join(relative_path)).map_err(|e| FaberError::CreateDir {
+                    e,
+                    details: format!("Failed to recreate environment symlink {}", path),
+                })?;
+                continue;
+            }
+            if !source.exists() {
+                continue;
+            }
+
+            Self::mount_point(rootfs, Path::new(path), false)?;
+            mounts.push(json!({
+                "destination": path,
+                "type": "bind",
+                "source": source,
+                "options": ["rbind", "ro", "nosuid", "nodev"],
+            }));
+        }
+
+        for spec in &container.mounts {
+            spec.check_workdir(&container.workdir)?;
+            mounts.push(Self::extra_mount(spec));
+            let is_file = matches!(&spec.kind, MountKind::Bind(source) if source.is_file());
+            Self::mount_point(rootfs, &spec.target, is_file)?;
+        }
+
+        Ok(mounts)
+    }
+
+    fn extra_mount(spec: &MountSpec) -> Value {
+        let mut options = vec![if spec.read_only { "ro" } else { "rw" }.to_string()];
+        if spec.nosuid {
+            options.push("nosuid".to_string());
+        }
+        if spec.nodev {
+            options.push("nodev".to_string());
+        }
+        if spec.noexec {
+            options.push("noexec".to_string());
+        }
+
+        match &spec.kind {
+            MountKind::Bind(source) => {
+                options.insert(0, "rbind".to_string());
+                json!({
+                    "destination": spec.target,
+                    "type": "bind",
+                    "source": source,
+                    "options": options,
+                })
+            }
+            MountKind::Tmpfs { size } => {
+                options.push(format!("size={size}"));
+                json!({
+                    "destination": spec.target,
+                    "type": "tmpfs",
+                    "source": "tmpfs",
+                    "options": options,
+                })
+            }
+        }
+    }
+
+    fn mount_point(rootfs: &Path, destination: &Path, is_file: bool) -> Result<()> {
+        let target = rootfs.join(destination.strip_prefix("/").unwrap_or(destination));
+        let created = if is_file {
+            target
+                .parent()
+                .map_or(Ok(()), create_dir_all)
+                .and_then(|()| File::create(&target).map(drop))
+        } else {
+            create_dir_all(&target)
+        };
+        created.map_err(|e| FaberError::CreateDir {
+            e,
+            details: format!("Failed to create mount point {}", target.display()),
+        })
+    }
+
+    fn resources(&self) -> Value {
+        let mut resources = json!({ "pids": { "limit": self.cgroup.pids_max } });
+        if let Some(limit) = parse_memory_max(&self.cgroup.memory_max) {
+            resources["memory"] = json!({ "limit": limit });
+            // OCI counts swap together with memory.
+            if let Some(swap) = parse_memory_max(&self.cgroup.memory_swap_max) {
+                resources["memory"]["swap"] = json!(limit + swap);
+            }
+        }
+        if let Some((quota, period)) = self.cgroup.cpu_max.split_once(' ')
+            && let (Ok(quota), Ok(period)) = (quota.parse::<i64>(), period.parse::<u64>())
+        {
+            resources["cpu"] = json!({ "quota": quota, "period": period });
+        }
+        if let Some(weight) = self.cgroup.io_weight {
+            resources["blockIO"]["weight"] = json!(weight);
+        }
+        for limit in &self.cgroup.io_max {
+            let throttles = [
+                ("throttleReadBpsDevice", limit.read_bps),
+                ("throttleWriteBpsDevice", limit.write_bps),
+                ("throttleReadIOPSDevice", limit.read_iops),
+                ("throttleWriteIOPSDevice", limit.write_iops),
+            ];
+            for (key, rate) in throttles {
+                let Some(rate) = rate else { continue };
+                let device = json!({ "major": limit.major, "minor": limit.minor, "rate": rate });
+                match &mut resources["blockIO"][key] {
+                    Value::Array(devices) => devices.push(device),
+                    entry => *entry = json!([device]),
+                }
+            }
+        }
+        resources
+    }
+}
+
+impl ExecutionBackend for GvisorBackend {
+    fn name(&self) -> &'static str {
+        "gvisor"
+    }
+
+    fn is_isolated(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, task_group: TaskGroup, container: ContainerConfig) -> BackendFuture {
+        let backend = self.clone();
+        Box::pin(async move { Ok(backend.run_task_group(task_group, container).await) })
+    }
+
+    fn readiness(&self, min_free_bytes: u64) -> Vec<ReadinessCheck> {
+        let runsc = match self.runsc.metadata() {
+            Ok(metadata) if metadata.is_file() && metadata.permissions().mode() & 0o111 != 0 => {
+                ReadinessCheck::pass("runsc", self.runsc.display().to_string())
+            }
+            Ok(_) => ReadinessCheck::fail(
+                "runsc",
+                format!("{} is not an executable file", self.runsc.display()),
+            ),
+            Err(e) => ReadinessCheck::fail("runsc", format!("{}: {e}", self.runsc.display())),
+        };
+        vec![
+            runsc,
+            check_free_space("bundle_space", &self.bundle_dir, min_free_bytes),
+        ]
+    }
+}
+
+/// Parses a cgroup v2 `memory.max` value; `None` means unlimited.
+fn parse_memory_max(value: &str) -> Option<i64> {
+    let (digits, multiplier) = match value.chars().last()? {
+        'K' | 'k' => (&value[..value.len() - 1], 1024),
+        'M' | 'm' => (&value[..value.len() - 1], 1024 * 1024),
+        'G' | 'g' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    digits.parse::<i64>().ok().map(|bytes| bytes * multiplier)
+}
+
+/// One task's sandbox. [`Sandbox::delete`] force-deletes it together with its
+/// bundle; a sandbox dropped on an error path is deleted in the background.
+struct Sandbox {
+    runsc: PathBuf,
+    state_dir: PathBuf,
+    id: String,
+    bundle: PathBuf,
+    deleted: bool,
+}
+
+impl Sandbox {
+    fn runsc(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(&self.runsc);
+        command
+            .arg("--root")
+            .arg(&self.state_dir)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        command
+    }
+
+    /// Starts `runsc kill` without waiting for it; the task's exit is what
+    /// the caller waits for.
+    fn kill(&self) {
+        let _ = self.runsc(&["kill", "--all", &self.id, "KILL"]).spawn();
+    }
+
+    async fn delete(mut self) {
+        self.deleted = true;
+        self.teardown().await;
+    }
+
+    fn teardown(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut delete = self.runsc(&["delete", "--force", &self.id]);
+        let bundle = self.bundle.clone();
+        async move {
+            let _ = delete.status().await;
+            let _ = tokio::task::spawn_blocking(move || std::fs::remove_dir_all(bundle)).await;
+        }
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        if !self.deleted
+            && let Ok(handle) = tokio::runtime::Handle::try_current()
+        {
+            handle.spawn(self.teardown());
+        }
+    }
+}
+
+/// The bundles and shared workspace of one task group.
+struct GroupDir {
+    path: PathBuf,
+}
+
+impl GroupDir {
+    fn create(path: &Path) -> Result<Self> {
+        let workspace = path.join("workspace");
+        create_dir_all(&workspace).map_err(|e| FaberError::CreateDir {
+            e,
+            details: format!("Failed to create workspace {}", workspace.display()),
+        })?;
+        let group_dir = Self {
+            path: path.to_path_buf(),
+        };
+        // Tasks run as an unprivileged user inside the sandbox.
+        std::fs::set_permissions(&workspace, std::fs::Permissions::from_mode(0o777)).map_err(
+            |e| FaberError::CreateDir {
+                e,
+                details: format!("Failed to open up workspace {}", workspace.display()),
+            },
+        )?;
+        Ok(group_dir)
+    }
+}
+
+impl Drop for GroupDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,300 @@
+mod gvisor;
+mod namespace;
+mod process;
+
+use std::{
+    fmt::Debug,
+    fs::File,
+    future::Future,
+    os::unix::process::ExitStatusExt,
+    pin::Pin,
+    process::Stdio,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+pub use gvisor::GvisorBackend;
+pub use namespace::NamespaceBackend;
+pub use process::ProcessBackend;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    process::Child,
+    task::JoinSet,
+};
+
+use crate::{
+    container::ContainerConfig,
+    prelude::*,
+    readiness::ReadinessCheck,
+    result::{
+        ExecutionStepResult, OutputStream, RuntimeResult, TaskGroupResult, TaskOutcome, TaskResult,
+        TaskResultStats, TaskTimings, TerminationPhase,
+    },
+    runtime::{CollectedOutput, MergedBuffer, OutputBuffer, SpillDir},
+    task::{ExecutionStep, OutputCapture, Task, TaskGroup},
+};
+
+/// The pending result of [`ExecutionBackend::execute`].
+pub type BackendFuture = Pin<Box<dyn Future<Output = Result<RuntimeResult>> + Send>>;
+
+/// Runs task groups on behalf of the API.
+///
+/// Backends share one task contract: they take a [`TaskGroup`] and report a
+/// [`RuntimeResult`] with a result per task, in step order.
+pub trait ExecutionBackend: Debug + Send + Sync {
+    /// Short name reported by the health endpoint.
+    fn name(&self) -> &'static str;
+
+    /// Whether tasks are isolated from the host and from each other.
+    fn is_isolated(&self) -> bool;
+
+    /// Starts `task_group` in a container built from `container`.
+    ///
+    /// Must be called from within a tokio runtime. Dropping the returned
+    /// future must not leak processes or container state.
+    fn execute(&self, task_group: TaskGroup, container: ContainerConfig) -> BackendFuture;
+
+    /// Checks that the host currently provides what this backend needs,
+    /// with at least `min_free_bytes` free where containers are built.
+    fn readiness(&self, min_free_bytes: u64) -> Vec<ReadinessCheck> {
+        let _ = min_free_bytes;
+        Vec::new()
+    }
+}
+
+/// Runs the steps of `task_group` in order, and the tasks of a parallel step
+/// concurrently. Dropping the future aborts the tasks still running.
+async fn run_steps<F, Fut>(task_group: TaskGroup, run_task: F) -> TaskGroupResult
+where
+    F: Fn(Task) -> Fut,
+    Fut: Future<Output = TaskResult> + Send + 'static,
+{
+    let mut results = Vec::with_capacity(task_group.len());
+    for step in task_group {
+        let result = match step {
+            ExecutionStep::Single(task) => ExecutionStepResult::Single(run_task(task).await),
+            ExecutionStep::Parallel(tasks) => {
+                let mut running = JoinSet::new();
+                for (index, task) in tasks.into_iter().enumerate() {
+                    let task_result = run_task(task);
+                    running.spawn(async move { (index, task_result.await) });
+                }
+
+                let mut task_results = Vec::with_capacity(running.len());
+                while let Some(joined) = running.join_next().await {
+                    task_results.push(joined.unwrap_or_else(|e| {
+                        (
+                            usize::MAX,
+                            failed_task(FaberError::Generic {
+                                message: e.to_string(),
+                            }),
+                        )
+                    }));
+                }
+                task_results.sort_by_key(|(index, _)| *index);
+                ExecutionStepResult::Parallel(
+                    task_results.into_iter().map(|(_, result)| result).collect(),
+                )
+            }
+        };
+        results.push(result);
+    }
+    results
+}
+
+/// Feeds `stdin` to `child` and collects its output until it exits. `kill`
+/// must stop the task; it is called when `timeout` expires or once output
+/// beyond `output_limit` had to be dropped, unless `capture` keeps the task
+/// running, as the namespace runtime does. Output past the inline limit goes
+/// to `spill`.
+async fn collect_output(
+    child: &mut Child,
+    stdin: Vec<u8>,
+    timeout: Duration,
+    (output_limit, capture, spill): (usize, OutputCapture, Option<&SpillDir>),
+    kill: &(dyn Fn() + Sync),
+) -> Result<CollectedOutput> {
+    let stdin_writer = child.stdin.take();
+    let stdout_reader = child.stdout.take();
+    let stderr_reader = child.stderr.take();
+    let feed_stdin = async move {
+        if let Some(mut stdin_writer) = stdin_writer {
+            let _ = stdin_writer.write_all(&stdin).await;
+        }
+    };
+
+    let started = Instant::now();
+    let merged = capture.merged.then(|| {
+        Mutex::new(MergedBuffer::new(
+            SpillDir::merged_limit(spill, output_limit),
+            &capture,
+        ))
+    });
+    let stop_on_limit = capture.stops_at_limit().then_some(kill);
+    let mut timed_out = false;
+    let ((), stdout, stderr, status) = {
+        let collect = async {
+            tokio::join!(
+                feed_stdin,
+                read_limited(
+                    stdout_reader,
+                    OutputBuffer::new(capture.stream_limit(output_limit), &capture, spill),
+                    (OutputStream::Stdout, merged.as_ref(), started),
+                    stop_on_limit,
+                ),
+                read_limited(
+                    stderr_reader,
+                    OutputBuffer::new(capture.stream_limit(output_limit), &capture, spill),
+                    (OutputStream::Stderr, merged.as_ref(), started),
+                    stop_on_limit,
+                ),
+                child.wait(),
+            )
+        };
+        tokio::pin!(collect);
+
+        match tokio::time::timeout(timeout, &mut collect).await {
+            Ok(collected) => collected,
+            Err(_) => {
+                timed_out = true;
+                kill();
+                collect.await
+            }
+        }
+    };
+    let status = status.map_err(|e| FaberError::Generic {
+        message: format!("Failed to wait for task: {e}"),
+    })?;
+
+    let termination_signal = status.signal();
+    let (stdout_truncated, stderr_truncated) = (stdout.is_truncated(), stderr.is_truncated());
+    let output_terminated = capture.stops_at_limit() && (stdout_truncated || stderr_truncated);
+    let (stdout_elided_bytes, stderr_elided_bytes) = (stdout.elided(), stderr.elided());
+    let (stdout, stdout_spill) = stdout.finish();
+    let (stderr, stderr_spill) = stderr.finish();
+    Ok(CollectedOutput {
+        stdout,
+        stderr,
+        stdout_truncated,
+        stderr_truncated,
+        stdout_elided_bytes,
+        stderr_elided_bytes,
+        stdout_spill,
+        stderr_spill,
+        merged_output: merged.map(|merged| {
+            merged
+                .into_inner()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .into_output()
+        }),
+        exit_code: status
+            .code()
+            .or(termination_signal.map(|signal| 128 + signal))
+            .unwrap_or(-1),
+        termination_signal,
+        timed_out,
+        cpu_time_exceeded: false,
+        idle_timed_out: false,
+        output_terminated,
+        timeline: Vec::new(),
+        memory_breakdown_peak: (0, 0),
+        process_usage: None,
+        termination_phase: (timed_out || output_terminated).then_some(TerminationPhase::Kill),
+    })
+}
+
+/// The stdin of a task's process: the host file it names with
+/// `stdin_file_id`, opened directly rather than copied, or a pipe that
+/// [`collect_output`] feeds from `stdin`.
+fn task_stdin(task: &Task, container: &ContainerConfig) -> Result<Stdio> {
+    let Some(path) = container.stdin_file(task)? else {
+        return Ok(Stdio::piped());
+    };
+    File::open(path)
+        .map(Stdio::from)
+        .map_err(|e| FaberError::Generic {
+            message: format!("Failed to open stdin file {}: {e}", path.display()),
+        })
+}
+
+/// Reads `reader` to EOF into `output` and, if set, the merged stream.
+/// `kill` is called once output had to be dropped.
+async fn read_limited<'a>(
+    reader: Option<impl AsyncRead + Unpin>,
+    mut output: OutputBuffer<'a>,
+    (stream, merged, started): (OutputStream, Option<&Mutex<MergedBuffer>>, Instant),
+    kill: Option<&(dyn Fn() + Sync)>,
+) -> OutputBuffer<'a> {
+    let Some(mut reader) = reader else {
+        return output;
+    };
+
+    let mut chunk = [0u8; 8192];
+    let mut killed = false;
+    while let Ok(bytes_read @ 1..) = reader.read(&mut chunk).await {
+        output.push(&chunk[..bytes_read]);
+        if let Some(merged) = merged {
+            merged
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(stream, started.elapsed(), &chunk[..bytes_read]);
+        }
+        if let Some(kill) = kill
+            && output.is_truncated()
+            && !killed
+        {
+            killed = true;
+            kill();
+        }
+    }
+    output
+}
+
+/// Builds the result of a task that ran to completion. Backends that cannot
+/// measure resource usage leave those statistics at zero.
+fn completed_task(
+    collected: CollectedOutput,
+    start_time: Instant,
+    timings: TaskTimings,
+) -> TaskResult {
+    let outcome = if collected.timed_out {
+        TaskOutcome::TimedOut
+    } else if collected.output_terminated {
+        TaskOutcome::OutputLimit
+    } else if collected.termination_signal == Some(nix::libc::SIGSYS) {
+        TaskOutcome::PolicyViolation
+    } else if collected.termination_signal.is_some() {
+        TaskOutcome::Signaled
+    } else {
+        TaskOutcome::Exited
+    };
+
+    TaskResult::Completed {
+        stdout: String::from_utf8_lossy(&collected.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&collected.stderr).into_owned(),
+        exit_code: collected.exit_code,
+        stats: TaskResultStats {
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+            stdout_truncated: collected.stdout_truncated,
+            stderr_truncated: collected.stderr_truncated,
+            stdout_elided_bytes: collected.stdout_elided_bytes,
+            stderr_elided_bytes: collected.stderr_elided_bytes,
+            stdout_spill: collected.stdout_spill,
+            stderr_spill: collected.stderr_spill,
+            outcome,
+            termination_signal: collected.termination_signal,
+            cleanup_succeeded: true,
+            merged_output: collected.merged_output,
+            timings,
+            ..Default::default()
+        },
+    }
+}
+
+fn failed_task(e: FaberError) -> TaskResult {
+    TaskResult::Failed {
+        error: format!("Task execution failed: {}", e),
+        info: e.info(),
+        stats: TaskResultStats::default(),
+    }
+}
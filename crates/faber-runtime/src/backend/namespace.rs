@@ -0,0 +1,111 @@
+use std::{path::Path, sync::Arc, time::Instant};
+
+use super::{BackendFuture, ExecutionBackend};
+use crate::{
+    cgroup::{CgroupConfig, CpuAllocator},
+    container::{CONTAINERS_DIR, ContainerConfig},
+    prelude::*,
+    readiness::{
+        ReadinessCheck, check_cgroups, check_free_space, check_seccomp, check_user_namespaces,
+    },
+    runtime::{ContainerPool, RuntimeBuilder},
+    task::TaskGroup,
+    utils::elapsed_usec,
+};
+
+/// Runs each task group in its own set of Linux namespaces and cgroups.
+#[derive(Debug, Clone, Default)]
+pub struct NamespaceBackend {
+    cgroup: CgroupConfig,
+    pool: Option<Arc<ContainerPool>>,
+    cpus: Option<Arc<CpuAllocator>>,
+}
+
+impl NamespaceBackend {
+    pub fn with_cgroup_config(mut self, cgroup_config: CgroupConfig) -> Self {
+        self.cgroup = cgroup_config;
+        self
+    }
+
+    /// Claims pre-warmed containers from `pool` for matching executions.
+    pub fn with_pool(mut self, pool: Arc<ContainerPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Pins each execution to CPUs leased from `cpus`, waiting for a free
+    /// slot before the container is started.
+    pub fn with_cpu_allocator(mut self, cpus: Arc<CpuAllocator>) -> Self {
+        self.cpus = Some(cpus);
+        self
+    }
+}
+
+impl ExecutionBackend for NamespaceBackend {
+    fn name(&self) -> &'static str {
+        "namespace"
+    }
+
+    fn is_isolated(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, task_group: TaskGroup, container: ContainerConfig) -> BackendFuture {
+        let mut runtime = RuntimeBuilder::default()
+            .with_task_group(task_group)
+            .with_container_config(container);
+        if let Some(pool) = &self.pool {
+            runtime = runtime.with_pool(pool.clone());
+        }
+
+        let Some(cpus) = self.cpus.clone() else {
+            // The handle's supervisor keeps running if the future is dropped,
+            // so the container is still cleaned up.
+            return match runtime
+                .with_cgroup_config(self.cgroup.clone())
+                .build()
+                .spawn()
+            {
+                Ok(handle) => Box::pin(handle.wait()),
+                Err(e) => Box::pin(async { Err(e) }),
+            };
+        };
+
+        let mut cgroup = self.cgroup.clone();
+        Box::pin(async move {
+            let queued = Instant::now();
+            let lease = cpus.acquire().await;
+            let queue_usec = elapsed_usec(queued);
+            cgroup.cpuset = Some(lease.cpuset().clone());
+            let handle = runtime.with_cgroup_config(cgroup).build().spawn()?;
+            // Hold the lease until the container is gone, even if the caller
+            // stops waiting, so its cores are never handed out twice.
+            let mut result = tokio::spawn(async move {
+                let result = handle.wait().await;
+                drop(lease);
+                result
+            })
+            .await
+            .map_err(|e| FaberError::Generic {
+                message: format!("Execution task failed: {e}"),
+            })??;
+            if let Some(timings) = result.timings_mut() {
+                timings.queue_usec = queue_usec;
+            }
+            Ok(result)
+        })
+    }
+
+    fn readiness(&self, min_free_bytes: u64) -> Vec<ReadinessCheck> {
+        vec![
+            check_cgroups(),
+            check_user_namespaces(),
+            check_seccomp(),
+            check_free_space(
+                "container_root_space",
+                Path::new(CONTAINERS_DIR),
+                min_free_bytes,
+            ),
+        ]
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use nix::{
+    sys::signal::{Signal, killpg},
+    unistd::Pid,
+};
+use tokio::process::Command;
+
+use super::{
+    BackendFuture, ExecutionBackend, collect_output, completed_task, failed_task, run_steps,
+    task_stdin,
+};
+use crate::{
+    Runtime,
+    container::ContainerConfig,
+    prelude::*,
+    result::{GroupTimings, RuntimeResult, TaskOutcome, TaskResult, TaskResultStats, TaskTimings},
+    runtime::SpillDir,
+    task::{Task, TaskGroup},
+    utils::{elapsed_usec, generate_random_string},
+};
+
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Runs tasks as ordinary host processes, WITHOUT ANY ISOLATION.
+///
+/// Meant for development and for exercising the API without root: tasks run
+/// as the server's user with full access to the host filesystem and network.
+/// Only the wall-clock timeout and the output limit are enforced, and the
+/// resource usage fields of [`TaskResultStats`] are left at zero.
+#[derive(Debug, Clone)]
+pub struct ProcessBackend {
+    timeout: Duration,
+    output_limit: usize,
+    scratch_dir: PathBuf,
+}
+
+impl Default for ProcessBackend {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            output_limit: 1024 * 1024,
+            scratch_dir: std::env::temp_dir().join("faber-process"),
+        }
+    }
+}
+
+impl ProcessBackend {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_output_limit(mut self, output_limit: usize) -> Self {
+        self.output_limit = output_limit;
+        self
+    }
+
+    /// Directory under which each task group gets a scratch workspace.
+    pub fn with_scratch_dir(mut self, scratch_dir: impl Into<PathBuf>) -> Self {
+        self.scratch_dir = scratch_dir.into();
+        self
+    }
+
+    async fn run_task_group(
+        self,
+        task_group: TaskGroup,
+        container: ContainerConfig,
+    ) -> RuntimeResult {
+        let start = Instant::now();
+        if !container.is_plain() {
+            return RuntimeResult::ContainerSetupFailed {
+                error: "The process backend does not support environments, workspaces or mounts"
+                    .to_string(),
+                info: ErrorInfo::new(ErrorCode::InvalidConfiguration, ErrorStage::Validation),
+            };
+        }
+        let workspace = match Workspace::create(&self.scratch_dir) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                return RuntimeResult::ContainerSetupFailed {
+                    error: e.to_string(),
+                    info: e.info().in_stage(ErrorStage::ContainerSetup),
+                };
+            }
+        };
+
+        let container_setup_usec = elapsed_usec(start);
+
+        let container = Arc::new(container);
+        let steps_start = Instant::now();
+        let results = run_steps(task_group, |task| {
+            let backend = self.clone();
+            let workspace = workspace.path.clone();
+            let container = container.clone();
+            async move {
+                backend
+                    .run_task(task, &workspace, &container)
+                    .await
+                    .unwrap_or_else(failed_task)
+            }
+        })
+        .await;
+        let steps_usec = elapsed_usec(steps_start);
+
+        let cleanup_start = Instant::now();
+        drop(workspace);
+        RuntimeResult::Success(
+            results,
+            GroupTimings {
+                container_setup_usec,
+                steps_usec,
+                cleanup_usec: elapsed_usec(cleanup_start),
+                total_usec: elapsed_usec(start),
+                ..Default::default()
+            },
+        )
+    }
+
+    async fn run_task(
+        &self,
+        task: Task,
+        workspace: &Path,
+        container: &ContainerConfig,
+    ) -> Result<TaskResult> {
+        let start_time = Instant::now();
+        let mut timings = TaskTimings::default();
+        let stdin = task_stdin(&task, container)?;
+        let spill = container
+            .output_spill
+            .as_ref()
+            .map(SpillDir::open)
+            .transpose()?;
+
+        for (file_path, file_content) in task.files.unwrap_or_default() {
+            Runtime::write_workspace_file(workspace, &file_path, file_content.as_bytes())?;
+        }
+        timings.file_setup_usec = elapsed_usec(start_time);
+
+        // Paths below the container workdir map into the scratch workspace;
+        // any other absolute path is used on the host as is.
+        let current_dir = match &task.working_dir {
+            Some(working_dir) => {
+                let working_dir = Path::new(working_dir);
+                workspace.join(
+                    working_dir
+                        .strip_prefix(&container.workdir)
+                        .unwrap_or(working_dir),
+                )
+            }
+            None => workspace.to_path_buf(),
+        };
+
+        let mut command = Command::new(&task.cmd);
+        command
+            .args(task.args.unwrap_or_default())
+            .env_clear()
+            .env("PATH", DEFAULT_PATH)
+            .envs(task.env.unwrap_or_default())
+            .current_dir(current_dir)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0);
+
+        let spawn_start = Instant::now();
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            // Match the namespace runtime, where a failed exec exits with 127.
+            Err(e) => {
+                return Ok(TaskResult::Completed {
+                    stdout: String::new(),
+                    stderr: format!("Failed to execute {}: {}\n", task.cmd, e),
+                    exit_code: 127,
+                    stats: TaskResultStats {
+                        execution_time_ms: start_time.elapsed().as_millis() as u64,
+                        outcome: TaskOutcome::Exited,
+                        cleanup_succeeded: true,
+                        timings,
+                        ..Default::default()
+                    },
+                });
+            }
+        };
+        let Some(pid) = child.id() else {
+            return Err(FaberError::Generic {
+                message: "Task exited before it could be supervised".to_string(),
+            });
+        };
+        let group = ProcessGroup(Pid::from_raw(pid as i32));
+        timings.spawn_usec = elapsed_usec(spawn_start);
+
+        let run_start = Instant::now();
+        let collected = collect_output(
+            &mut child,
+            task.stdin.unwrap_or_default().into_bytes(),
+            self.timeout,
+            (
+                self.output_limit,
+                task.capture.unwrap_or_default(),
+                spill.as_ref(),
+            ),
+            &|| group.kill(),
+        )
+        .await?;
+        timings.run_usec = elapsed_usec(run_start);
+
+        Ok(completed_task(collected, start_time, timings))
+    }
+}
+
+impl ExecutionBackend for ProcessBackend {
+    fn name(&self) -> &'static str {
+        "process"
+    }
+
+    fn is_isolated(&self) -> bool {
+        false
+    }
+
+    fn execute(&self, task_group: TaskGroup, container: ContainerConfig) -> BackendFuture {
+        let backend = self.clone();
+        Box::pin(async move { Ok(backend.run_task_group(task_group, container).await) })
+    }
+}
+
+/// A task's process group, killed when the task finishes or is dropped so
+/// that background processes do not outlive it.
+struct ProcessGroup(Pid);
+
+impl ProcessGroup {
+    fn kill(&self) {
+        let _ = killpg(self.0, Signal::SIGKILL);
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// A scratch directory shared by the tasks of one task group.
+struct Workspace {
+    path: PathBuf,
+}
+
+impl Workspace {
+    fn create(scratch_dir: &Path) -> Result<Self> {
+        let path = scratch_dir.join(generate_random_string(12));
+        std::fs::create_dir_all(&path).map_err(|e| FaberError::CreateDir {
+            e,
+            details: format!("Failed to create workspace {}", path.display()),
+        })?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
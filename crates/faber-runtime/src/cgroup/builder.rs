@@ -1,4 +1,7 @@
-use super::config::CgroupConfig;
+use std::time::Duration;
+
+use super::config::{CgroupConfig, IoMax};
+use crate::prelude::*;
 
 #[derive(Default)]
 pub struct CgroupConfigBuilder {
@@ -17,16 +20,80 @@ impl CgroupConfigBuilder {
         self
     }
 
+    /// Kills a task once all of its processes together have used `budget`
+    /// of CPU time, unlike the per-process `RLIMIT_CPU`.
+    pub fn with_cpu_time_budget(mut self, budget: Duration) -> Self {
+        self.config.cpu_time_budget = Some(budget);
+        self
+    }
+
+    /// Stops a task that has written no output and used less than 1% of a
+    /// CPU for `timeout`, such as one blocked reading stdin or sleeping.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Samples memory, process count and pressure stalls every `interval`
+    /// while a task runs, reported as its `timeline`; `None` turns the
+    /// timeline off. Off by default.
+    pub fn with_sample_interval(mut self, interval: Option<Duration>) -> Self {
+        self.config.sample_interval = interval.filter(|interval| !interval.is_zero());
+        self
+    }
+
     pub fn with_memory(mut self, memory_max: String) -> Self {
         self.config.memory_max = memory_max;
         self
     }
 
+    /// Sets `memory.high`, above which the task is throttled and reclaimed
+    /// rather than killed.
+    pub fn with_memory_high(mut self, memory_high: String) -> Self {
+        self.config.memory_high = Some(memory_high);
+        self
+    }
+
+    /// Sets `memory.swap.max`; defaults to `0` so tasks never swap.
+    pub fn with_memory_swap(mut self, memory_swap_max: String) -> Self {
+        self.config.memory_swap_max = memory_swap_max;
+        self
+    }
+
+    /// Sets `memory.oom.group`, so an OOM kill takes down every process in
+    /// the task rather than the largest one. Enabled by default.
+    pub fn with_memory_oom_group(mut self, oom_group: bool) -> Self {
+        self.config.memory_oom_group = oom_group;
+        self
+    }
+
     pub fn with_pids(mut self, pids_max: u32) -> Self {
         self.config.pids_max = pids_max;
         self
     }
 
+    /// Adds `io.max` limits for a block device, replacing earlier limits for
+    /// the same device.
+    pub fn with_io_max(mut self, io_max: IoMax) -> Self {
+        self.config
+            .io_max
+            .retain(|limit| (limit.major, limit.minor) != (io_max.major, io_max.minor));
+        self.config.io_max.push(io_max);
+        self
+    }
+
+    /// Sets the proportional `io.weight` (1-10000, kernel default 100) that
+    /// shares disk time between tasks contending for the same device.
+    pub fn with_io_weight(mut self, io_weight: u16) -> Result<Self> {
+        if !(1..=10000).contains(&io_weight) {
+            return Err(FaberError::InvalidCgroupConfig {
+                details: format!("io.weight {io_weight} is outside 1-10000"),
+            });
+        }
+        self.config.io_weight = Some(io_weight);
+        Ok(self)
+    }
+
     pub fn build(self) -> CgroupConfig {
         self.config
     }
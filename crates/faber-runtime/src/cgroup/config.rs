@@ -1,18 +1,140 @@
-#[derive(Debug, Clone)]
+use std::{
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    path::Path,
+    time::Duration,
+};
+
+use super::cpuset::Cpuset;
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CgroupConfig {
     pub(crate) cpu_max: String,
+    pub(crate) cpu_time_budget: Option<Duration>,
+    pub(crate) idle_timeout: Option<Duration>,
     pub(crate) memory_max: String,
+    pub(crate) memory_high: Option<String>,
+    pub(crate) memory_swap_max: String,
+    pub(crate) memory_oom_group: bool,
     pub(crate) pids_max: u32,
+    pub(crate) io_max: Vec<IoMax>,
+    pub(crate) io_weight: Option<u16>,
+    pub(crate) cpuset: Option<Cpuset>,
+    pub(crate) sample_interval: Option<Duration>,
 }
 
 impl Default for CgroupConfig {
     fn default() -> Self {
         Self {
             cpu_max: "50000 100000".to_string(),
+            cpu_time_budget: None,
+            idle_timeout: None,
             // Use "max" to inherit parent's memory limit without additional constraints
             // This prevents ENOMEM when running inside Docker with memory limits
             memory_max: "max".to_string(),
+            memory_high: None,
+            // Swapping would let a task exceed memory.max without being
+            // killed, hiding memory-limit-exceeded behind slowness.
+            memory_swap_max: "0".to_string(),
+            memory_oom_group: true,
             pids_max: 64,
+            io_max: Vec::new(),
+            io_weight: None,
+            cpuset: None,
+            // Sampling wakes the supervisor of every task, so it is opt-in.
+            sample_interval: None,
+        }
+    }
+}
+
+impl CgroupConfig {
+    /// Whether task cgroups created from `other` get the same limits apart
+    /// from their CPUs, which can be changed once a cgroup exists.
+    pub(crate) fn same_shape(&self, other: &Self) -> bool {
+        let strip = |config: &Self| Self {
+            cpuset: None,
+            ..config.clone()
+        };
+        strip(self) == strip(other)
+    }
+}
+
+/// Bandwidth and IOPS limits for one block device, written to `io.max`.
+///
+/// Limits that are not set stay unlimited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoMax {
+    pub(crate) major: u32,
+    pub(crate) minor: u32,
+    pub(crate) read_bps: Option<u64>,
+    pub(crate) write_bps: Option<u64>,
+    pub(crate) read_iops: Option<u64>,
+    pub(crate) write_iops: Option<u64>,
+}
+
+impl IoMax {
+    /// Limits the block device with the given device numbers, e.g. `8:0`.
+    pub fn new(major: u32, minor: u32) -> Self {
+        Self {
+            major,
+            minor,
+            read_bps: None,
+            write_bps: None,
+            read_iops: None,
+            write_iops: None,
+        }
+    }
+
+    /// Limits the block device behind the device node `path`, e.g. `/dev/sda`.
+    pub fn for_device(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let metadata = std::fs::metadata(path).map_err(|e| FaberError::InvalidCgroupConfig {
+            details: format!("cannot inspect device {}: {e}", path.display()),
+        })?;
+        if !metadata.file_type().is_block_device() {
+            return Err(FaberError::InvalidCgroupConfig {
+                details: format!("{} is not a block device", path.display()),
+            });
         }
+
+        let device = metadata.rdev();
+        Ok(Self::new(
+            nix::sys::stat::major(device) as u32,
+            nix::sys::stat::minor(device) as u32,
+        ))
+    }
+
+    pub fn read_bps(mut self, bytes_per_second: u64) -> Self {
+        self.read_bps = Some(bytes_per_second);
+        self
+    }
+
+    pub fn write_bps(mut self, bytes_per_second: u64) -> Self {
+        self.write_bps = Some(bytes_per_second);
+        self
+    }
+
+    pub fn read_iops(mut self, ops_per_second: u64) -> Self {
+        self.read_iops = Some(ops_per_second);
+        self
+    }
+
+    pub fn write_iops(mut self, ops_per_second: u64) -> Self {
+        self.write_iops = Some(ops_per_second);
+        self
+    }
+
+    /// Formats the limits as an `io.max` line, e.g. `8:0 rbps=1048576 ...`.
+    pub(crate) fn to_cgroup_line(&self) -> String {
+        let limit = |value: Option<u64>| value.map_or("max".to_string(), |v| v.to_string());
+        format!(
+            "{}:{} rbps={} wbps={} riops={} wiops={}",
+            self.major,
+            self.minor,
+            limit(self.read_bps),
+            limit(self.write_bps),
+            limit(self.read_iops),
+            limit(self.write_iops)
+        )
     }
 }
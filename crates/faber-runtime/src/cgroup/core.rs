@@ -1,10 +1,14 @@
 use std::fs::{create_dir_all, read_dir, read_to_string, remove_dir, write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tracing::{debug, warn};
 
-use super::{config::CgroupConfig, task::TaskCgroup};
+use super::{
+    config::CgroupConfig,
+    cpuset::{Cpuset, CpusetPartition, format_cpu_list},
+    task::TaskCgroup,
+};
 use crate::prelude::*;
 
 static CGROUP_INITIALIZED: AtomicBool = AtomicBool::new(false);
@@ -14,21 +18,68 @@ static FABER_CGROUP_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
 #[derive(Debug, Clone, Default)]
 pub struct Cgroup {
     config: CgroupConfig,
+    scope: Option<String>,
 }
 
 impl Cgroup {
     pub fn new(config: CgroupConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            scope: None,
+        }
+    }
+
+    /// Names this execution's task cgroups after `scope` instead of the
+    /// creating process, so they can be found and killed from outside it.
+    pub(crate) fn scoped(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Sends SIGKILL to every process in the task cgroups of `scope`.
+    pub(crate) fn kill_scope(scope: &str) {
+        for task_cgroup in Self::scope_task_cgroups(scope) {
+            task_cgroup.signal_all_processes();
+            // Removal is left to the execution itself or remove_scope().
+            task_cgroup.release();
+        }
+    }
+
+    /// Kills every process in the task cgroups of `scope` and removes them.
+    pub(crate) fn remove_scope(scope: &str) {
+        for task_cgroup in Self::scope_task_cgroups(scope) {
+            if let Err(e) = task_cgroup.cleanup() {
+                warn!("Failed to remove task cgroup: {}", e);
+            }
+        }
+    }
+
+    fn scope_task_cgroups(scope: &str) -> Vec<TaskCgroup> {
+        let Ok(faber_cgroup_path) = Self::get_faber_cgroup_path() else {
+            return Vec::new();
+        };
+        let prefix = format!("task-{scope}-");
+
+        read_dir(&faber_cgroup_path)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+            })
+            .map(TaskCgroup::open)
+            .collect()
     }
 
     pub fn ensure_faber_cgroup_hierarchy() -> Result<()> {
         if CGROUP_INITIALIZED.load(Ordering::SeqCst) {
-            if let Ok(guard) = CGROUP_INIT_ERROR.lock() {
-                if let Some(error) = guard.as_ref() {
-                    return Err(FaberError::Generic {
-                        message: format!("Cgroup initialization previously failed: {}", error),
-                    });
-                }
+            if let Ok(guard) = CGROUP_INIT_ERROR.lock()
+                && let Some(error) = guard.as_ref()
+            {
+                return Err(FaberError::Generic {
+                    message: format!("Cgroup initialization previously failed: {}", error),
+                });
             }
             return Ok(());
         }
@@ -47,33 +98,63 @@ impl Cgroup {
     /// Returns the resolved faber cgroup path (e.g. /sys/fs/cgroup/.../faber).
     /// Must be called after ensure_faber_cgroup_hierarchy().
     pub fn get_faber_cgroup_path() -> Result<PathBuf> {
-        if let Ok(guard) = FABER_CGROUP_PATH.lock() {
-            if let Some(path) = guard.as_ref() {
-                return Ok(path.clone());
-            }
+        if let Ok(guard) = FABER_CGROUP_PATH.lock()
+            && let Some(path) = guard.as_ref()
+        {
+            return Ok(path.clone());
         }
         Err(FaberError::Generic {
             message: "Faber cgroup hierarchy not initialized".to_string(),
         })
     }
 
-    fn controllers_already_enabled(subtree_control_path: &PathBuf) -> bool {
-        let required = ["cpu", "memory", "pids"];
+    /// Why setting up the faber cgroup failed, if it was tried and did.
+    pub(crate) fn initialization_error() -> Option<String> {
+        CGROUP_INIT_ERROR.lock().ok()?.clone()
+    }
+
+    /// Where the faber cgroup is, or will be created, without creating it.
+    pub(crate) fn inspect_faber_cgroup_path() -> Result<PathBuf> {
+        Self::get_faber_cgroup_path().or_else(|_| Ok(Self::detect_own_cgroup_path()?.join("faber")))
+    }
+
+    /// Controllers delegated to task cgroups. `io` and `cpuset` are only
+    /// enabled where the parent offers them, so hosts without them keep
+    /// working without I/O limits or CPU pinning.
+    fn wanted_controllers(subtree_control_path: &Path) -> Vec<&'static str> {
+        let available = read_to_string(subtree_control_path.with_file_name("cgroup.controllers"))
+            .unwrap_or_default();
+        let mut wanted = vec!["cpu", "memory", "pids"];
+        for optional in ["io", "cpuset"] {
+            if available.split_whitespace().any(|c| c == optional) {
+                wanted.push(optional);
+            }
+        }
+        wanted
+    }
+
+    fn controllers_already_enabled(subtree_control_path: &Path) -> bool {
+        let required = Self::wanted_controllers(subtree_control_path);
         match read_to_string(subtree_control_path) {
             Ok(content) => {
-                let enabled: Vec<&str> = content.trim().split_whitespace().collect();
+                let enabled: Vec<&str> = content.split_whitespace().collect();
                 required.iter().all(|r| enabled.contains(r))
             }
             Err(_) => false,
         }
     }
 
-    fn enable_controllers(path: &PathBuf, context: &str) -> Result<()> {
+    fn enable_controllers(path: &Path, context: &str) -> Result<()> {
         if Self::controllers_already_enabled(path) {
             return Ok(());
         }
 
-        write(path, "+cpu +memory +pids")
+        let controllers = Self::wanted_controllers(path)
+            .iter()
+            .map(|controller| format!("+{controller}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write(path, controllers)
             .or_else(|e| {
                 if e.raw_os_error() == Some(16) {
                     return Ok(());
@@ -170,27 +251,22 @@ impl Cgroup {
         Ok(())
     }
 
-    fn cleanup_stale_task_cgroups(faber_cgroup_path: &PathBuf) {
+    fn cleanup_stale_task_cgroups(faber_cgroup_path: &Path) {
         if let Ok(entries) = read_dir(faber_cgroup_path) {
             for entry in entries.filter_map(|e| e.ok()) {
                 let path = entry.path();
-                if path.is_dir() {
-                    if let Some(name) = path.file_name() {
-                        if name.to_string_lossy().starts_with("task-") {
-                            let procs_path = path.join("cgroup.procs");
-                            if let Ok(procs) = read_to_string(&procs_path) {
-                                if procs.trim().is_empty() {
-                                    if let Err(e) = remove_dir(&path) {
-                                        warn!(
-                                            "Failed to cleanup stale cgroup {}: {}",
-                                            path.display(),
-                                            e
-                                        );
-                                    } else {
-                                        debug!("Cleaned up stale cgroup: {}", path.display());
-                                    }
-                                }
-                            }
+                if path.is_dir()
+                    && let Some(name) = path.file_name()
+                    && name.to_string_lossy().starts_with("task-")
+                {
+                    let procs_path = path.join("cgroup.procs");
+                    if let Ok(procs) = read_to_string(&procs_path)
+                        && procs.trim().is_empty()
+                    {
+                        if let Err(e) = remove_dir(&path) {
+                            warn!("Failed to cleanup stale cgroup {}: {}", path.display(), e);
+                        } else {
+                            debug!("Cleaned up stale cgroup: {}", path.display());
                         }
                     }
                 }
@@ -198,7 +274,7 @@ impl Cgroup {
         }
     }
 
-    fn setup_faber_cgroup_limits(faber_cgroup_path: &PathBuf) -> Result<()> {
+    fn setup_faber_cgroup_limits(faber_cgroup_path: &Path) -> Result<()> {
         let memory_max_path = faber_cgroup_path.join("memory.max");
         if let Err(e) = write(&memory_max_path, "max") {
             debug!(
@@ -218,7 +294,67 @@ impl Cgroup {
         Ok(())
     }
 
+    /// Makes the faber cgroup a `partition` owning `cpus`, taking them away
+    /// from every cgroup outside it.
+    pub(crate) fn partition_cpus(cpus: &[u32], partition: CpusetPartition) -> Result<()> {
+        Self::ensure_faber_cgroup_hierarchy()?;
+        let faber_cgroup_path = Self::get_faber_cgroup_path()?;
+        let cpu_list = format_cpu_list(cpus);
+
+        write(faber_cgroup_path.join("cpuset.cpus"), &cpu_list).map_err(|e| {
+            FaberError::WriteFile {
+                e,
+                details: format!(
+                    "Failed to assign CPUs '{}' to the faber cgroup (is the cpuset controller available?)",
+                    cpu_list
+                ),
+            }
+        })?;
+        // Newer kernels require partition CPUs to be claimed exclusively first.
+        let exclusive_path = faber_cgroup_path.join("cpuset.cpus.exclusive");
+        if exclusive_path.exists() {
+            write(&exclusive_path, &cpu_list).map_err(|e| FaberError::WriteFile {
+                e,
+                details: format!("Failed to claim CPUs '{}' exclusively", cpu_list),
+            })?;
+        }
+
+        let value = partition.as_cgroup_value();
+        let partition_path = faber_cgroup_path.join("cpuset.cpus.partition");
+        write(&partition_path, value).map_err(|e| FaberError::WriteFile {
+            e,
+            details: format!("Failed to make the faber cgroup a '{}' partition", value),
+        })?;
+        // An invalid partition is accepted by the write and reported on read,
+        // e.g. "root invalid (Parent is not a partition root)".
+        let state = read_to_string(&partition_path).unwrap_or_default();
+        if state.trim() != value {
+            return Err(FaberError::InvalidCgroupConfig {
+                details: format!(
+                    "cpuset partition for CPUs '{}' was rejected: {}",
+                    cpu_list,
+                    state.trim()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn config(&self) -> &CgroupConfig {
+        &self.config
+    }
+
+    /// Confines task cgroups created from now on to `cpuset`.
+    pub(crate) fn with_cpuset(mut self, cpuset: Option<Cpuset>) -> Self {
+        self.config.cpuset = cpuset;
+        self
+    }
+
     pub fn create_task_cgroup(&self) -> Result<TaskCgroup> {
-        TaskCgroup::new(self.config.clone())
+        let scope = self
+            .scope
+            .clone()
+            .unwrap_or_else(|| std::process::id().to_string());
+        TaskCgroup::new(self.config.clone(), &scope)
     }
 }
@@ -0,0 +1,284 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::core::Cgroup;
+use crate::prelude::*;
+
+/// How the faber cgroup holds the allocator's CPUs, written to its
+/// `cpuset.cpus.partition`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CpusetPartition {
+    /// Executions are pinned to their CPUs, which processes outside Faber
+    /// may still use.
+    #[default]
+    Member,
+    /// A partition root: the CPUs are removed from every cgroup outside
+    /// Faber, including the API server's.
+    Root,
+    /// Like `Root`, and the CPUs are also taken out of scheduler load
+    /// balancing.
+    Isolated,
+}
+
+impl CpusetPartition {
+    pub(crate) fn as_cgroup_value(self) -> &'static str {
+        match self {
+            Self::Member => "member",
+            Self::Root => "root",
+            Self::Isolated => "isolated",
+        }
+    }
+}
+
+impl std::str::FromStr for CpusetPartition {
+    type Err = FaberError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "member" => Ok(Self::Member),
+            "root" => Ok(Self::Root),
+            "isolated" => Ok(Self::Isolated),
+            other => Err(FaberError::InvalidCgroupConfig {
+                details: format!("unknown cpuset partition '{other}'"),
+            }),
+        }
+    }
+}
+
+/// The CPUs an execution's task cgroups are confined to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Cpuset {
+    pub(crate) cpus: Vec<u32>,
+}
+
+impl Cpuset {
+    /// Formats the CPUs as a `cpuset.cpus` list, e.g. `2,3`.
+    pub(crate) fn cpu_list(&self) -> String {
+        format_cpu_list(&self.cpus)
+    }
+}
+
+pub(crate) fn format_cpu_list(cpus: &[u32]) -> String {
+    cpus.iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parses a kernel CPU list such as `0-3,6`.
+pub(crate) fn parse_cpu_list(list: &str) -> Result<Vec<u32>> {
+    let invalid = || FaberError::InvalidCgroupConfig {
+        details: format!("invalid CPU list '{list}'"),
+    };
+
+    let mut cpus = BTreeSet::new();
+    for range in list.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        let (first, last) = match range.split_once('-') {
+            Some((first, last)) => (first, last),
+            None => (range, range),
+        };
+        let first: u32 = first.trim().parse().map_err(|_| invalid())?;
+        let last: u32 = last.trim().parse().map_err(|_| invalid())?;
+        if first > last {
+            return Err(invalid());
+        }
+        cpus.extend(first..=last);
+    }
+    Ok(cpus.into_iter().collect())
+}
+
+/// Hands out exclusive CPUs to concurrent executions.
+///
+/// The CPUs are split into slots of `cores_per_execution`; an execution
+/// holds a slot for its whole lifetime, and executions that find every slot
+/// taken wait for one to be released instead of sharing cores.
+pub struct CpuAllocator {
+    free: Mutex<BTreeSet<u32>>,
+    slots: Arc<Semaphore>,
+    total_slots: usize,
+    cores_per_execution: usize,
+    waiting: AtomicUsize,
+}
+
+/// Counters describing how contended the CPU allocator is.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CpuAllocatorStats {
+    /// Executions that can hold CPUs at the same time.
+    pub slots: usize,
+    /// Slots not held by any execution.
+    pub free: usize,
+    /// Executions waiting for a slot.
+    pub waiting: usize,
+}
+
+impl CpuAllocator {
+    /// Waits until `cores_per_execution` CPUs are free and leases them.
+    pub async fn acquire(self: &Arc<Self>) -> CpuLease {
+        let waiting = Waiting::new(&self.waiting);
+        let permit = self.slots.clone().acquire_owned().await;
+        drop(waiting);
+        // The semaphore is never closed.
+        let permit = permit.expect("CPU allocator semaphore closed");
+
+        let cpus = {
+            let mut free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+            let cpus: Vec<u32> = free
+                .iter()
+                .take(self.cores_per_execution)
+                .copied()
+                .collect();
+            for cpu in &cpus {
+                free.remove(cpu);
+            }
+            cpus
+        };
+
+        CpuLease {
+            cpuset: Cpuset { cpus },
+            allocator: self.clone(),
+            _permit: permit,
+        }
+    }
+
+    pub fn stats(&self) -> CpuAllocatorStats {
+        CpuAllocatorStats {
+            slots: self.total_slots,
+            free: self.slots.available_permits(),
+            waiting: self.waiting.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counts an execution as waiting for as long as it lives, so that a caller
+/// dropping [`CpuAllocator::acquire`] before it completes is not counted
+/// forever.
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    fn new(waiting: &'a AtomicUsize) -> Self {
+        waiting.fetch_add(1, Ordering::Relaxed);
+        Self(waiting)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl std::fmt::Debug for CpuAllocator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CpuAllocator")
+            .field("cores_per_execution", &self.cores_per_execution)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// CPUs held by one execution; they return to the allocator on drop.
+pub struct CpuLease {
+    cpuset: Cpuset,
+    allocator: Arc<CpuAllocator>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl CpuLease {
+    pub fn cpus(&self) -> &[u32] {
+        &self.cpuset.cpus
+    }
+
+    pub(crate) fn cpuset(&self) -> &Cpuset {
+        &self.cpuset
+    }
+}
+
+impl Drop for CpuLease {
+    fn drop(&mut self) {
+        // Return the CPUs before the permit so the next holder finds them.
+        let mut free = self
+            .allocator
+            .free
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        free.extend(self.cpuset.cpus.iter().copied());
+    }
+}
+
+pub struct CpuAllocatorBuilder {
+    cpus: Vec<u32>,
+    cores_per_execution: usize,
+    partition: CpusetPartition,
+}
+
+impl Default for CpuAllocatorBuilder {
+    fn default() -> Self {
+        Self {
+            cpus: Vec::new(),
+            cores_per_execution: 1,
+            partition: CpusetPartition::default(),
+        }
+    }
+}
+
+impl CpuAllocatorBuilder {
+    /// Sets the CPUs to hand out, e.g. all but the ones serving the API.
+    pub fn with_cpus(mut self, cpus: Vec<u32>) -> Self {
+        self.cpus = cpus;
+        self
+    }
+
+    /// Sets the CPUs to hand out from a kernel CPU list such as `2-7`.
+    pub fn with_cpu_list(self, list: &str) -> Result<Self> {
+        Ok(self.with_cpus(parse_cpu_list(list)?))
+    }
+
+    pub fn with_cores_per_execution(mut self, cores_per_execution: usize) -> Self {
+        self.cores_per_execution = cores_per_execution;
+        self
+    }
+
+    pub fn with_partition(mut self, partition: CpusetPartition) -> Self {
+        self.partition = partition;
+        self
+    }
+
+    /// Creates the allocator. Unless the partition is `Member`, this first
+    /// turns the faber cgroup into a partition owning the CPUs, which
+    /// requires the cgroup above it to be a partition root too.
+    pub fn build(self) -> Result<Arc<CpuAllocator>> {
+        let free: BTreeSet<u32> = self.cpus.into_iter().collect();
+        if self.cores_per_execution == 0 || free.len() < self.cores_per_execution {
+            return Err(FaberError::InvalidCgroupConfig {
+                details: format!(
+                    "{} CPUs cannot be split into slots of {} cores",
+                    free.len(),
+                    self.cores_per_execution
+                ),
+            });
+        }
+
+        if self.partition != CpusetPartition::Member {
+            let cpus: Vec<u32> = free.iter().copied().collect();
+            Cgroup::partition_cpus(&cpus, self.partition)?;
+        }
+
+        let total_slots = free.len() / self.cores_per_execution;
+        Ok(Arc::new(CpuAllocator {
+            free: Mutex::new(free),
+            slots: Arc::new(Semaphore::new(total_slots)),
+            total_slots,
+            cores_per_execution: self.cores_per_execution,
+            waiting: AtomicUsize::new(0),
+        }))
+    }
+}
@@ -1,9 +1,15 @@
 mod builder;
 mod config;
 mod core;
+mod cpuset;
 pub(crate) mod task;
+mod timeline;
 
 pub(crate) use config::CgroupConfig;
 pub(crate) use core::Cgroup;
+pub(crate) use cpuset::Cpuset;
+pub use cpuset::{CpuAllocator, CpuAllocatorBuilder, CpuAllocatorStats, CpuLease, CpusetPartition};
+pub(crate) use timeline::ResourceTimeline;
 
 pub use builder::CgroupConfigBuilder;
+pub use config::IoMax;
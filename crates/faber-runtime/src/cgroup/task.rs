@@ -4,8 +4,11 @@ use std::path::PathBuf;
 use std::thread;
 use std::time::Duration;
 
-use super::config::CgroupConfig;
+use nix::sys::signal::Signal;
+
+use super::{config::CgroupConfig, cpuset::Cpuset};
 use crate::prelude::*;
+use crate::result::{PressureStall, ResourceSample, TaskPressure};
 use crate::task::{TaskCgroupEvents, TaskStats};
 use crate::utils::generate_random_string;
 
@@ -37,11 +40,10 @@ impl Drop for TaskCgroup {
 }
 
 impl TaskCgroup {
-    pub fn new(config: CgroupConfig) -> Result<Self> {
+    pub fn new(config: CgroupConfig, scope: &str) -> Result<Self> {
         let faber_cgroup_path = super::core::Cgroup::get_faber_cgroup_path()?;
         let task_id = generate_random_string(16);
-        let task_cgroup_path =
-            faber_cgroup_path.join(format!("task-{}-{task_id}", std::process::id()));
+        let task_cgroup_path = faber_cgroup_path.join(format!("task-{scope}-{task_id}"));
 
         create_dir_all(&task_cgroup_path).map_err(|e| FaberError::CreateDir {
             e,
@@ -59,6 +61,21 @@ impl TaskCgroup {
         Ok(task_cgroup)
     }
 
+    /// Takes over an existing task cgroup, e.g. one left behind by a killed
+    /// execution, so that it can be cleaned up.
+    pub(crate) fn open(task_cgroup_path: PathBuf) -> Self {
+        Self {
+            task_cgroup_path,
+            config: CgroupConfig::default(),
+            cleaned: false,
+        }
+    }
+
+    /// Drops this handle without removing the cgroup.
+    pub(crate) fn release(mut self) {
+        self.cleaned = true;
+    }
+
     pub fn add_process(&self, pid: u32) -> Result<()> {
         let cgroup_procs_path = self.task_cgroup_path.join("cgroup.procs");
         let pid_str = pid.to_string();
@@ -102,6 +119,10 @@ impl TaskCgroup {
             memory_peak_bytes = value;
         }
 
+        // Read once more after the task exited: page cache charged to the
+        // task outlives it, so this can exceed anything sampled while it ran.
+        let (memory_anon_bytes, memory_file_bytes) = self.memory_breakdown();
+
         let pids_max_path = self.task_cgroup_path.join("pids.peak");
         if let Ok(content) = read_to_string(&pids_max_path)
             && let Ok(value) = content.trim().parse::<u64>()
@@ -109,18 +130,149 @@ impl TaskCgroup {
             pids_max = value;
         }
 
+        // io.stat has a line per device, e.g. "8:0 rbytes=4096 wbytes=0 rios=1
+        // wios=0 dbytes=0 dios=0"; tmpfs I/O does not appear in it.
+        let (mut io_read_bytes, mut io_write_bytes, mut io_read_ops, mut io_write_ops) =
+            (0u64, 0u64, 0u64, 0u64);
+        let io_stat_path = self.task_cgroup_path.join("io.stat");
+        if let Ok(contents) = read_to_string(&io_stat_path) {
+            for field in contents
+                .lines()
+                .flat_map(|line| line.split_whitespace().skip(1))
+            {
+                if let Some((key, value)) = field.split_once('=')
+                    && let Ok(value) = value.parse::<u64>()
+                {
+                    match key {
+                        "rbytes" => io_read_bytes += value,
+                        "wbytes" => io_write_bytes += value,
+                        "rios" => io_read_ops += value,
+                        "wios" => io_write_ops += value,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
         Ok(TaskStats {
             cpu_usage_usec,
             cpu_nr_throttled,
             cpu_throttled_usec,
             memory_peak_bytes,
+            memory_anon_bytes,
+            memory_file_bytes,
             pids_max,
+            io_read_bytes,
+            io_write_bytes,
+            io_read_ops,
+            io_write_ops,
+        })
+    }
+
+    /// Stall totals over the cgroup's lifetime, with averages relative to
+    /// `elapsed`. `None` if the kernel was built without PSI.
+    pub(crate) fn measure_pressure(&self, elapsed: Duration) -> Option<TaskPressure> {
+        let elapsed_usec = elapsed.as_micros().max(1) as f64;
+        let stall = |resource: &str| {
+            let (some_usec, full_usec) = self.pressure_totals(resource)?;
+            Some(PressureStall {
+                some_avg: (some_usec as f64 / elapsed_usec * 100.0).min(100.0),
+                full_avg: (full_usec as f64 / elapsed_usec * 100.0).min(100.0),
+                some_usec,
+                full_usec,
+            })
+        };
+        Some(TaskPressure {
+            cpu: stall("cpu")?,
+            memory: stall("memory")?,
+            io: stall("io")?,
         })
     }
 
+    /// Reads the current usage for a timeline sample taken `elapsed` into
+    /// the task.
+    pub(crate) fn sample(&self, elapsed: Duration) -> ResourceSample {
+        let current = |file_name: &str| {
+            read_to_string(self.task_cgroup_path.join(file_name))
+                .ok()
+                .and_then(|contents| contents.trim().parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+        let stall = |resource: &str| self.pressure_totals(resource).map_or(0, |(some, _)| some);
+        ResourceSample {
+            elapsed_ms: elapsed.as_millis() as u64,
+            memory_bytes: current("memory.current"),
+            pids: current("pids.current"),
+            cpu_usage_usec: self.event_value("cpu.stat", "usage_usec"),
+            cpu_stall_usec: stall("cpu"),
+            memory_stall_usec: stall("memory"),
+            io_stall_usec: stall("io"),
+        }
+    }
+
+    /// The anonymous and page-cache bytes currently charged to the task, from
+    /// `memory.stat`.
+    pub(crate) fn memory_breakdown(&self) -> (u64, u64) {
+        let (mut anon, mut file) = (0, 0);
+        if let Ok(contents) = read_to_string(self.task_cgroup_path.join("memory.stat")) {
+            for line in contents.lines() {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.len() == 2
+                    && let Ok(value) = parts[1].parse::<u64>()
+                {
+                    match parts[0] {
+                        "anon" => anon = value,
+                        "file" => file = value,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        (anon, file)
+    }
+
+    /// The `some` and `full` stall totals of `<resource>.pressure`, e.g.
+    /// "some avg10=0.00 avg60=0.00 avg300=0.00 total=1234".
+    fn pressure_totals(&self, resource: &str) -> Option<(u64, u64)> {
+        let contents =
+            read_to_string(self.task_cgroup_path.join(format!("{resource}.pressure"))).ok()?;
+        let total = |kind: &str| {
+            contents
+                .lines()
+                .filter(|line| line.starts_with(kind))
+                .flat_map(|line| line.split_whitespace())
+                .find_map(|field| field.strip_prefix("total=")?.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+        Some((total("some"), total("full")))
+    }
+
+    /// How often to sample the task for its timeline, if at all.
+    pub(crate) fn sample_interval(&self) -> Option<Duration> {
+        self.config.sample_interval
+    }
+
+    /// The CPU time the whole task may use, if limited.
+    pub(crate) fn cpu_time_budget(&self) -> Option<Duration> {
+        self.config.cpu_time_budget
+    }
+
+    /// How long the task may go without progress, if limited.
+    pub(crate) fn idle_timeout(&self) -> Option<Duration> {
+        self.config.idle_timeout
+    }
+
+    /// CPU time used so far by every process in the cgroup.
+    pub(crate) fn cpu_usage(&self) -> Duration {
+        Duration::from_micros(self.event_value("cpu.stat", "usage_usec"))
+    }
+
     pub fn measure_events(&self) -> TaskCgroupEvents {
         TaskCgroupEvents {
             oom_kill_count: self.event_value("memory.events", "oom_kill"),
+            oom_count: self.event_value("memory.events", "oom"),
+            memory_high_count: self.event_value("memory.events", "high"),
+            memory_max_count: self.event_value("memory.events", "max"),
             pids_limit_hit_count: self.event_value("pids.events", "max"),
         }
     }
@@ -154,17 +306,7 @@ impl TaskCgroup {
     pub(crate) fn kill_all_processes(&self) -> Result<()> {
         let procs_path = self.task_cgroup_path.join("cgroup.procs");
 
-        if let Ok(file) = File::open(&procs_path) {
-            let reader = BufReader::new(file);
-            for line in reader.lines().map_while(|line| line.ok()) {
-                if let Ok(pid) = line.trim().parse::<i32>() {
-                    let _ = nix::sys::signal::kill(
-                        nix::unistd::Pid::from_raw(pid),
-                        nix::sys::signal::Signal::SIGKILL,
-                    );
-                }
-            }
-        }
+        self.signal_all_processes();
 
         let mut attempts = 0;
         while attempts < 50 {
@@ -182,6 +324,31 @@ impl TaskCgroup {
         Ok(())
     }
 
+    /// Sends SIGKILL to every process in the cgroup without waiting for them
+    /// to exit. Uses `cgroup.kill` where available, which also catches
+    /// processes forked while the cgroup is being killed.
+    pub(crate) fn signal_all_processes(&self) {
+        if write(self.task_cgroup_path.join("cgroup.kill"), "1").is_ok() {
+            return;
+        }
+        self.signal_processes(Signal::SIGKILL);
+    }
+
+    /// Sends `signal` to every process currently in the cgroup, e.g. to ask
+    /// them to exit before they are killed.
+    pub(crate) fn signal_processes(&self, signal: Signal) {
+        let procs_path = self.task_cgroup_path.join("cgroup.procs");
+
+        if let Ok(file) = File::open(&procs_path) {
+            let reader = BufReader::new(file);
+            for line in reader.lines().map_while(|line| line.ok()) {
+                if let Ok(pid) = line.trim().parse::<i32>() {
+                    let _ = nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), signal);
+                }
+            }
+        }
+    }
+
     fn event_value(&self, file_name: &str, key: &str) -> u64 {
         read_to_string(self.task_cgroup_path.join(file_name))
             .ok()
@@ -225,17 +392,59 @@ impl TaskCgroup {
             ),
         })?;
 
-        if memory_max_value != "max" {
-            let memory_swap_max_path = self.task_cgroup_path.join("memory.swap.max");
-            write(&memory_swap_max_path, "0").map_err(|e| FaberError::WriteFile {
+        if let Some(memory_high) = &self.config.memory_high {
+            let memory_high_path = self.task_cgroup_path.join("memory.high");
+            let memory_high_value = self.parse_memory_string(memory_high)?.to_string();
+            write(&memory_high_path, &memory_high_value).map_err(|e| FaberError::WriteFile {
                 e,
                 details: format!(
-                    "Failed to disable task swap at {}",
-                    memory_swap_max_path.display()
+                    "Failed to write memory throttling limit '{}' to task cgroup at {}",
+                    memory_high_value,
+                    memory_high_path.display()
                 ),
             })?;
         }
 
+        let memory_swap_max_path = self.task_cgroup_path.join("memory.swap.max");
+        let memory_swap_max_value = if self.config.memory_swap_max == "max" {
+            "max".to_string()
+        } else {
+            self.parse_memory_string(&self.config.memory_swap_max)?
+                .to_string()
+        };
+        match write(&memory_swap_max_path, &memory_swap_max_value) {
+            Ok(()) => {}
+            // Without swap accounting there is no file to write, which only
+            // matters if there is swap to escape into.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !Self::swap_active() => {}
+            Err(e) => {
+                return Err(FaberError::WriteFile {
+                    e,
+                    details: format!(
+                        "Failed to write swap limit '{}' to task cgroup at {}",
+                        memory_swap_max_value,
+                        memory_swap_max_path.display()
+                    ),
+                });
+            }
+        }
+
+        let memory_oom_group_path = self.task_cgroup_path.join("memory.oom.group");
+        let memory_oom_group_value = if self.config.memory_oom_group {
+            "1"
+        } else {
+            "0"
+        };
+        write(&memory_oom_group_path, memory_oom_group_value).map_err(|e| {
+            FaberError::WriteFile {
+                e,
+                details: format!(
+                    "Failed to configure OOM group kills at {}",
+                    memory_oom_group_path.display()
+                ),
+            }
+        })?;
+
         let pids_max_path = self.task_cgroup_path.join("pids.max");
         let pids_max_value = self.config.pids_max.to_string();
 
@@ -247,9 +456,59 @@ impl TaskCgroup {
             ),
         })?;
 
+        if let Some(cpuset) = &self.config.cpuset {
+            self.apply_cpuset(cpuset)?;
+        }
+
+        // io.max takes one device per write.
+        let io_max_path = self.task_cgroup_path.join("io.max");
+        for io_max in &self.config.io_max {
+            let io_max_value = io_max.to_cgroup_line();
+            write(&io_max_path, &io_max_value).map_err(|e| FaberError::WriteFile {
+                e,
+                details: format!(
+                    "Failed to write I/O limit '{}' to task cgroup (is the io controller available?)",
+                    io_max_value
+                ),
+            })?;
+        }
+
+        if let Some(io_weight) = self.config.io_weight {
+            let io_weight_path = self.task_cgroup_path.join("io.weight");
+            write(&io_weight_path, format!("default {io_weight}")).map_err(|e| {
+                FaberError::WriteFile {
+                    e,
+                    details: format!(
+                        "Failed to write I/O weight '{}' to task cgroup (is the io controller available?)",
+                        io_weight
+                    ),
+                }
+            })?;
+        }
+
         Ok(())
     }
 
+    /// Whether any swap device or file is in use, per `/proc/swaps`.
+    fn swap_active() -> bool {
+        read_to_string("/proc/swaps").map_or(true, |swaps| swaps.lines().count() > 1)
+    }
+
+    /// Pins the cgroup to `cpuset`. Safe to call while the cgroup is still
+    /// empty, as with a pre-warmed container's first task.
+    pub(crate) fn apply_cpuset(&self, cpuset: &Cpuset) -> Result<()> {
+        let cpu_list = cpuset.cpu_list();
+        write(self.task_cgroup_path.join("cpuset.cpus"), &cpu_list).map_err(|e| {
+            FaberError::WriteFile {
+                e,
+                details: format!(
+                    "Failed to pin task cgroup to CPUs '{}' (is the cpuset controller available?)",
+                    cpu_list
+                ),
+            }
+        })
+    }
+
     fn parse_memory_string(&self, memory_str: &str) -> Result<u64> {
         let memory_str = memory_str.trim();
 
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use crate::result::ResourceSample;
+
+/// Most samples kept per task; longer runs are downsampled to fit.
+const MAX_SAMPLES: usize = 128;
+
+/// Collects a task's resource samples at a fixed interval, halving the
+/// resolution whenever the timeline fills up so it stays compact however long
+/// the task runs.
+pub(crate) struct ResourceTimeline {
+    interval: Duration,
+    next_at: Duration,
+    samples: Vec<ResourceSample>,
+}
+
+impl ResourceTimeline {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_at: interval,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Whether a sample is due `elapsed` into the task.
+    pub(crate) fn is_due(&self, elapsed: Duration) -> bool {
+        elapsed >= self.next_at
+    }
+
+    pub(crate) fn record(&mut self, sample: ResourceSample) {
+        if self.samples.len() == MAX_SAMPLES {
+            let mut index = 0;
+            self.samples.retain(|_| {
+                index += 1;
+                index % 2 == 0
+            });
+            self.interval *= 2;
+        }
+        self.next_at = Duration::from_millis(sample.elapsed_ms) + self.interval;
+        self.samples.push(sample);
+    }
+
+    pub(crate) fn into_samples(self) -> Vec<ResourceSample> {
+        self.samples
+    }
+}
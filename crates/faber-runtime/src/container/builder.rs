@@ -1,6 +1,11 @@
 use std::path::PathBuf;
 
-use super::config::ContainerConfig;
+use super::{
+    config::{ContainerConfig, OutputSpill},
+    environment::{Environment, EnvironmentSource},
+    mount::MountSpec,
+    workspace::WorkspaceTemplate,
+};
 
 #[derive(Default)]
 pub struct ContainerConfigBuilder {
@@ -24,6 +29,36 @@ impl ContainerConfigBuilder {
         self
     }
 
+    /// Adds an extra mount; later mounts may be nested below earlier ones.
+    pub fn with_mount(mut self, mount: MountSpec) -> Self {
+        self.config.mounts.push(mount);
+        self
+    }
+
+    /// Registers the host file `path` as stdin file `id`, which tasks read
+    /// by setting `stdin_file_id`. The file is opened by each task rather
+    /// than copied, so it can be arbitrarily large.
+    pub fn with_stdin_file(mut self, id: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.config.stdin_files.insert(id.into(), path.into());
+        self
+    }
+
+    pub fn with_rootfs(mut self, rootfs: PathBuf) -> Self {
+        self.config.rootfs = rootfs;
+        self
+    }
+
+    pub fn with_environment(mut self, environment: &Environment) -> Self {
+        match &environment.source {
+            EnvironmentSource::Rootfs(rootfs) => self.config.rootfs = rootfs.clone(),
+            EnvironmentSource::Image(image) => {
+                self.config.rootfs_layers = image.layers.clone();
+                self.config.image_digest = Some(image.digest.clone());
+            }
+        }
+        self
+    }
+
     pub fn with_tmpdir_size(mut self, tmpdir_size: String) -> Self {
         self.config.tmpdir_size = tmpdir_size;
         self
@@ -39,11 +74,36 @@ impl ContainerConfigBuilder {
         self
     }
 
+    pub fn with_workspace_template(mut self, template: &WorkspaceTemplate) -> Self {
+        self.config.workdir_layers = template.layers.clone();
+        self
+    }
+
     pub fn with_hostname(mut self, hostname: String) -> Self {
         self.config.hostname = hostname;
         self
     }
 
+    /// Confines tasks with a Landlock ruleset, where the kernel supports it,
+    /// so they can write only to the workdir and `/tmp` and read only the
+    /// toolchain directories and extra mounts, whatever the mount flags say.
+    pub fn with_landlock(mut self, landlock: bool) -> Self {
+        self.config.landlock = landlock;
+        self
+    }
+
+    /// Writes stdout or stderr longer than `inline_limit` bytes to a file in
+    /// the host directory `dir` as it is read, and returns the file's path,
+    /// size and digest in place of the output. The directory is created if
+    /// needed; the caller owns the files.
+    pub fn with_output_spill(mut self, dir: impl Into<PathBuf>, inline_limit: usize) -> Self {
+        self.config.output_spill = Some(OutputSpill {
+            dir: dir.into(),
+            inline_limit,
+        });
+        self
+    }
+
     pub fn build(self) -> ContainerConfig {
         self.config
     }
@@ -1,23 +1,51 @@
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
-use crate::utils::generate_random_string;
+use super::mount::MountSpec;
+use crate::{prelude::*, runtime::LandlockRules, task::Task, utils::generate_random_string};
 
+/// Host directory under which each container's root directory is created.
+pub(crate) const CONTAINERS_DIR: &str = "/tmp/faber";
+
+/// Directory inside the container where stdin files are bound, one per id.
+const STDIN_FILES_DIR: &str = "/.faber/stdin";
+
+#[derive(Clone)]
 pub struct ContainerConfig {
-    #[allow(dead_code)]
     pub(crate) id: String,
     pub(crate) container_root_dir: PathBuf,
+    pub(crate) rootfs: PathBuf,
+    pub(crate) rootfs_layers: Vec<PathBuf>,
+    pub(crate) image_digest: Option<String>,
     pub(crate) workdir: PathBuf,
+    pub(crate) workdir_layers: Vec<PathBuf>,
     pub(crate) tmpdir_size: String,
     pub(crate) workdir_size: String,
     pub(crate) bind_mounts_ro: Vec<&'static str>,
     pub(crate) bind_mounts_rw: Vec<&'static str>,
+    pub(crate) mounts: Vec<MountSpec>,
+    /// Host files tasks may read as stdin, by id.
+    pub(crate) stdin_files: BTreeMap<String, PathBuf>,
     pub(crate) hostname: String,
+    pub(crate) landlock: bool,
+    pub(crate) output_spill: Option<OutputSpill>,
+}
+
+/// A host directory that streams longer than `inline_limit` are written to
+/// while they are read, instead of being returned in the result.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct OutputSpill {
+    pub(crate) dir: PathBuf,
+    pub(crate) inline_limit: usize,
 }
 
 impl Default for ContainerConfig {
     fn default() -> Self {
         let id = generate_random_string(12);
-        let container_root_dir = PathBuf::from(format!("/tmp/faber/{}", id));
+        let container_root_dir = Path::new(CONTAINERS_DIR).join(&id);
+        let rootfs = PathBuf::from("/");
         let bind_mounts_ro = vec!["/bin", "/lib", "/lib64", "/usr"];
         let bind_mounts_rw = vec![""];
         let workdir = PathBuf::from("/faber");
@@ -28,12 +56,129 @@ impl Default for ContainerConfig {
         Self {
             id,
             container_root_dir,
+            rootfs,
+            rootfs_layers: Vec::new(),
+            image_digest: None,
             workdir,
+            workdir_layers: Vec::new(),
             tmpdir_size,
             workdir_size,
             bind_mounts_ro,
             bind_mounts_rw,
+            mounts: Vec::new(),
+            stdin_files: BTreeMap::new(),
             hostname,
+            landlock: false,
+            output_spill: None,
+        }
+    }
+}
+
+impl ContainerConfig {
+    /// Returns a copy of this configuration with its own id and root
+    /// directory, so that one shape can back many containers.
+    pub(crate) fn renewed(&self) -> Self {
+        let fresh = Self::default();
+        Self {
+            id: fresh.id,
+            container_root_dir: fresh.container_root_dir,
+            ..self.clone()
+        }
+    }
+
+    /// Whether two configurations build identical containers, ignoring the
+    /// per-container id and root directory.
+    pub(crate) fn same_shape(&self, other: &Self) -> bool {
+        self.rootfs == other.rootfs
+            && self.rootfs_layers == other.rootfs_layers
+            && self.image_digest == other.image_digest
+            && self.workdir == other.workdir
+            && self.workdir_layers == other.workdir_layers
+            && self.tmpdir_size == other.tmpdir_size
+            && self.workdir_size == other.workdir_size
+            && self.bind_mounts_ro == other.bind_mounts_ro
+            && self.bind_mounts_rw == other.bind_mounts_rw
+            && self.mounts == other.mounts
+            && self.stdin_files == other.stdin_files
+            && self.hostname == other.hostname
+            && self.landlock == other.landlock
+            && self.output_spill == other.output_spill
+    }
+
+    /// Whether tasks see the host filesystem unchanged, i.e. no environment,
+    /// workspace template or extra mounts were requested.
+    pub(crate) fn is_plain(&self) -> bool {
+        self.rootfs.as_os_str() == "/"
+            && self.rootfs_layers.is_empty()
+            && self.workdir_layers.is_empty()
+            && self.mounts.is_empty()
+    }
+
+    /// The layers, lowest first, assembled with overlayfs into the container
+    /// root. Empty when tasks run against the host's toolchain bind mounts;
+    /// an environment rootfs is a single layer, like a one-layer image.
+    pub(crate) fn root_layers(&self) -> Vec<PathBuf> {
+        if !self.rootfs_layers.is_empty() {
+            self.rootfs_layers.clone()
+        } else if self.rootfs != Path::new("/") {
+            vec![self.rootfs.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Where the stdin file `id` is bound inside the container.
+    pub(crate) fn stdin_file_target(id: &str) -> Result<PathBuf> {
+        if id.is_empty() || !id.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+            return Err(FaberError::InvalidTask {
+                details: format!("invalid stdin file id '{id}'"),
+            });
+        }
+        Ok(Path::new(STDIN_FILES_DIR).join(id))
+    }
+
+    /// Read-only bind mounts that expose the stdin files to tasks.
+    pub(crate) fn stdin_file_mounts(&self) -> Result<Vec<MountSpec>> {
+        self.stdin_files
+            .iter()
+            .map(|(id, source)| {
+                Ok(MountSpec::bind(source, Self::stdin_file_target(id)?)?.noexec(true))
+            })
+            .collect()
+    }
+
+    /// The host file `task` reads as stdin, if it names one.
+    pub(crate) fn stdin_file(&self, task: &Task) -> Result<Option<&Path>> {
+        let Some(id) = task.stdin_file_id()? else {
+            return Ok(None);
+        };
+        match self.stdin_files.get(id) {
+            Some(path) => Ok(Some(path)),
+            None => Err(FaberError::InvalidTask {
+                details: format!("stdin file '{id}' was not provided"),
+            }),
+        }
+    }
+
+    /// The Landlock ruleset for this container's tasks, if enabled: writes
+    /// only to the workdir and `/tmp`, reads from the toolchain directories
+    /// (the whole root for images and environments) and extra mounts.
+    pub(crate) fn landlock_rules(&self) -> Option<LandlockRules> {
+        if !self.landlock {
+            return None;
+        }
+
+        let mut rules = LandlockRules::default().write(&self.workdir).write("/tmp");
+        if self.root_layers().is_empty() {
+            for path in &self.bind_mounts_ro {
+                rules = rules.read(path, true);
+            }
+        } else {
+            rules = rules.read("/", true);
+        }
+        for mount in &self.mounts {
+            rules = rules.read(&mount.target, !mount.noexec);
         }
+        Some(rules)
     }
 }
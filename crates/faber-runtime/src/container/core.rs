@@ -1,20 +1,35 @@
 use std::{
     env::set_current_dir,
-    fs::{create_dir_all, remove_dir, remove_dir_all},
+    ffi::CString,
+    fs::{Permissions, create_dir_all, remove_dir, remove_dir_all, set_permissions},
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use nix::{
+    libc,
     mount::{MntFlags, MsFlags, mount, umount2},
     sched::CloneFlags,
     sched::unshare,
-    sys::stat::{Mode, SFlag, makedev, mknod},
     unistd::sethostname,
 };
 
+use super::{
+    mount::{MountKind, MountSpec},
+    overlay::mount_overlay,
+};
 use crate::{container::config::ContainerConfig, prelude::*};
 
-#[derive(Default)]
+/// The image root's writable layer only holds the container's mount points.
+const IMAGE_ROOT_SCRATCH_SIZE: &str = "1M";
+const WORKSPACE_SCRATCH_DIR: &str = "/.faber-workspace";
+
+/// Containers whose root directory could not be removed, across every
+/// runtime in this process.
+static CLEANUP_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Default, Clone)]
 pub struct Container {
     config: ContainerConfig,
 }
@@ -47,8 +62,13 @@ impl Container {
             details: "Failed to make the container mount namespace private".to_string(),
         })?;
 
-        self.rebind_new_root()?;
-        self.bind_mounts()?;
+        if self.config.root_layers().is_empty() {
+            self.rebind_new_root()?;
+            self.bind_mounts()?;
+        } else {
+            self.mount_image_root()?;
+        }
+        self.extra_mounts()?;
         self.bind_dev_devices()?;
         self.pivot_root()?;
         self.create_proc()?;
@@ -58,10 +78,19 @@ impl Container {
         self.change_hostname()?;
         self.create_workdir()?;
         self.unmount_oldroot()?;
+        self.seal_image_root()?;
 
         Ok(())
     }
 
+    pub(crate) fn config(&self) -> &ContainerConfig {
+        &self.config
+    }
+
+    pub(crate) fn image_digest(&self) -> Option<&str> {
+        self.config.image_digest.as_deref()
+    }
+
     pub(crate) fn cleanup(&self) -> Result<()> {
         // Unmount filesystems mounted in newroot before removing directory
         let _ = umount2(
@@ -77,8 +106,10 @@ impl Container {
             MntFlags::MNT_DETACH,
         );
         let _ = umount2(&self.config.container_root_dir, MntFlags::MNT_DETACH);
+        let _ = remove_dir_all(self.overlay_scratch_dir());
 
         remove_dir_all(&self.config.container_root_dir).map_err(|e| {
+            CLEANUP_FAILURES.fetch_add(1, Ordering::Relaxed);
             FaberError::RemoveContainerRootDir {
                 e,
                 details: "Failed to remove container root directory".to_string(),
@@ -87,6 +118,10 @@ impl Container {
         Ok(())
     }
 
+    pub(crate) fn cleanup_failures() -> u64 {
+        CLEANUP_FAILURES.load(Ordering::Relaxed)
+    }
+
     pub(crate) fn mask_paths() -> Result<()> {
         umount2("/sys", MntFlags::MNT_DETACH).map_err(|e| FaberError::Umount {
             e,
@@ -164,22 +199,163 @@ impl Container {
                 details: format!("Failed to bind mount {} to {:?}", source, target),
             })?;
 
-            mount(
-                None::<&str>,
-                target.as_os_str(),
-                None::<&str>,
-                MsFlags::MS_BIND
-                    | MsFlags::MS_REMOUNT
-                    | MsFlags::MS_RDONLY
-                    | MsFlags::MS_NOSUID
-                    | MsFlags::MS_NODEV,
-                None::<&str>,
-            )
-            .map_err(|e| FaberError::Mount {
-                e,
-                details: format!("Failed to remount {} read-only at {:?}", source, target),
-            })?;
+            set_mount_attributes_recursive(
+                &target,
+                MsFlags::MS_RDONLY | MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn extra_mounts(&self) -> Result<()> {
+        let stdin_file_mounts = self.config.stdin_file_mounts()?;
+        for spec in self.config.mounts.iter().chain(&stdin_file_mounts) {
+            let target = self.mount_target(spec)?;
+
+            let mut flags = MsFlags::empty();
+            flags.set(MsFlags::MS_RDONLY, spec.read_only);
+            flags.set(MsFlags::MS_NOSUID, spec.nosuid);
+            flags.set(MsFlags::MS_NODEV, spec.nodev);
+            flags.set(MsFlags::MS_NOEXEC, spec.noexec);
+
+            match &spec.kind {
+                MountKind::Bind(source) => {
+                    mount(
+                        Some(source),
+                        &target,
+                        None::<&str>,
+                        MsFlags::MS_BIND | MsFlags::MS_REC,
+                        None::<&str>,
+                    )
+                    .map_err(|e| FaberError::Mount {
+                        e,
+                        details: format!("Failed to bind mount {:?} to {:?}", source, target),
+                    })?;
+
+                    // A bind remount only changes the top mount; submounts of
+                    // the source must not stay writable or executable.
+                    set_mount_attributes_recursive(&target, flags)?;
+                }
+                MountKind::Tmpfs { size } => {
+                    let mount_options = format!("size={},mode=1777", size);
+                    mount(
+                        Some("tmpfs"),
+                        &target,
+                        Some("tmpfs"),
+                        flags,
+                        Some(mount_options.as_str()),
+                    )
+                    .map_err(|e| FaberError::Mount {
+                        e,
+                        details: format!("Failed to mount tmpfs to {:?}", target),
+                    })?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the mount point for `spec` inside the container root. Existing
+    /// symlinks along the way are refused: they would be resolved against the
+    /// host before pivot_root and could place the mount outside the new root.
+    fn mount_target(&self, spec: &MountSpec) -> Result<PathBuf> {
+        let invalid = |details: String| FaberError::InvalidMount { details };
+        spec.check_workdir(&self.config.workdir)?;
+
+        let relative = spec.target.strip_prefix("/").unwrap_or(&spec.target);
+        let mut target = self.config.container_root_dir.clone();
+        for component in relative.components() {
+            target.push(component);
+            if target.is_symlink() {
+                return Err(invalid(format!(
+                    "mount target {} passes through a symlink",
+                    spec.target.display()
+                )));
+            }
+        }
+
+        let is_file = matches!(&spec.kind, MountKind::Bind(source) if !source.is_dir());
+        let directory = if is_file {
+            target.parent().unwrap_or(&target)
+        } else {
+            &target
+        };
+        create_dir_all(directory).map_err(|e| FaberError::CreateDir {
+            e,
+            details: format!("Failed to create mount point {:?}", target),
+        })?;
+        if is_file {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&target)
+                .map_err(|e| FaberError::CreateDir {
+                    e,
+                    details: format!("Failed to create mount point {:?}", target),
+                })?;
+        }
+
+        let root = self
+            .config
+            .container_root_dir
+            .canonicalize()
+            .map_err(|e| invalid(format!("failed to resolve container root: {e}")))?;
+        let resolved = target
+            .canonicalize()
+            .map_err(|e| invalid(format!("failed to resolve {:?}: {e}", target)))?;
+        if !resolved.starts_with(&root) {
+            return Err(invalid(format!(
+                "mount target {} escapes the container root",
+                spec.target.display()
+            )));
+        }
+
+        Ok(target)
+    }
+
+    fn overlay_scratch_dir(&self) -> PathBuf {
+        self.config.container_root_dir.with_extension("overlay")
+    }
+
+    /// Assembles the image layers, or the environment rootfs, into the
+    /// container root. Layers are stored lowest first, while overlayfs expects
+    /// the topmost lower directory first.
+    fn mount_image_root(&self) -> Result<()> {
+        let lower_dirs: Vec<PathBuf> = self.config.root_layers().into_iter().rev().collect();
+
+        mount_overlay(
+            &self.config.container_root_dir,
+            &lower_dirs,
+            &self.overlay_scratch_dir(),
+            IMAGE_ROOT_SCRATCH_SIZE,
+        )
+    }
+
+    /// Image roots are writable only while mount points are created; remount
+    /// them read-only like the host toolchain bind mounts.
+    fn seal_image_root(&self) -> Result<()> {
+        if self.config.root_layers().is_empty() {
+            return Ok(());
         }
+
+        mount(
+            None::<&str>,
+            "/",
+            None::<&str>,
+            MsFlags::MS_BIND
+                | MsFlags::MS_REMOUNT
+                | MsFlags::MS_RDONLY
+                | MsFlags::MS_NOSUID
+                | MsFlags::MS_NODEV,
+            None::<&str>,
+        )
+        .map_err(|e| FaberError::Mount {
+            e,
+            details: "Failed to remount the image root read-only".to_string(),
+        })?;
+
         Ok(())
     }
 
@@ -237,8 +413,8 @@ impl Container {
             details: "Failed to change current directory".to_string(),
         })?;
 
-        // Note: oldroot is NOT unmounted here - mounts need to be moved first
-        // The cleanup happens in move_mounts_to_newroot() and final cleanup
+        // Note: oldroot is NOT unmounted here - /proc and the workspace
+        // templates are still reached through it. See unmount_oldroot().
 
         Ok(())
     }
@@ -277,6 +453,7 @@ impl Container {
 
             std::fs::OpenOptions::new()
                 .create(true)
+                .truncate(false)
                 .write(true)
                 .open(&target)
                 .map_err(|e| FaberError::MkDevDevice {
@@ -300,59 +477,27 @@ impl Container {
         Ok(())
     }
 
-    fn create_dev_devices(&self) -> Result<()> {
-        let flags = SFlag::S_IFCHR;
-        let mode = Mode::S_IRUSR
-            | Mode::S_IWUSR
-            | Mode::S_IRGRP
-            | Mode::S_IWGRP
-            | Mode::S_IROTH
-            | Mode::S_IWOTH;
-
-        create_dir_all("/dev").map_err(|e| FaberError::CreateDir {
-            e,
-            details: ("Failed to create dev directory".to_string()),
-        })?;
-
-        let device_id = makedev(1, 3);
-        mknod("/dev/null", flags, mode, device_id).map_err(|e| FaberError::MkDevDevice {
-            detaills: "Failed to create null device".to_string(),
-            e,
-        })?;
-
-        let device_id = makedev(1, 5);
-        mknod("/dev/zero", flags, mode, device_id).map_err(|e| FaberError::MkDevDevice {
-            detaills: "Failed to create zero device".to_string(),
-            e,
-        })?;
-
-        let device_id = makedev(1, 7);
-        mknod("/dev/full", flags, mode, device_id).map_err(|e| FaberError::MkDevDevice {
-            detaills: "Failed to create full device".to_string(),
+    fn create_workdir(&self) -> Result<()> {
+        create_dir_all(&self.config.workdir).map_err(|e| FaberError::CreateDir {
             e,
+            details: "Failed to create workdir".to_string(),
         })?;
 
-        let device_id = makedev(1, 8);
-        mknod("/dev/random", flags, mode, device_id).map_err(|e| FaberError::MkDevDevice {
-            detaills: "Failed to create random device".to_string(),
-            e,
-        })?;
+        if self.config.workdir_layers.is_empty() {
+            self.mount_tmpfs_workdir()?;
+        } else {
+            self.mount_overlay_workdir()?;
+        }
 
-        let device_id = makedev(1, 9);
-        mknod("/dev/urandom", flags, mode, device_id).map_err(|e| FaberError::MkDevDevice {
-            detaills: "Failed to create urandom device".to_string(),
+        set_current_dir(&self.config.workdir).map_err(|e| FaberError::Chdir {
             e,
+            details: "Failed to change current directory to workdir".to_string(),
         })?;
 
         Ok(())
     }
 
-    fn create_workdir(&self) -> Result<()> {
-        create_dir_all(&self.config.workdir).map_err(|e| FaberError::CreateDir {
-            e,
-            details: "Failed to create workdir".to_string(),
-        })?;
-
+    fn mount_tmpfs_workdir(&self) -> Result<()> {
         let mount_options = format!("size={},mode=0777", self.config.workdir_size);
         let workdir_str = self
             .config
@@ -374,9 +519,35 @@ impl Container {
             details: format!("Failed to mount tmpfs workdir to {}", workdir_str),
         })?;
 
-        set_current_dir(&self.config.workdir).map_err(|e| FaberError::Chdir {
-            e,
-            details: "Failed to change current directory to workdir".to_string(),
+        Ok(())
+    }
+
+    /// Mounts the workspace as an overlay over the read-only template layers.
+    /// Runs after pivot_root, so the host template directories are reached
+    /// through /oldroot; the fresh upper layer is bounded by `workdir_size`.
+    fn mount_overlay_workdir(&self) -> Result<()> {
+        let lower_dirs: Vec<PathBuf> = self
+            .config
+            .workdir_layers
+            .iter()
+            .rev()
+            .map(|layer| Path::new("/oldroot").join(layer.strip_prefix("/").unwrap_or(layer)))
+            .collect();
+
+        mount_overlay(
+            &self.config.workdir,
+            &lower_dirs,
+            Path::new(WORKSPACE_SCRATCH_DIR),
+            &self.config.workdir_size,
+        )?;
+
+        // The overlay root takes its attributes from the fresh upper layer, so
+        // this keeps the workspace writable without touching the templates.
+        set_permissions(&self.config.workdir, Permissions::from_mode(0o777)).map_err(|e| {
+            FaberError::CreateDir {
+                e,
+                details: "Failed to make the overlay workdir writable".to_string(),
+            }
         })?;
 
         Ok(())
@@ -469,116 +640,6 @@ impl Container {
         Ok(())
     }
 
-    fn create_proc_in_newroot(&self) -> Result<()> {
-        let proc_path = self.config.container_root_dir.join("proc");
-        let proc_fstype = "proc";
-        let proc_flags = MsFlags::MS_NODEV | MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC;
-
-        create_dir_all(&proc_path).map_err(|e| FaberError::CreateDir {
-            e,
-            details: "Failed to create proc directory in newroot".to_string(),
-        })?;
-
-        mount(
-            None::<&str>,
-            proc_path.as_os_str(),
-            Some(proc_fstype),
-            proc_flags,
-            None::<&str>,
-        )
-        .map_err(|e| FaberError::Mount {
-            e,
-            details: "Failed to mount proc filesystem in newroot".to_string(),
-        })?;
-
-        Ok(())
-    }
-
-    fn create_sys_in_newroot(&self) -> Result<()> {
-        let sys_path = self.config.container_root_dir.join("sys");
-        let sys_fstype = "sysfs";
-        let sys_flags = MsFlags::MS_NODEV | MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC;
-
-        create_dir_all(&sys_path).map_err(|e| FaberError::CreateDir {
-            e,
-            details: "Failed to create sys directory in newroot".to_string(),
-        })?;
-
-        mount(
-            None::<&str>,
-            sys_path.as_os_str(),
-            Some(sys_fstype),
-            sys_flags,
-            None::<&str>,
-        )
-        .map_err(|e| FaberError::Mount {
-            e,
-            details: "Failed to mount sys filesystem in newroot".to_string(),
-        })?;
-
-        Ok(())
-    }
-
-    fn create_cgroup_in_newroot(&self) -> Result<()> {
-        let cgroup_path = self.config.container_root_dir.join("sys/fs/cgroup");
-        let cgroup_fstype = "cgroup2";
-        let cgroup_flags =
-            MsFlags::MS_RELATIME | MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC;
-
-        create_dir_all(&cgroup_path).map_err(|e| FaberError::CreateDir {
-            e,
-            details: "Failed to create cgroup directory in newroot".to_string(),
-        })?;
-
-        mount(
-            None::<&str>,
-            cgroup_path.as_os_str(),
-            Some(cgroup_fstype),
-            cgroup_flags,
-            None::<&str>,
-        )
-        .map_err(|e| FaberError::Mount {
-            e,
-            details: "Failed to mount cgroup2 filesystem in newroot".to_string(),
-        })?;
-
-        Ok(())
-    }
-
-    fn move_mounts_to_newroot(&self) -> Result<()> {
-        // After pivot_root, mounts under oldroot need to be moved to new root
-        // Use MS_MOVE to relocate them without unmounting
-        let move_flags = MsFlags::MS_MOVE;
-
-        // Move proc mount
-        mount(
-            Some("/oldroot/proc"),
-            "/proc",
-            None::<&str>,
-            move_flags,
-            None::<&str>,
-        )
-        .map_err(|e| FaberError::Mount {
-            e,
-            details: "Failed to move proc mount to new root".to_string(),
-        })?;
-
-        // Move sys mount
-        mount(
-            Some("/oldroot/sys"),
-            "/sys",
-            None::<&str>,
-            move_flags,
-            None::<&str>,
-        )
-        .map_err(|e| FaberError::Mount {
-            e,
-            details: "Failed to move sys mount to new root".to_string(),
-        })?;
-
-        Ok(())
-    }
-
     fn create_tmpdir(&self) -> Result<()> {
         let target = PathBuf::from("/tmp");
         create_dir_all(&target).map_err(|e| FaberError::CreateDir {
@@ -615,3 +676,48 @@ impl Container {
         Ok(())
     }
 }
+
+/// Applies the read-only, nosuid, nodev and noexec bits of `flags` to the
+/// mount at `target` and every mount below it, which a bind remount cannot do.
+fn set_mount_attributes_recursive(target: &Path, flags: MsFlags) -> Result<()> {
+    let path = CString::new(target.as_os_str().as_bytes()).map_err(|_| FaberError::Generic {
+        message: format!("Mount target {:?} contains a NUL byte", target),
+    })?;
+
+    let mut attr_set = 0;
+    for (flag, attr) in [
+        (MsFlags::MS_RDONLY, libc::MOUNT_ATTR_RDONLY),
+        (MsFlags::MS_NOSUID, libc::MOUNT_ATTR_NOSUID),
+        (MsFlags::MS_NODEV, libc::MOUNT_ATTR_NODEV),
+        (MsFlags::MS_NOEXEC, libc::MOUNT_ATTR_NOEXEC),
+    ] {
+        if flags.contains(flag) {
+            attr_set |= attr;
+        }
+    }
+    let attr = libc::mount_attr {
+        attr_set,
+        attr_clr: 0,
+        propagation: 0,
+        userns_fd: 0,
+    };
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_mount_setattr,
+            libc::AT_FDCWD,
+            path.as_ptr(),
+            libc::AT_RECURSIVE as libc::c_uint,
+            &attr,
+            std::mem::size_of::<libc::mount_attr>(),
+        )
+    };
+    if result != 0 {
+        return Err(FaberError::Mount {
+            e: nix::errno::Errno::last(),
+            details: format!("Failed to apply mount flags to {:?} recursively", target),
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+
+use crate::{image::Image, prelude::*};
+
+/// An operator-defined root filesystem that tasks run against instead of the
+/// daemon host's toolchain.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    pub(crate) source: EnvironmentSource,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum EnvironmentSource {
+    Rootfs(PathBuf),
+    Image(Image),
+}
+
+impl Environment {
+    /// Uses an unpacked root filesystem (e.g. a Debian or Alpine rootfs) as the
+    /// container root, overlaid read-only like a single image layer. Mounts
+    /// inside the rootfs are not followed.
+    pub fn from_rootfs(rootfs: impl Into<PathBuf>) -> Result<Self> {
+        let rootfs = rootfs.into();
+        if !rootfs.is_absolute() || !rootfs.is_dir() {
+            return Err(FaberError::InvalidEnvironment {
+                details: format!(
+                    "root filesystem {} must be an absolute path to a directory",
+                    rootfs.display()
+                ),
+            });
+        }
+
+        Ok(Self {
+            source: EnvironmentSource::Rootfs(rootfs),
+        })
+    }
+
+    /// Uses an imported image's layers, assembled with overlayfs, as the
+    /// container root.
+    pub fn from_image(image: Image) -> Self {
+        Self {
+            source: EnvironmentSource::Image(image),
+        }
+    }
+
+    pub fn rootfs(&self) -> Option<&Path> {
+        match &self.source {
+            EnvironmentSource::Rootfs(rootfs) => Some(rootfs),
+            EnvironmentSource::Image(_) => None,
+        }
+    }
+
+    pub fn image_digest(&self) -> Option<&str> {
+        match &self.source {
+            EnvironmentSource::Rootfs(_) => None,
+            EnvironmentSource::Image(image) => Some(image.digest()),
+        }
+    }
+}
@@ -1,8 +1,16 @@
 mod builder;
 mod config;
 mod core;
+mod environment;
+mod mount;
+mod overlay;
+mod workspace;
 
-pub(crate) use config::ContainerConfig;
+pub(crate) use config::{CONTAINERS_DIR, ContainerConfig, OutputSpill};
 pub(crate) use core::Container;
+pub(crate) use mount::MountKind;
 
 pub use builder::ContainerConfigBuilder;
+pub use environment::Environment;
+pub use mount::MountSpec;
+pub use workspace::WorkspaceTemplate;
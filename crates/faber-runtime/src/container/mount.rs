@@ -0,0 +1,184 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::prelude::*;
+
+/// Paths the container sets up itself; extra mounts may neither replace them
+/// nor be placed inside them, where they would be shadowed or shadow them.
+const RESERVED_TARGETS: [&str; 5] = ["/proc", "/sys", "/dev", "/tmp", "/oldroot"];
+
+/// An extra filesystem mounted into the container before it is entered.
+///
+/// Bind mounts are read-only unless [`MountSpec::read_only`] says otherwise;
+/// every mount is `nosuid` and `nodev` unless explicitly relaxed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountSpec {
+    pub(crate) kind: MountKind,
+    pub(crate) target: PathBuf,
+    pub(crate) read_only: bool,
+    pub(crate) nosuid: bool,
+    pub(crate) nodev: bool,
+    pub(crate) noexec: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MountKind {
+    Bind(PathBuf),
+    Tmpfs { size: String },
+}
+
+impl MountSpec {
+    /// Binds the host directory or file `source` at `target` inside the
+    /// container.
+    pub fn bind(source: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Result<Self> {
+        let source = source.into();
+        if !source.is_absolute() || !source.exists() {
+            return Err(FaberError::InvalidMount {
+                details: format!(
+                    "bind source {} must be an absolute path that exists",
+                    source.display()
+                ),
+            });
+        }
+
+        Self::new(MountKind::Bind(source), target.into(), true)
+    }
+
+    /// Mounts an empty tmpfs of at most `size` (e.g. `64M`) at `target`.
+    pub fn tmpfs(target: impl Into<PathBuf>, size: impl Into<String>) -> Result<Self> {
+        let size = size.into();
+        if size.is_empty() || size.contains(',') {
+            return Err(FaberError::InvalidMount {
+                details: format!("invalid tmpfs size '{size}'"),
+            });
+        }
+
+        Self::new(MountKind::Tmpfs { size }, target.into(), false)
+    }
+
+    fn new(kind: MountKind, target: PathBuf, read_only: bool) -> Result<Self> {
+        // Only plain components are accepted so the target cannot climb out
+        // of the container root lexically; symlinks are checked at mount time.
+        let mut components = target.components();
+        let is_contained = components.next() == Some(Component::RootDir)
+            && components.clone().next().is_some()
+            && components.all(|component| matches!(component, Component::Normal(_)));
+        if !is_contained {
+            return Err(FaberError::InvalidMount {
+                details: format!(
+                    "mount target {} must be a normalized absolute path below /",
+                    target.display()
+                ),
+            });
+        }
+
+        if let Some(reserved) = RESERVED_TARGETS
+            .iter()
+            .find(|reserved| target.starts_with(reserved))
+        {
+            return Err(FaberError::InvalidMount {
+                details: format!(
+                    "mount target {} would shadow the container's {reserved}",
+                    target.display()
+                ),
+            });
+        }
+
+        Ok(Self {
+            kind,
+            target,
+            read_only,
+            nosuid: true,
+            nodev: true,
+            noexec: false,
+        })
+    }
+
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn nosuid(mut self, nosuid: bool) -> Self {
+        self.nosuid = nosuid;
+        self
+    }
+
+    pub fn nodev(mut self, nodev: bool) -> Self {
+        self.nodev = nodev;
+        self
+    }
+
+    pub fn noexec(mut self, noexec: bool) -> Self {
+        self.noexec = noexec;
+        self
+    }
+
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Refuses a target that equals, contains or lies inside `workdir`: the
+    /// workspace is mounted after extra mounts and would hide them, or the
+    /// mount would hide the workspace.
+    pub(crate) fn check_workdir(&self, workdir: &Path) -> Result<()> {
+        if self.target.starts_with(workdir) || workdir.starts_with(&self.target) {
+            return Err(FaberError::InvalidMount {
+                details: format!(
+                    "mount target {} overlaps the workdir {}",
+                    self.target.display(),
+                    workdir.display()
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MountSpec {
+    type Err = FaberError;
+
+    /// Parses `source:target[:options]`, where `source` is a host path or the
+    /// literal `tmpfs` and `options` is a comma-separated list of `ro`, `rw`,
+    /// `noexec`, `exec`, `suid`, `dev`, and (for tmpfs) `size=<bytes>`.
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = |details: String| FaberError::InvalidMount { details };
+
+        let mut fields = value.splitn(3, ':');
+        let source = fields.next().unwrap_or_default();
+        let target = fields
+            .next()
+            .ok_or_else(|| invalid(format!("'{value}' is not of the form source:target")))?;
+        let options: Vec<&str> = fields
+            .next()
+            .map(|options| options.split(',').filter(|o| !o.is_empty()).collect())
+            .unwrap_or_default();
+
+        let mut spec = if source == "tmpfs" {
+            let size = options
+                .iter()
+                .find_map(|option| option.strip_prefix("size="))
+                .ok_or_else(|| invalid(format!("tmpfs mount '{value}' needs size=")))?;
+            Self::tmpfs(target, size)?
+        } else {
+            Self::bind(source, target)?
+        };
+
+        for option in options {
+            spec = match option {
+                "ro" => spec.read_only(true),
+                "rw" => spec.read_only(false),
+                "noexec" => spec.noexec(true),
+                "exec" => spec.noexec(false),
+                "suid" => spec.nosuid(false),
+                "dev" => spec.nodev(false),
+                _ if option.starts_with("size=") && source == "tmpfs" => spec,
+                _ => return Err(invalid(format!("unknown mount option '{option}'"))),
+            };
+        }
+
+        Ok(spec)
+    }
+}
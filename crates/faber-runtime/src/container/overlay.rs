@@ -0,0 +1,96 @@
+use std::{
+    fs::{create_dir_all, remove_dir},
+    path::{Path, PathBuf},
+};
+
+use nix::mount::{MntFlags, MsFlags, mount, umount2};
+
+use crate::prelude::*;
+
+/// Mounts an overlayfs at `target` over read-only `lower_dirs` (topmost first).
+///
+/// The upper and work directories live on a private tmpfs mounted at `scratch`
+/// for the duration of the mount call only: once the overlay holds its own
+/// reference the tmpfs is detached, so the writable layer is never reachable
+/// through any other path and disappears with the mount namespace.
+pub(crate) fn mount_overlay(
+    target: &Path,
+    lower_dirs: &[PathBuf],
+    scratch: &Path,
+    scratch_size: &str,
+) -> Result<()> {
+    create_dir_all(scratch).map_err(|e| FaberError::CreateDir {
+        e,
+        details: format!("Failed to create overlay scratch directory {:?}", scratch),
+    })?;
+
+    let scratch_options = format!("size={scratch_size},mode=0755");
+    mount(
+        Some("tmpfs"),
+        scratch,
+        Some("tmpfs"),
+        MsFlags::MS_NODEV | MsFlags::MS_NOSUID,
+        Some(scratch_options.as_str()),
+    )
+    .map_err(|e| FaberError::Mount {
+        e,
+        details: format!("Failed to mount overlay scratch tmpfs at {:?}", scratch),
+    })?;
+
+    let upper = scratch.join("upper");
+    let work = scratch.join("work");
+    for dir in [&upper, &work] {
+        create_dir_all(dir).map_err(|e| FaberError::CreateDir {
+            e,
+            details: format!("Failed to create overlay directory {:?}", dir),
+        })?;
+    }
+
+    let lowerdir = lower_dirs
+        .iter()
+        .map(|dir| {
+            let dir = dir.to_str().ok_or_else(|| FaberError::Generic {
+                message: format!("Overlay lower directory {:?} is not valid UTF-8", dir),
+            })?;
+            if dir.contains([':', ',']) {
+                return Err(FaberError::Generic {
+                    message: format!("Overlay lower directory {dir} contains ':' or ','"),
+                });
+            }
+            Ok(dir)
+        })
+        .collect::<Result<Vec<_>>>()?
+        .join(":");
+    let options = format!(
+        "lowerdir={lowerdir},upperdir={},workdir={}",
+        upper.display(),
+        work.display()
+    );
+
+    create_dir_all(target).map_err(|e| FaberError::CreateDir {
+        e,
+        details: format!("Failed to create overlay target {:?}", target),
+    })?;
+    mount(
+        Some("overlay"),
+        target,
+        Some("overlay"),
+        MsFlags::MS_NODEV | MsFlags::MS_NOSUID,
+        Some(options.as_str()),
+    )
+    .map_err(|e| FaberError::Mount {
+        e,
+        details: format!("Failed to mount overlay at {:?}", target),
+    })?;
+
+    umount2(scratch, MntFlags::MNT_DETACH).map_err(|e| FaberError::Umount {
+        e,
+        details: format!("Failed to detach overlay scratch tmpfs at {:?}", scratch),
+    })?;
+    remove_dir(scratch).map_err(|e| FaberError::RemoveDir {
+        e,
+        details: format!("Failed to remove overlay scratch directory {:?}", scratch),
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use crate::prelude::*;
+
+/// Read-only starter files that a task's workspace is layered over.
+///
+/// Each execution gets its own writable upper layer, so tasks see the
+/// template contents without copying them and never observe another job's
+/// writes.
+#[derive(Debug, Clone)]
+pub struct WorkspaceTemplate {
+    pub(crate) layers: Vec<PathBuf>,
+}
+
+impl WorkspaceTemplate {
+    /// Builds a template from directories listed lowest first; files in later
+    /// directories shadow files at the same path in earlier ones.
+    pub fn from_layers(layers: Vec<PathBuf>) -> Result<Self> {
+        if layers.is_empty() {
+            return Err(FaberError::InvalidEnvironment {
+                details: "workspace templates need at least one layer".to_string(),
+            });
+        }
+
+        for layer in &layers {
+            if !layer.is_absolute() || !layer.is_dir() {
+                return Err(FaberError::InvalidEnvironment {
+                    details: format!(
+                        "workspace layer {} must be an absolute path to a directory",
+                        layer.display()
+                    ),
+                });
+            }
+        }
+
+        Ok(Self { layers })
+    }
+
+    pub fn layers(&self) -> &[PathBuf] {
+        &self.layers
+    }
+}
@@ -1,3 +1,5 @@
+use nix::errno::Errno;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,6 +13,27 @@ pub enum FaberError {
     #[error("Invalid task file path '{path}': {details}")]
     InvalidTaskFilePath { path: String, details: String },
 
+    #[error("Invalid task: {details}")]
+    InvalidTask { details: String },
+
+    #[error("Invalid execution environment: {details}")]
+    InvalidEnvironment { details: String },
+
+    #[error("Invalid mount: {details}")]
+    InvalidMount { details: String },
+
+    #[error("Invalid cgroup configuration: {details}")]
+    InvalidCgroupConfig { details: String },
+
+    #[error("Failed to import image: {details}")]
+    ImportImage { details: String },
+
+    #[error("Execution was cancelled")]
+    Cancelled,
+
+    #[error("Pre-warmed container failed: {details}")]
+    WarmContainer { details: String },
+
     #[error("Failed to create pipe:\n Details: {details} \nError: {e}")]
     MkPipe { e: std::io::Error, details: String },
 
@@ -107,3 +130,232 @@ pub enum FaberError {
         details: String,
     },
 }
+
+/// Stable, machine-readable category of an infrastructure error. Codes are
+/// only ever added, never renamed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request was rejected before anything ran.
+    InvalidRequest,
+    InvalidTask,
+    InvalidConfiguration,
+    /// The operator's environment, mount or cgroup configuration does not
+    /// work on this host. Not the submission's fault.
+    Configuration,
+    ImageImport,
+    Cancelled,
+    WarmContainer,
+    Namespace,
+    Filesystem,
+    Cgroup,
+    Process,
+    TaskIo,
+    Timeout,
+    #[default]
+    Internal,
+}
+
+impl ErrorCode {
+    /// Whether the error is caused by the submission rather than the host.
+    pub fn is_client_error(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::InvalidRequest | ErrorCode::InvalidTask | ErrorCode::InvalidConfiguration
+        )
+    }
+}
+
+/// Where in the life of an execution an error happened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorStage {
+    Validation,
+    ContainerSetup,
+    TaskSetup,
+    #[default]
+    Execution,
+    Cleanup,
+}
+
+/// Serializable classification of a [`FaberError`], reported next to its
+/// message so clients can tell whether to retry, alert, or fix the request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorInfo {
+    pub code: ErrorCode,
+    pub stage: ErrorStage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errno: Option<i32>,
+    #[serde(default)]
+    pub retryable: bool,
+}
+
+impl ErrorInfo {
+    pub fn new(code: ErrorCode, stage: ErrorStage) -> Self {
+        Self {
+            code,
+            stage,
+            errno: None,
+            retryable: false,
+        }
+    }
+
+    /// Reports the error at `stage`, which the caller knows better than the
+    /// error itself. Rejected submissions stay in [`ErrorStage::Validation`].
+    pub fn in_stage(mut self, stage: ErrorStage) -> Self {
+        if self.stage != ErrorStage::Validation {
+            self.stage = stage;
+        }
+        self
+    }
+}
+
+impl FaberError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            FaberError::Generic { .. } => ErrorCode::Internal,
+            FaberError::InvalidTask { .. } | FaberError::InvalidTaskFilePath { .. } => {
+                ErrorCode::InvalidTask
+            }
+            FaberError::InvalidEnvironment { .. }
+            | FaberError::InvalidMount { .. }
+            | FaberError::InvalidCgroupConfig { .. } => ErrorCode::Configuration,
+            FaberError::ImportImage { .. } => ErrorCode::ImageImport,
+            FaberError::Cancelled => ErrorCode::Cancelled,
+            FaberError::WarmContainer { .. } => ErrorCode::WarmContainer,
+            FaberError::Unshare { .. }
+            | FaberError::SetHostname { .. }
+            | FaberError::SetUserId { .. }
+            | FaberError::SetGroupId { .. } => ErrorCode::Namespace,
+            FaberError::WriteFile { .. }
+            | FaberError::Mount { .. }
+            | FaberError::Umount { .. }
+            | FaberError::PivotRoot { .. }
+            | FaberError::Chdir { .. }
+            | FaberError::CreateContainerRootDir { .. }
+            | FaberError::CreateDir { .. }
+            | FaberError::RemoveContainerRootDir { .. }
+            | FaberError::RemoveDir { .. }
+            | FaberError::MkDevDevice { .. } => ErrorCode::Filesystem,
+            FaberError::CgroupControllers { .. } | FaberError::CgroupControllerEnable { .. } => {
+                ErrorCode::Cgroup
+            }
+            FaberError::MkPipe { .. }
+            | FaberError::CloseFd { .. }
+            | FaberError::WaitPid { .. }
+            | FaberError::Fork { .. }
+            | FaberError::ExecuteTask { .. } => ErrorCode::Process,
+            FaberError::ParseResult { .. }
+            | FaberError::GetStdout { .. }
+            | FaberError::GetStderr { .. }
+            | FaberError::GetStdin { .. }
+            | FaberError::WriteStdin { .. }
+            | FaberError::GetExitCode { .. } => ErrorCode::TaskIo,
+            FaberError::TaskTimeout { .. } => ErrorCode::Timeout,
+        }
+    }
+
+    /// The stage this kind of error usually comes from.
+    pub fn stage(&self) -> ErrorStage {
+        match self {
+            FaberError::InvalidTask { .. } | FaberError::InvalidTaskFilePath { .. } => {
+                ErrorStage::Validation
+            }
+            FaberError::InvalidEnvironment { .. }
+            | FaberError::InvalidMount { .. }
+            | FaberError::InvalidCgroupConfig { .. }
+            | FaberError::ImportImage { .. }
+            | FaberError::WarmContainer { .. }
+            | FaberError::Unshare { .. }
+            | FaberError::SetHostname { .. }
+            | FaberError::Mount { .. }
+            | FaberError::PivotRoot { .. }
+            | FaberError::CreateContainerRootDir { .. }
+            | FaberError::MkDevDevice { .. }
+            | FaberError::CgroupControllers { .. }
+            | FaberError::CgroupControllerEnable { .. } => ErrorStage::ContainerSetup,
+            FaberError::WriteFile { .. }
+            | FaberError::CreateDir { .. }
+            | FaberError::Chdir { .. }
+            | FaberError::MkPipe { .. }
+            | FaberError::Fork { .. }
+            | FaberError::SetUserId { .. }
+            | FaberError::SetGroupId { .. }
+            | FaberError::ExecuteTask { .. } => ErrorStage::TaskSetup,
+            FaberError::Umount { .. }
+            | FaberError::RemoveContainerRootDir { .. }
+            | FaberError::RemoveDir { .. } => ErrorStage::Cleanup,
+            FaberError::Generic { .. }
+            | FaberError::Cancelled
+            | FaberError::CloseFd { .. }
+            | FaberError::WaitPid { .. }
+            | FaberError::ParseResult { .. }
+            | FaberError::GetStdout { .. }
+            | FaberError::GetStderr { .. }
+            | FaberError::GetStdin { .. }
+            | FaberError::WriteStdin { .. }
+            | FaberError::GetExitCode { .. }
+            | FaberError::TaskTimeout { .. } => ErrorStage::Execution,
+        }
+    }
+
+    /// The OS error behind this error, if any.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            FaberError::CloseFd { e }
+            | FaberError::WaitPid { e }
+            | FaberError::Fork { e }
+            | FaberError::Unshare { e }
+            | FaberError::SetUserId { e }
+            | FaberError::SetGroupId { e }
+            | FaberError::Mount { e, .. }
+            | FaberError::Umount { e, .. }
+            | FaberError::PivotRoot { e, .. }
+            | FaberError::SetHostname { e, .. }
+            | FaberError::MkDevDevice { e, .. } => Some(*e as i32),
+            FaberError::WriteFile { e, .. }
+            | FaberError::MkPipe { e, .. }
+            | FaberError::Chdir { e, .. }
+            | FaberError::CreateContainerRootDir { e, .. }
+            | FaberError::CreateDir { e, .. }
+            | FaberError::RemoveContainerRootDir { e, .. }
+            | FaberError::RemoveDir { e, .. }
+            | FaberError::CgroupControllers { e, .. }
+            | FaberError::ExecuteTask { e, .. }
+            | FaberError::WriteStdin { e, .. }
+            | FaberError::GetExitCode { e, .. }
+            | FaberError::CgroupControllerEnable { e, .. } => e.raw_os_error(),
+            _ => None,
+        }
+    }
+
+    /// Whether running the same request again may succeed: a pre-warmed
+    /// container broke, or the host was briefly out of a resource.
+    pub fn is_retryable(&self) -> bool {
+        if matches!(self, FaberError::WarmContainer { .. }) {
+            return true;
+        }
+        self.errno().is_some_and(|errno| {
+            matches!(
+                Errno::from_raw(errno),
+                Errno::EAGAIN
+                    | Errno::EINTR
+                    | Errno::EBUSY
+                    | Errno::ENOMEM
+                    | Errno::ENOSPC
+                    | Errno::EMFILE
+                    | Errno::ENFILE
+                    | Errno::ETIMEDOUT
+            )
+        })
+    }
+
+    pub fn info(&self) -> ErrorInfo {
+        ErrorInfo {
+            code: self.code(),
+            stage: self.stage(),
+            errno: self.errno(),
+            retryable: self.is_retryable(),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    ffi::CString,
+    fs::{create_dir_all, remove_dir_all, remove_file, symlink_metadata},
+    io::Read,
+    os::unix::ffi::OsStrExt,
+    path::{Component, Path, PathBuf},
+};
+
+use nix::sys::stat::{Mode, SFlag, makedev, mknod};
+use tar::Archive;
+
+use crate::prelude::*;
+
+const WHITEOUT_PREFIX: &str = ".wh.";
+const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+/// Unpacks one image layer, translating OCI whiteout files into the
+/// representation overlayfs understands: `.wh.<name>` becomes a 0/0 character
+/// device and `.wh..wh..opq` marks its directory `trusted.overlay.opaque`.
+pub(super) fn unpack_layer(reader: impl Read, destination: &Path) -> Result<()> {
+    let mut archive = Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_ownerships(true);
+    archive.set_unpack_xattrs(true);
+    archive.set_overwrite(true);
+
+    let entries = archive.entries().map_err(|e| FaberError::ImportImage {
+        details: format!("Failed to read layer archive: {e}"),
+    })?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| FaberError::ImportImage {
+            details: format!("Failed to read layer entry: {e}"),
+        })?;
+        let path = entry
+            .path()
+            .map_err(|e| FaberError::ImportImage {
+                details: format!("Invalid layer entry path: {e}"),
+            })?
+            .into_owned();
+        let file_name = path.file_name().map(|name| name.to_string_lossy());
+
+        match file_name.as_deref() {
+            Some(OPAQUE_WHITEOUT) => {
+                let directory = resolve_beneath(destination, path.parent())?;
+                create_dir_all(&directory).map_err(|e| FaberError::CreateDir {
+                    e,
+                    details: format!("Failed to create opaque directory {:?}", directory),
+                })?;
+                set_opaque(&directory)?;
+            }
+            Some(name) if name.starts_with(WHITEOUT_PREFIX) => {
+                let hidden = &name[WHITEOUT_PREFIX.len()..];
+                if !matches!(
+                    Path::new(hidden).components().collect::<Vec<_>>()[..],
+                    [Component::Normal(_)]
+                ) {
+                    return Err(FaberError::ImportImage {
+                        details: format!("Invalid whiteout entry {:?}", path),
+                    });
+                }
+                let parent = resolve_beneath(destination, path.parent())?;
+                create_dir_all(&parent).map_err(|e| FaberError::CreateDir {
+                    e,
+                    details: format!("Failed to create whiteout parent {:?}", parent),
+                })?;
+                // The layer may also carry the path it hides; the whiteout wins.
+                remove_existing(&parent.join(hidden))?;
+                mknod(
+                    &parent.join(hidden),
+                    SFlag::S_IFCHR,
+                    Mode::empty(),
+                    makedev(0, 0),
+                )
+                .map_err(|e| FaberError::MkDevDevice {
+                    detaills: format!("Failed to create whiteout for {:?}", path),
+                    e,
+                })?;
+            }
+            _ => {
+                entry
+                    .unpack_in(destination)
+                    .map_err(|e| FaberError::ImportImage {
+                        details: format!("Failed to unpack layer entry {:?}: {e}", path),
+                    })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Joins a layer-relative path onto the destination, refusing traversal and
+/// parents that resolve outside of it through previously unpacked symlinks.
+fn resolve_beneath(destination: &Path, relative: Option<&Path>) -> Result<PathBuf> {
+    let relative = relative.unwrap_or(Path::new(""));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(FaberError::ImportImage {
+            details: format!("Layer path {:?} escapes the layer root", relative),
+        });
+    }
+
+    let joined = destination.join(relative);
+    let existing = joined
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(destination);
+    if let (Ok(resolved), Ok(root)) = (existing.canonicalize(), destination.canonicalize())
+        && !resolved.starts_with(&root)
+    {
+        return Err(FaberError::ImportImage {
+            details: format!("Layer path {:?} resolves outside the layer root", relative),
+        });
+    }
+
+    Ok(joined)
+}
+
+/// Removes whatever is at `path` without following a final symlink.
+fn remove_existing(path: &Path) -> Result<()> {
+    let removed = match symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => remove_dir_all(path),
+        Ok(_) => remove_file(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => Err(e),
+    };
+    removed.map_err(|e| FaberError::ImportImage {
+        details: format!("Failed to remove {:?} before whiteout: {e}", path),
+    })
+}
+
+fn set_opaque(directory: &Path) -> Result<()> {
+    let path =
+        CString::new(directory.as_os_str().as_bytes()).map_err(|_| FaberError::ImportImage {
+            details: format!("Layer path {:?} contains a NUL byte", directory),
+        })?;
+    let name = c"trusted.overlay.opaque";
+    let value = b"y";
+
+    let result = unsafe {
+        nix::libc::lsetxattr(
+            path.as_ptr(),
+            name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+        )
+    };
+    if result != 0 {
+        return Err(FaberError::ImportImage {
+            details: format!(
+                "Failed to mark {:?} opaque: {}",
+                directory,
+                std::io::Error::last_os_error()
+            ),
+        });
+    }
+
+    Ok(())
+}
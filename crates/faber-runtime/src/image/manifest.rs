@@ -0,0 +1,31 @@
+use serde::Deserialize;
+
+pub(super) const OCI_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+pub(super) const DOCKER_MANIFEST_LIST_MEDIA_TYPE: &str =
+    "application/vnd.docker.distribution.manifest.list.v2+json";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct OciDescriptor {
+    #[serde(default)]
+    pub(super) media_type: String,
+    pub(super) digest: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct OciIndex {
+    pub(super) manifests: Vec<OciDescriptor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct OciManifest {
+    pub(super) layers: Vec<OciDescriptor>,
+}
+
+/// One entry of the `manifest.json` written by `docker save`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(super) struct DockerManifest {
+    pub(super) config: String,
+    pub(super) layers: Vec<String>,
+}
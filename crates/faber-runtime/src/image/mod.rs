@@ -0,0 +1,5 @@
+mod layer;
+mod manifest;
+mod store;
+
+pub use store::{Image, ImageStore};
@@ -0,0 +1,269 @@
+use std::{
+    fs::{File, create_dir_all, read_to_string, remove_dir_all, rename},
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+
+use super::{
+    layer::unpack_layer,
+    manifest::{
+        DOCKER_MANIFEST_LIST_MEDIA_TYPE, DockerManifest, OCI_INDEX_MEDIA_TYPE, OciIndex,
+        OciManifest,
+    },
+};
+use crate::{prelude::*, utils::generate_random_string};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// An imported image: its digest and unpacked layer directories, lowest first.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub(crate) digest: String,
+    pub(crate) layers: Vec<PathBuf>,
+}
+
+impl Image {
+    /// The OCI manifest digest, or the image ID for `docker save` archives.
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    pub fn layers(&self) -> &[PathBuf] {
+        &self.layers
+    }
+}
+
+/// Imports OCI image layouts and `docker save` tarballs into a content-addressed
+/// cache of unpacked layers under `root/layers/sha256/<digest>`.
+#[derive(Debug, Clone)]
+pub struct ImageStore {
+    root: PathBuf,
+}
+
+impl ImageStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn import(&self, source: &Path) -> Result<Image> {
+        if source.is_dir() {
+            return self.import_layout(source);
+        }
+
+        let staging = self
+            .root
+            .join(format!("staging-{}", generate_random_string(12)));
+        let result = self
+            .unpack_archive(source, &staging)
+            .and_then(|()| self.import_layout(&staging));
+        let _ = remove_dir_all(&staging);
+        result
+    }
+
+    fn import_layout(&self, layout: &Path) -> Result<Image> {
+        // Recent `docker save` output is also a valid OCI layout, so prefer the
+        // index whenever one is present.
+        if layout.join("index.json").is_file() {
+            self.import_oci_layout(layout)
+        } else if layout.join("manifest.json").is_file() {
+            self.import_docker_archive(layout)
+        } else {
+            Err(FaberError::ImportImage {
+                details: format!(
+                    "{} is neither an OCI image layout nor a docker save archive",
+                    layout.display()
+                ),
+            })
+        }
+    }
+
+    fn import_oci_layout(&self, layout: &Path) -> Result<Image> {
+        let mut index: OciIndex = read_json(&layout.join("index.json"))?;
+
+        let manifest_descriptor = loop {
+            let [descriptor] = index.manifests.as_slice() else {
+                return Err(FaberError::ImportImage {
+                    details: format!(
+                        "OCI index in {} must reference exactly one image, found {}",
+                        layout.display(),
+                        index.manifests.len()
+                    ),
+                });
+            };
+            if descriptor.media_type == OCI_INDEX_MEDIA_TYPE
+                || descriptor.media_type == DOCKER_MANIFEST_LIST_MEDIA_TYPE
+            {
+                index = read_json(&blob_path(layout, &descriptor.digest)?)?;
+                continue;
+            }
+            break descriptor;
+        };
+
+        let manifest: OciManifest = read_json(&blob_path(layout, &manifest_descriptor.digest)?)?;
+        let layers = manifest
+            .layers
+            .iter()
+            .map(|layer| self.import_layer(&blob_path(layout, &layer.digest)?, Some(&layer.digest)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Image {
+            digest: manifest_descriptor.digest.clone(),
+            layers,
+        })
+    }
+
+    fn import_docker_archive(&self, archive: &Path) -> Result<Image> {
+        let manifests: Vec<DockerManifest> = read_json(&archive.join("manifest.json"))?;
+        let [manifest] = manifests.as_slice() else {
+            return Err(FaberError::ImportImage {
+                details: format!(
+                    "docker save archive must contain exactly one image, found {}",
+                    manifests.len()
+                ),
+            });
+        };
+
+        let config_path = beneath(archive, &manifest.config)?;
+        let digest = format!("sha256:{}", sha256_file(&config_path)?);
+        let layers = manifest
+            .layers
+            .iter()
+            .map(|layer| self.import_layer(&beneath(archive, layer)?, None))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Image { digest, layers })
+    }
+
+    /// Unpacks a layer blob into the cache unless a layer with the same digest
+    /// is already present. Layers are staged and renamed into place so a
+    /// partially unpacked layer is never used.
+    fn import_layer(&self, blob: &Path, expected_digest: Option<&str>) -> Result<PathBuf> {
+        let digest = sha256_file(blob)?;
+        if let Some(expected) = expected_digest
+            && expected != format!("sha256:{digest}")
+        {
+            return Err(FaberError::ImportImage {
+                details: format!("Layer {} has digest sha256:{digest}", expected),
+            });
+        }
+
+        let layers_dir = self.root.join("layers").join("sha256");
+        let layer_dir = layers_dir.join(&digest);
+        if layer_dir.is_dir() {
+            return Ok(layer_dir);
+        }
+
+        let staging = layers_dir.join(format!(".{digest}-{}", generate_random_string(8)));
+        create_dir_all(&staging).map_err(|e| FaberError::CreateDir {
+            e,
+            details: format!("Failed to create layer staging directory {:?}", staging),
+        })?;
+
+        let result = open_layer(blob)
+            .and_then(|reader| unpack_layer(reader, &staging))
+            .and_then(|()| {
+                rename(&staging, &layer_dir).or_else(|e| {
+                    // Another import finished the same layer first.
+                    if layer_dir.is_dir() {
+                        let _ = remove_dir_all(&staging);
+                        Ok(())
+                    } else {
+                        Err(FaberError::ImportImage {
+                            details: format!("Failed to publish layer {digest}: {e}"),
+                        })
+                    }
+                })
+            });
+        if result.is_err() {
+            let _ = remove_dir_all(&staging);
+        }
+        result.map(|()| layer_dir)
+    }
+
+    fn unpack_archive(&self, source: &Path, staging: &Path) -> Result<()> {
+        create_dir_all(staging).map_err(|e| FaberError::CreateDir {
+            e,
+            details: format!("Failed to create image staging directory {:?}", staging),
+        })?;
+
+        let mut archive = tar::Archive::new(open_layer(source)?);
+        archive
+            .unpack(staging)
+            .map_err(|e| FaberError::ImportImage {
+                details: format!("Failed to unpack image archive {}: {e}", source.display()),
+            })
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let contents = read_to_string(path).map_err(|e| FaberError::ImportImage {
+        details: format!("Failed to read {}: {e}", path.display()),
+    })?;
+    serde_json::from_str(&contents).map_err(|e| FaberError::ImportImage {
+        details: format!("Failed to parse {}: {e}", path.display()),
+    })
+}
+
+fn blob_path(layout: &Path, digest: &str) -> Result<PathBuf> {
+    let (algorithm, hex) = digest.split_once(':').unwrap_or(("", digest));
+    if algorithm != "sha256" || hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(FaberError::ImportImage {
+            details: format!("Unsupported blob digest {digest}"),
+        });
+    }
+    Ok(layout.join("blobs").join(algorithm).join(hex))
+}
+
+fn beneath(root: &Path, relative: &str) -> Result<PathBuf> {
+    let path = Path::new(relative);
+    if path
+        .components()
+        .any(|component| !matches!(component, std::path::Component::Normal(_)))
+    {
+        return Err(FaberError::ImportImage {
+            details: format!("Archive path {relative} escapes the image archive"),
+        });
+    }
+    Ok(root.join(path))
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).map_err(|e| FaberError::ImportImage {
+        details: format!("Failed to open {}: {e}", path.display()),
+    })?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| FaberError::ImportImage {
+        details: format!("Failed to hash {}: {e}", path.display()),
+    })?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Opens a (possibly gzip-compressed) tar stream, detected by magic bytes
+/// since `docker save` layers carry no media type.
+fn open_layer(path: &Path) -> Result<Box<dyn Read>> {
+    let open_error = |e: std::io::Error| FaberError::ImportImage {
+        details: format!("Failed to read {}: {e}", path.display()),
+    };
+
+    let mut file = File::open(path).map_err(open_error)?;
+    let mut magic = [0u8; 4];
+    let read = file.read(&mut magic).map_err(open_error)?;
+    file.seek(SeekFrom::Start(0)).map_err(open_error)?;
+
+    if read >= GZIP_MAGIC.len() && magic[..2] == GZIP_MAGIC {
+        Ok(Box::new(GzDecoder::new(BufReader::new(file))))
+    } else if read == ZSTD_MAGIC.len() && magic == ZSTD_MAGIC {
+        Err(FaberError::ImportImage {
+            details: format!(
+                "{} is zstd-compressed, which is not supported",
+                path.display()
+            ),
+        })
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
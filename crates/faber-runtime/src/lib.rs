@@ -1,17 +1,39 @@
+mod backend;
 mod cgroup;
 mod container;
 mod error;
+mod image;
 mod prelude;
+mod readiness;
 mod result;
 mod runtime;
 mod task;
 mod utils;
 
-pub use cgroup::CgroupConfigBuilder;
-pub use container::ContainerConfigBuilder;
+pub use backend::{
+    BackendFuture, ExecutionBackend, GvisorBackend, NamespaceBackend, ProcessBackend,
+};
+pub use cgroup::{
+    CgroupConfigBuilder, CpuAllocator, CpuAllocatorBuilder, CpuAllocatorStats, CpuLease,
+    CpusetPartition, IoMax,
+};
+pub use container::{ContainerConfigBuilder, Environment, MountSpec, WorkspaceTemplate};
+pub use error::{ErrorCode, ErrorInfo, ErrorStage, FaberError};
+pub use image::{Image, ImageStore};
+pub use readiness::{
+    ReadinessCheck, check_cgroups, check_free_space, check_seccomp, check_user_namespaces,
+};
 
 pub use result::{
-    ExecutionStepResult, RuntimeResult, TaskGroupResult, TaskOutcome, TaskResult, TaskResultStats,
+    ExecutionStepResult, GroupTimings, MergedOutput, OutputChunk, OutputStream, PressureStall,
+    ProcessUsage, ResourceSample, RuntimeResult, SpilledOutput, StoredOutput, TaskGroupResult,
+    TaskOutcome, TaskPressure, TaskResult, TaskResultStats, TaskTimings, TerminationPhase,
+};
+pub use runtime::{
+    ContainerPool, ContainerPoolBuilder, PoolStats, Runtime, RuntimeBuilder, RuntimeEvent,
+    RuntimeHandle,
+};
+pub use task::{
+    ExecutionStep, OutputCapture, OutputRetention, SandboxProfile, Task, TaskGroup,
+    TerminationPolicy,
 };
-pub use runtime::{Runtime, RuntimeBuilder};
-pub use task::{ExecutionStep, SandboxProfile, Task, TaskGroup};
@@ -1,3 +1,3 @@
-pub use crate::error::FaberError;
+pub use crate::error::{ErrorCode, ErrorInfo, ErrorStage, FaberError};
 
 pub type Result<T> = std::result::Result<T, FaberError>;
@@ -0,0 +1,149 @@
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use nix::{errno::Errno, libc, sys::statvfs::statvfs, unistd::geteuid};
+use serde::{Deserialize, Serialize};
+
+use crate::cgroup::Cgroup;
+
+/// Controllers every task cgroup needs; `io` and `cpuset` are optional.
+const REQUIRED_CONTROLLERS: [&str; 3] = ["cpu", "memory", "pids"];
+
+/// The outcome of one check of whether executions can currently succeed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadinessCheck {
+    pub name: String,
+    pub ready: bool,
+    /// What was found, or why the check failed.
+    pub detail: String,
+}
+
+impl ReadinessCheck {
+    pub fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ready: true,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn fail(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ready: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Whether the faber cgroup delegates the controllers task cgroups are
+/// limited with or, before the first execution has created it, whether its
+/// parent offers them. Only reads the hierarchy.
+pub fn check_cgroups() -> ReadinessCheck {
+    const NAME: &str = "cgroup_hierarchy";
+
+    if let Some(error) = Cgroup::initialization_error() {
+        return ReadinessCheck::fail(NAME, error);
+    }
+    let faber = match Cgroup::inspect_faber_cgroup_path() {
+        Ok(faber) => faber,
+        Err(e) => return ReadinessCheck::fail(NAME, e.to_string()),
+    };
+    let (controllers, verb) = if faber.is_dir() {
+        (faber.join("cgroup.subtree_control"), "delegated")
+    } else {
+        (faber.with_file_name("cgroup.controllers"), "available")
+    };
+    let enabled = match read_to_string(&controllers) {
+        Ok(enabled) => enabled,
+        Err(e) => return ReadinessCheck::fail(NAME, format!("{}: {e}", controllers.display())),
+    };
+    let enabled: Vec<_> = enabled.split_whitespace().collect();
+    let missing: Vec<_> = REQUIRED_CONTROLLERS
+        .into_iter()
+        .filter(|controller| !enabled.contains(controller))
+        .collect();
+    if missing.is_empty() {
+        ReadinessCheck::pass(NAME, format!("controllers {verb}: {}", enabled.join(" ")))
+    } else {
+        ReadinessCheck::fail(
+            NAME,
+            format!("controllers not {verb}: {}", missing.join(" ")),
+        )
+    }
+}
+
+/// Whether the kernel lets this process create the user namespace tasks run
+/// in.
+pub fn check_user_namespaces() -> ReadinessCheck {
+    const NAME: &str = "user_namespaces";
+
+    let max = read_to_string("/proc/sys/user/max_user_namespaces");
+    match max.as_deref().map(str::trim) {
+        Err(e) => {
+            return ReadinessCheck::fail(NAME, format!("user namespaces unsupported: {e}"));
+        }
+        Ok("0") => {
+            return ReadinessCheck::fail(NAME, "user.max_user_namespaces is 0");
+        }
+        Ok(_) => {}
+    }
+    // Debian-style kernels can forbid unprivileged users from creating them.
+    if !geteuid().is_root()
+        && read_to_string("/proc/sys/kernel/unprivileged_userns_clone")
+            .is_ok_and(|value| value.trim() == "0")
+    {
+        return ReadinessCheck::fail(NAME, "kernel.unprivileged_userns_clone is 0");
+    }
+    ReadinessCheck::pass(NAME, "available")
+}
+
+/// Whether the kernel supports the seccomp filters that sandbox profiles are
+/// enforced with.
+pub fn check_seccomp() -> ReadinessCheck {
+    const NAME: &str = "seccomp";
+
+    let mode = unsafe { libc::prctl(libc::PR_GET_SECCOMP) };
+    if mode < 0 {
+        return ReadinessCheck::fail(NAME, format!("seccomp unsupported: {}", Errno::last()));
+    }
+    match read_to_string("/proc/sys/kernel/seccomp/actions_avail") {
+        Ok(actions) if actions.split_whitespace().any(|action| action == "errno") => {
+            ReadinessCheck::pass(NAME, format!("actions: {}", actions.trim()))
+        }
+        Ok(actions) => ReadinessCheck::fail(
+            NAME,
+            format!("errno action unavailable: {}", actions.trim()),
+        ),
+        Err(e) => ReadinessCheck::fail(NAME, format!("seccomp filters unsupported: {e}")),
+    }
+}
+
+/// Whether the filesystem holding `path`, or the nearest ancestor that
+/// exists, has at least `min_bytes` available.
+pub fn check_free_space(name: &str, path: &Path, min_bytes: u64) -> ReadinessCheck {
+    let existing = existing_ancestor(path);
+    match statvfs(&existing) {
+        Ok(stats) => {
+            // The statvfs fields are narrower than u64 on 32-bit targets.
+            #[allow(clippy::unnecessary_cast)]
+            let free = stats.blocks_available() as u64 * stats.fragment_size() as u64;
+            let detail = format!("{} bytes free at {}", free, existing.display());
+            if free >= min_bytes {
+                ReadinessCheck::pass(name, detail)
+            } else {
+                ReadinessCheck::fail(name, format!("{detail}, below {min_bytes}"))
+            }
+        }
+        Err(e) => ReadinessCheck::fail(name, format!("{}: {e}", existing.display())),
+    }
+}
+
+fn existing_ancestor(path: &Path) -> PathBuf {
+    path.ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(Path::new("/"))
+        .to_path_buf()
+}
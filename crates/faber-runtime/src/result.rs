@@ -1,20 +1,51 @@
+use std::path::PathBuf;
+
 use serde::de::Error;
 use serde::{Deserialize, Serialize};
 
+use crate::error::ErrorInfo;
+
 pub type TaskGroupResult = Vec<ExecutionStepResult>;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum RuntimeResult {
-    Success(TaskGroupResult),
-    ContainerSetupFailed { error: String },
+    Success(TaskGroupResult, GroupTimings),
+    ContainerSetupFailed {
+        error: String,
+        #[serde(default)]
+        info: ErrorInfo,
+    },
+}
+
+impl RuntimeResult {
+    pub(crate) fn timings_mut(&mut self) -> Option<&mut GroupTimings> {
+        match self {
+            RuntimeResult::Success(_, timings) => Some(timings),
+            RuntimeResult::ContainerSetupFailed { .. } => None,
+        }
+    }
 }
 
+// Boxing the single result would make every caller's pattern matches awkward
+// for a value built once per step.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum ExecutionStepResult {
     Single(TaskResult),
     Parallel(Vec<TaskResult>),
 }
 
+impl ExecutionStepResult {
+    pub(crate) fn task_results_mut(&mut self) -> impl Iterator<Item = &mut TaskResult> {
+        match self {
+            ExecutionStepResult::Single(task_result) => {
+                std::slice::from_mut(task_result).iter_mut()
+            }
+            ExecutionStepResult::Parallel(task_results) => task_results.iter_mut(),
+        }
+    }
+}
+
 impl serde::Serialize for ExecutionStepResult {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -60,10 +91,25 @@ pub enum TaskResult {
     },
     Failed {
         error: String,
+        info: ErrorInfo,
         stats: TaskResultStats,
     },
 }
 
+impl TaskResult {
+    pub fn stats(&self) -> &TaskResultStats {
+        match self {
+            TaskResult::Completed { stats, .. } | TaskResult::Failed { stats, .. } => stats,
+        }
+    }
+
+    pub(crate) fn stats_mut(&mut self) -> &mut TaskResultStats {
+        match self {
+            TaskResult::Completed { stats, .. } | TaskResult::Failed { stats, .. } => stats,
+        }
+    }
+}
+
 impl serde::Serialize for TaskResult {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -85,9 +131,10 @@ impl serde::Serialize for TaskResult {
                 state.serialize_field("stats", stats)?;
                 state.end()
             }
-            TaskResult::Failed { error, stats } => {
-                let mut state = serializer.serialize_struct("TaskResult", 2)?;
+            TaskResult::Failed { error, info, stats } => {
+                let mut state = serializer.serialize_struct("TaskResult", 3)?;
                 state.serialize_field("error", error)?;
+                state.serialize_field("error_info", info)?;
                 state.serialize_field("stats", stats)?;
                 state.end()
             }
@@ -120,6 +167,7 @@ impl<'de> serde::Deserialize<'de> for TaskResult {
                 let mut stderr = None;
                 let mut exit_code = None;
                 let mut error = None;
+                let mut info = None;
                 let mut stats = None;
 
                 while let Some(key) = map.next_key::<String>()? {
@@ -128,6 +176,7 @@ impl<'de> serde::Deserialize<'de> for TaskResult {
                         "stderr" => stderr = Some(map.next_value()?),
                         "exit_code" => exit_code = Some(map.next_value()?),
                         "error" => error = Some(map.next_value()?),
+                        "error_info" => info = Some(map.next_value()?),
                         "stats" => stats = Some(map.next_value()?),
                         _ => {
                             let _: serde_json::Value = map.next_value()?;
@@ -138,7 +187,11 @@ impl<'de> serde::Deserialize<'de> for TaskResult {
                 let stats = stats.ok_or_else(|| Error::missing_field("stats"))?;
 
                 if let Some(error) = error {
-                    Ok(TaskResult::Failed { error, stats })
+                    Ok(TaskResult::Failed {
+                        error,
+                        info: info.unwrap_or_default(),
+                        stats,
+                    })
                 } else {
                     let stdout = stdout.ok_or_else(|| Error::missing_field("stdout"))?;
                     let stderr = stderr.ok_or_else(|| Error::missing_field("stderr"))?;
@@ -155,7 +208,14 @@ impl<'de> serde::Deserialize<'de> for TaskResult {
 
         deserializer.deserialize_struct(
             "TaskResult",
-            &["stdout", "stderr", "exit_code", "error", "stats"],
+            &[
+                "stdout",
+                "stderr",
+                "exit_code",
+                "error",
+                "error_info",
+                "stats",
+            ],
             TaskResultVisitor,
         )
     }
@@ -167,6 +227,10 @@ pub enum TaskOutcome {
     Exited,
     Signaled,
     TimedOut,
+    CpuTimeLimit,
+    /// Stopped after using almost no CPU and writing no output for the
+    /// idle timeout, e.g. while blocked reading stdin.
+    IdleTimeout,
     OutOfMemory,
     PidsLimit,
     OutputLimit,
@@ -178,23 +242,247 @@ pub enum TaskOutcome {
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct TaskResultStats {
     pub memory_peak_bytes: u64,
+    /// Largest `memory.stat` anonymous and page-cache bytes sampled while the
+    /// task ran or when it exited.
+    #[serde(default)]
+    pub memory_anon_bytes: u64,
+    #[serde(default)]
+    pub memory_file_bytes: u64,
     pub cpu_usage_usec: u64,
     #[serde(default)]
     pub cpu_nr_throttled: u64,
     #[serde(default)]
     pub cpu_throttled_usec: u64,
     pub pids_peak: u64,
+    /// Block-device I/O from `io.stat`, summed over devices.
+    #[serde(default)]
+    pub io_read_bytes: u64,
+    #[serde(default)]
+    pub io_write_bytes: u64,
+    #[serde(default)]
+    pub io_read_ops: u64,
+    #[serde(default)]
+    pub io_write_ops: u64,
     pub execution_time_ms: u64,
     pub stdout_truncated: bool,
     pub stderr_truncated: bool,
+    /// Bytes of each stream that were dropped to stay within the output
+    /// limit.
+    #[serde(default)]
+    pub stdout_elided_bytes: u64,
+    #[serde(default)]
+    pub stderr_elided_bytes: u64,
+    /// Where each stream was stored when it was too large to return inline;
+    /// the stream's string is then empty.
+    #[serde(default)]
+    pub stdout_file: Option<StoredOutput>,
+    #[serde(default)]
+    pub stderr_file: Option<StoredOutput>,
+    /// Host file each stream was spilled to when it outgrew the inline
+    /// limit of a container's output spill; the stream's string is then
+    /// empty. Whoever runs the task moves the file and clears this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout_spill: Option<SpilledOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_spill: Option<SpilledOutput>,
     #[serde(default)]
     pub outcome: TaskOutcome,
     #[serde(default)]
     pub termination_signal: Option<i32>,
     #[serde(default)]
     pub oom_kill_count: u64,
+    /// `memory.events` counts: OOM conditions, and how often usage reached
+    /// `memory.high` (throttled) or `memory.max` (reclaimed or OOM).
+    #[serde(default)]
+    pub oom_count: u64,
+    #[serde(default)]
+    pub memory_high_count: u64,
+    #[serde(default)]
+    pub memory_max_count: u64,
     #[serde(default)]
     pub pids_limit_hit_count: u64,
     #[serde(default)]
     pub cleanup_succeeded: bool,
+    #[serde(default)]
+    pub image_digest: Option<String>,
+    /// Whether a Landlock ruleset confined the task's filesystem access.
+    #[serde(default)]
+    pub landlock_enforced: bool,
+    /// Time the task spent stalled on CPU, memory and I/O, if the kernel
+    /// reports pressure stall information.
+    #[serde(default)]
+    pub pressure: Option<TaskPressure>,
+    /// Resource usage sampled while the task ran, oldest first.
+    #[serde(default)]
+    pub timeline: Vec<ResourceSample>,
+    /// `wait4` resource usage of the task's main process, unlike the
+    /// cgroup-wide figures above.
+    #[serde(default)]
+    pub process_usage: Option<ProcessUsage>,
+    /// How the task was stopped after exceeding a limit, if it was.
+    #[serde(default)]
+    pub termination_phase: Option<TerminationPhase>,
+    /// Stdout and stderr interleaved, if the task asked for a merged
+    /// [`OutputCapture`](crate::OutputCapture).
+    #[serde(default)]
+    pub merged_output: Option<MergedOutput>,
+    /// Where the time spent running the task went.
+    #[serde(default)]
+    pub timings: TaskTimings,
+}
+
+/// Monotonic time spent in each phase of running one task, in
+/// microseconds. Phases a backend does not have are left at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TaskTimings {
+    /// Creating the task cgroup and applying its limits; zero when a
+    /// pre-warmed cgroup was used.
+    pub cgroup_setup_usec: u64,
+    /// Writing the task's files and opening its stdin file.
+    pub file_setup_usec: u64,
+    /// Forking the task and setting up its user namespace.
+    pub spawn_usec: u64,
+    /// From spawning the task until it and its output were collected.
+    pub run_usec: u64,
+    /// Reading the task cgroup's statistics and removing it.
+    pub cleanup_usec: u64,
+}
+
+/// Monotonic time spent in each phase of running a task group, in
+/// microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GroupTimings {
+    /// Waiting for CPUs to be leased before the execution could start. Not
+    /// part of `total_usec`.
+    #[serde(default)]
+    pub queue_usec: u64,
+    /// Building the container; zero when a pre-warmed container was used.
+    pub container_setup_usec: u64,
+    /// Running every step.
+    pub steps_usec: u64,
+    /// Tearing the container down.
+    pub cleanup_usec: u64,
+    /// The whole execution, including forking and collecting the result.
+    pub total_usec: u64,
+}
+
+/// Output moved out of a result into a file store.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StoredOutput {
+    pub file_id: String,
+    pub size: u64,
+    /// `sha256:` followed by the hex digest of the output.
+    pub digest: String,
+}
+
+/// Output the runtime wrote to a spill file instead of the result.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SpilledOutput {
+    pub path: PathBuf,
+    pub size: u64,
+    /// `sha256:` followed by the hex digest of the file's bytes.
+    pub digest: String,
+}
+
+/// A task's stdout and stderr as one stream, in the order the runtime read
+/// them. It is bounded by the output limit like each stream on its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MergedOutput {
+    pub chunks: Vec<OutputChunk>,
+    /// Bytes dropped from the middle (head and tail retention) or the end
+    /// (head retention) of the stream.
+    pub elided_bytes: u64,
+}
+
+/// Output read from one stream, merging reads within the same millisecond.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OutputChunk {
+    pub stream: OutputStream,
+    /// When the chunk's first read happened, in milliseconds since the task
+    /// started.
+    pub elapsed_ms: u64,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// The step of a [`TerminationPolicy`](crate::TerminationPolicy) that ended
+/// a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationPhase {
+    /// The task exited within the grace period after the termination signal.
+    Signal,
+    /// Every process left was killed through the cgroup.
+    Kill,
+}
+
+/// Resource usage of a task's main process and the descendants it waited
+/// for, as reported by `wait4`. Helpers left running in the background are
+/// not included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProcessUsage {
+    pub user_cpu_usec: u64,
+    pub system_cpu_usec: u64,
+    pub max_rss_bytes: u64,
+    pub voluntary_context_switches: u64,
+    pub involuntary_context_switches: u64,
+    pub minor_page_faults: u64,
+    pub major_page_faults: u64,
+}
+
+impl From<&nix::libc::rusage> for ProcessUsage {
+    fn from(usage: &nix::libc::rusage) -> Self {
+        let usec = |time: nix::libc::timeval| time.tv_sec as u64 * 1_000_000 + time.tv_usec as u64;
+        Self {
+            user_cpu_usec: usec(usage.ru_utime),
+            system_cpu_usec: usec(usage.ru_stime),
+            // Linux reports the maximum resident set size in kilobytes.
+            max_rss_bytes: usage.ru_maxrss as u64 * 1024,
+            voluntary_context_switches: usage.ru_nvcsw as u64,
+            involuntary_context_switches: usage.ru_nivcsw as u64,
+            minor_page_faults: usage.ru_minflt as u64,
+            major_page_faults: usage.ru_majflt as u64,
+        }
+    }
+}
+
+/// Pressure stall information from the task cgroup's `*.pressure` files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct TaskPressure {
+    pub cpu: PressureStall,
+    pub memory: PressureStall,
+    pub io: PressureStall,
+}
+
+/// How long a task was stalled on one resource over its whole run.
+///
+/// `some` counts time in which at least one of the task's processes waited
+/// for the resource, `full` time in which all of them did. The averages are
+/// percentages of the task's wall time, so a high `cpu.some_avg` with little
+/// CPU usage points at host contention rather than the program.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct PressureStall {
+    pub some_avg: f64,
+    pub full_avg: f64,
+    pub some_usec: u64,
+    pub full_usec: u64,
+}
+
+/// One point of a task's resource timeline. Stall times are cumulative
+/// `some` totals, as in the `*.pressure` files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResourceSample {
+    pub elapsed_ms: u64,
+    pub memory_bytes: u64,
+    pub pids: u64,
+    pub cpu_usage_usec: u64,
+    pub cpu_stall_usec: u64,
+    pub memory_stall_usec: u64,
+    pub io_stall_usec: u64,
 }
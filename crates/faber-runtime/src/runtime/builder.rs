@@ -1,5 +1,6 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
+use super::pool::ContainerPool;
 use crate::{
     Runtime,
     cgroup::{Cgroup, CgroupConfig},
@@ -14,6 +15,7 @@ pub struct RuntimeBuilder {
     timeout: Duration,
     cpu_time_limit: Duration,
     output_limit: usize,
+    pool: Option<Arc<ContainerPool>>,
 }
 
 impl Default for RuntimeBuilder {
@@ -25,6 +27,7 @@ impl Default for RuntimeBuilder {
             timeout: Duration::from_secs(5),
             cpu_time_limit: Duration::from_secs(5),
             output_limit: 1024 * 1024,
+            pool: None,
         }
     }
 }
@@ -60,14 +63,71 @@ impl RuntimeBuilder {
         self
     }
 
+    /// Claims a pre-warmed container from `pool` when one matches this
+    /// runtime's container and cgroup configuration.
+    pub fn with_pool(mut self, pool: Arc<ContainerPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
     pub fn build(self) -> Runtime {
+        // Name task cgroups after the container so a handle can find them.
+        let cgroup = self.cgroup.scoped(self.container.config().id.clone());
+
         Runtime {
             task_group: self.task_group,
             container: self.container,
-            cgroup: self.cgroup,
+            cgroup,
             timeout: self.timeout,
             cpu_time_limit: self.cpu_time_limit,
             output_limit: self.output_limit,
+            pool: self.pool,
+            events: None,
+        }
+    }
+}
+
+pub struct ContainerPoolBuilder {
+    shapes: Vec<(ContainerConfig, CgroupConfig)>,
+    size: usize,
+    refill_rate: u32,
+}
+
+impl Default for ContainerPoolBuilder {
+    fn default() -> Self {
+        Self {
+            shapes: vec![],
+            size: 2,
+            refill_rate: 4,
         }
     }
 }
+
+impl ContainerPoolBuilder {
+    /// Keeps containers ready for executions built from these configurations.
+    pub fn with_shape(
+        mut self,
+        container_config: ContainerConfig,
+        cgroup_config: CgroupConfig,
+    ) -> Self {
+        self.shapes.push((container_config, cgroup_config));
+        self
+    }
+
+    /// Number of ready containers to keep per shape.
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Maximum number of containers started per second across all shapes.
+    pub fn with_refill_rate(mut self, refill_rate: u32) -> Self {
+        self.refill_rate = refill_rate;
+        self
+    }
+
+    /// Builds the pool and starts filling it in the background.
+    pub fn build(self) -> Arc<ContainerPool> {
+        ContainerPool::start(self.shapes, self.size, self.refill_rate)
+    }
+}
@@ -8,14 +8,19 @@ use std::{
     },
     path::{Component, Path},
     process::exit,
-    time::Duration,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use caps::CapSet;
 use nix::{
     libc,
     sched::{CloneFlags, unshare},
-    sys::wait::{WaitPidFlag, WaitStatus, waitpid},
+    sys::{
+        signal::{SigSet, Signal},
+        signalfd::{SfdFlags, SignalFd},
+        wait::{WaitPidFlag, WaitStatus, waitpid},
+    },
     unistd::{ForkResult, Pid, chdir, execvpe, fork, pipe, setgid, setgroups, setuid},
 };
 
@@ -24,15 +29,39 @@ type RlimitResource = libc::__rlimit_resource_t;
 #[cfg(target_env = "musl")]
 type RlimitResource = libc::c_int;
 
+use super::{
+    handle::{ExecutionProcess, RuntimeEvent, RuntimeHandle},
+    landlock::{self, LandlockRules},
+    output::{MergedBuffer, OutputBuffer, SpillDir},
+    pool::{ContainerPool, PoolJob},
+    supervisor::{Supervisor, SupervisorEvent},
+};
 use crate::{
-    cgroup::{Cgroup, task::TaskCgroup},
-    container::Container,
+    cgroup::{Cgroup, ResourceTimeline, task::TaskCgroup},
+    container::{Container, ContainerConfig},
     prelude::*,
-    result::{ExecutionStepResult, RuntimeResult, TaskOutcome, TaskResult, TaskResultStats},
-    task::{ExecutionStep, SandboxProfile, Task, TaskGroup},
-    utils::{close_fd, mk_pipe},
+    result::{
+        ExecutionStepResult, GroupTimings, MergedOutput, OutputStream, ProcessUsage,
+        ResourceSample, RuntimeResult, SpilledOutput, TaskOutcome, TaskResult, TaskResultStats,
+        TaskTimings, TerminationPhase,
+    },
+    task::{ExecutionStep, OutputCapture, SandboxProfile, Task, TaskGroup},
+    utils::{close_fd, elapsed_usec, mk_pipe},
 };
 
+/// How often a task's cgroup CPU usage is checked against its budget; a task
+/// may overshoot by this interval times the CPUs it can use.
+const CPU_TIME_SAMPLE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Longest grace period a termination policy gets, whatever it asks for.
+/// Callers that accept tasks from users should bound it further, as the API
+/// does.
+const MAX_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// A task counts as idle while its CPU time stays below wall time divided by
+/// this, i.e. under 1% of one CPU.
+const IDLE_CPU_DIVISOR: u32 = 100;
+
 pub struct Runtime {
     pub(crate) task_group: TaskGroup,
     pub(crate) container: Container,
@@ -40,23 +69,55 @@ pub struct Runtime {
     pub(crate) timeout: Duration,
     pub(crate) cpu_time_limit: Duration,
     pub(crate) output_limit: usize,
+    pub(crate) pool: Option<Arc<ContainerPool>>,
+    /// Where the execution process reports lifecycle events, if anyone listens.
+    pub(crate) events: Option<PipeWriter>,
 }
 
-struct CollectedOutput {
-    stdout: Vec<u8>,
-    stderr: Vec<u8>,
-    exit_code: i32,
-    stdout_truncated: bool,
-    stderr_truncated: bool,
-    termination_signal: Option<i32>,
-    timed_out: bool,
-    output_terminated: bool,
+/// What a supervisor observed while a task ran.
+pub(crate) struct CollectedOutput {
+    pub(crate) stdout: Vec<u8>,
+    pub(crate) stderr: Vec<u8>,
+    pub(crate) exit_code: i32,
+    pub(crate) stdout_truncated: bool,
+    pub(crate) stderr_truncated: bool,
+    pub(crate) stdout_elided_bytes: u64,
+    pub(crate) stderr_elided_bytes: u64,
+    pub(crate) stdout_spill: Option<SpilledOutput>,
+    pub(crate) stderr_spill: Option<SpilledOutput>,
+    pub(crate) merged_output: Option<MergedOutput>,
+    pub(crate) termination_signal: Option<i32>,
+    pub(crate) timed_out: bool,
+    pub(crate) cpu_time_exceeded: bool,
+    pub(crate) idle_timed_out: bool,
+    pub(crate) output_terminated: bool,
+    pub(crate) timeline: Vec<ResourceSample>,
+    /// Largest anonymous and page-cache bytes seen while sampling the task.
+    pub(crate) memory_breakdown_peak: (u64, u64),
+    pub(crate) process_usage: Option<ProcessUsage>,
+    pub(crate) termination_phase: Option<TerminationPhase>,
 }
 
 impl Runtime {
+    /// Containers this process failed to tear down since it started.
+    pub fn container_cleanup_failures() -> u64 {
+        Container::cleanup_failures()
+    }
+
     pub fn execute(&self) -> Result<RuntimeResult> {
+        let start = Instant::now();
         Cgroup::ensure_faber_cgroup_hierarchy()?;
 
+        if let Some(pool) = &self.pool
+            && let Some(warm) = pool.claim(self.container.config(), self.cgroup.config())
+        {
+            let mut runtime_result = warm.run(&self.job())?;
+            if let Some(timings) = runtime_result.timings_mut() {
+                timings.total_usec = elapsed_usec(start);
+            }
+            return Ok(runtime_result);
+        }
+
         let (reader, writer) = mk_pipe()?;
 
         match unsafe { fork() } {
@@ -75,26 +136,135 @@ impl Runtime {
                 let runtime_result = serde_json::from_reader(reader);
                 waitpid(child, None).map_err(|e| FaberError::WaitPid { e })?;
 
+                let cleanup_start = Instant::now();
                 if let Err(e) = self.container.cleanup() {
                     eprintln!("Failed to cleanup container: {}", e);
                 }
+                let cleanup_usec = elapsed_usec(cleanup_start);
+
+                let mut runtime_result: RuntimeResult =
+                    runtime_result.map_err(|e| FaberError::ParseResult {
+                        e,
+                        details: "Failed to parse results from child process".to_string(),
+                    })?;
+                if let Some(timings) = runtime_result.timings_mut() {
+                    timings.cleanup_usec = cleanup_usec;
+                    timings.total_usec = elapsed_usec(start);
+                }
+                Ok(runtime_result)
+            }
+            Err(e) => Err(FaberError::Fork { e }),
+        }
+    }
 
-                runtime_result.map_err(|e| FaberError::ParseResult {
-                    e,
-                    details: "Failed to parse results from child process".to_string(),
+    /// Starts the execution without blocking and returns a handle to await,
+    /// observe or cancel it. Must be called within a tokio runtime.
+    pub fn spawn(mut self) -> Result<RuntimeHandle> {
+        Cgroup::ensure_faber_cgroup_hierarchy()?;
+
+        if let Some(pool) = self.pool.take()
+            && let Some(warm) = pool.claim(self.container.config(), self.cgroup.config())
+        {
+            return RuntimeHandle::start(warm.into_process(self.job()));
+        }
+
+        let (reader, writer) = mk_pipe()?;
+        let (events_reader, events_writer) = mk_pipe()?;
+
+        match unsafe { fork() } {
+            Ok(ForkResult::Child) => {
+                drop(reader);
+                drop(events_reader);
+                let _ = Self::set_nonblocking(events_writer.as_raw_fd());
+                self.events = Some(events_writer);
+
+                let runtime_result = self.execution_child();
+                let _ = serde_json::to_writer(writer, &runtime_result);
+                exit(0);
+            }
+            Ok(ForkResult::Parent { child }) => {
+                drop(writer);
+                drop(events_writer);
+
+                RuntimeHandle::start(ExecutionProcess {
+                    pid: child,
+                    scope: self.container.config().id.clone(),
+                    container: self.container,
+                    job: None,
+                    result: reader,
+                    events: events_reader,
                 })
             }
             Err(e) => Err(FaberError::Fork { e }),
         }
     }
 
+    fn job(&self) -> PoolJob {
+        PoolJob {
+            task_group: self.task_group.clone(),
+            timeout: self.timeout,
+            cpu_time_limit: self.cpu_time_limit,
+            output_limit: self.output_limit,
+            cpuset: self.cgroup.config().cpuset.clone(),
+        }
+    }
+
+    /// Reports `event` to the spawning handle. Events are best effort: the
+    /// pipe is non-blocking and a full or closed pipe drops them.
+    pub(crate) fn emit(&self, event: RuntimeEvent) {
+        if let Some(events) = &self.events
+            && let Ok(mut line) = serde_json::to_vec(&event)
+        {
+            line.push(b'\n');
+            let _ = (&*events).write_all(&line);
+        }
+    }
+
     fn execution_child(&self) -> RuntimeResult {
-        if let Err(e) = self.container.setup() {
-            return RuntimeResult::ContainerSetupFailed {
-                error: format!("Container setup failed: {}", e),
-            };
+        let setup_start = Instant::now();
+        // The spill directory is opened first: setup pivots into the
+        // container root, where the host path is no longer visible.
+        let spill = match self.open_spill_dir().and_then(|spill| {
+            self.container.setup()?;
+            Ok(spill)
+        }) {
+            Ok(spill) => spill,
+            Err(e) => {
+                return RuntimeResult::ContainerSetupFailed {
+                    error: format!("Container setup failed: {}", e),
+                    info: e.info().in_stage(ErrorStage::ContainerSetup),
+                };
+            }
+        };
+
+        let container_setup_usec = elapsed_usec(setup_start);
+
+        self.emit(RuntimeEvent::ContainerReady);
+        let mut runtime_result = self.run_task_group(None, spill.as_ref());
+        if let Some(timings) = runtime_result.timings_mut() {
+            timings.container_setup_usec = container_setup_usec;
         }
+        runtime_result
+    }
 
+    /// Opens the container's output spill directory, if it has one.
+    pub(crate) fn open_spill_dir(&self) -> Result<Option<SpillDir>> {
+        self.container
+            .config()
+            .output_spill
+            .as_ref()
+            .map(SpillDir::open)
+            .transpose()
+    }
+
+    /// Runs the task group inside an already set up container. A pre-warmed
+    /// task cgroup, if any, is used for the first sequential task. Output
+    /// past the inline limit goes to `spill`.
+    pub(crate) fn run_task_group(
+        &self,
+        mut prewarmed: Option<TaskCgroup>,
+        spill: Option<&SpillDir>,
+    ) -> RuntimeResult {
         // Fork a dedicated "init" process to keep the PID namespace alive.
         // container.setup() calls unshare(CLONE_NEWPID), so the first child we
         // fork becomes PID 1 in the new namespace. If PID 1 exits, the kernel
@@ -107,64 +277,143 @@ impl Runtime {
             Err(e) => {
                 return RuntimeResult::ContainerSetupFailed {
                     error: format!("Failed to fork namespace init process: {}", e),
+                    info: FaberError::Fork { e }
+                        .info()
+                        .in_stage(ErrorStage::ContainerSetup),
                 };
             }
         };
 
         let mut results = Vec::with_capacity(self.task_group.len());
 
-        for step in &self.task_group {
+        let steps_start = Instant::now();
+        for (index, step) in self.task_group.iter().enumerate() {
+            self.emit(RuntimeEvent::StepStarted { index });
             let result = match step {
-                ExecutionStep::Single(task) => self.execute_single(task.clone()),
-                ExecutionStep::Parallel(tasks) => self.execute_parallel(tasks.clone()),
+                ExecutionStep::Single(task) => {
+                    self.execute_single(task.clone(), prewarmed.take(), spill)
+                }
+                ExecutionStep::Parallel(tasks) => self.execute_parallel(tasks.clone(), spill),
             };
             results.push(result);
+            self.emit(RuntimeEvent::StepFinished { index });
+        }
+        let steps_usec = elapsed_usec(steps_start);
+
+        // The caller exits without running destructors, so remove an unused
+        // pre-warmed cgroup explicitly.
+        if let Some(task_cgroup) = prewarmed
+            && let Err(e) = task_cgroup.cleanup()
+        {
+            eprintln!("Warning: Failed to cleanup pre-warmed task cgroup: {}", e);
         }
 
         // Tear down the namespace init process
         let _ = nix::sys::signal::kill(init_pid, nix::sys::signal::Signal::SIGKILL);
         let _ = waitpid(init_pid, None);
 
-        RuntimeResult::Success(results)
+        // Record which image the tasks ran against so results are reproducible.
+        if let Some(digest) = self.container.image_digest() {
+            for task_result in results.iter_mut().flat_map(|step| step.task_results_mut()) {
+                task_result.stats_mut().image_digest = Some(digest.to_string());
+            }
+        }
+
+        RuntimeResult::Success(
+            results,
+            GroupTimings {
+                steps_usec,
+                ..Default::default()
+            },
+        )
     }
 
     fn run_namespace_init() -> ! {
+        // Die with the runtime process even if it is killed before it can
+        // tear this process down.
+        unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) };
+
+        // Block SIGCHLD before the first reap so that no exit can slip in
+        // between draining zombies and waiting on the signalfd.
+        let mut mask = SigSet::empty();
+        mask.add(Signal::SIGCHLD);
+        let signal_fd = match mask
+            .thread_block()
+            .and_then(|()| SignalFd::with_flags(&mask, SfdFlags::SFD_CLOEXEC))
+        {
+            Ok(signal_fd) => signal_fd,
+            Err(error) => {
+                eprintln!("Namespace init failed to watch for SIGCHLD: {error}");
+                exit(125);
+            }
+        };
+
         loop {
-            match waitpid(Pid::from_raw(-1), Some(WaitPidFlag::WNOHANG)) {
-                Ok(WaitStatus::StillAlive) | Err(nix::errno::Errno::ECHILD) => {
-                    std::thread::sleep(Duration::from_millis(10));
+            loop {
+                match waitpid(Pid::from_raw(-1), Some(WaitPidFlag::WNOHANG)) {
+                    Ok(WaitStatus::StillAlive) | Err(nix::errno::Errno::ECHILD) => break,
+                    Ok(_) | Err(nix::errno::Errno::EINTR) => {}
+                    Err(error) => {
+                        eprintln!("Namespace init failed to reap a descendant: {error}");
+                        exit(125);
+                    }
                 }
-                Ok(_) => {}
-                Err(nix::errno::Errno::EINTR) => {}
+            }
+
+            // SIGCHLD notifications coalesce, so each one triggers a full
+            // drain above rather than a single reap.
+            match signal_fd.read_signal() {
+                Ok(_) | Err(nix::errno::Errno::EINTR) => {}
                 Err(error) => {
-                    eprintln!("Namespace init failed to reap a descendant: {error}");
+                    eprintln!("Namespace init failed to read SIGCHLD: {error}");
                     exit(125);
                 }
             }
         }
     }
 
-    fn execute_single(&self, task: Task) -> ExecutionStepResult {
-        match Self::execute_single_task(
-            task,
-            &self.cgroup,
-            self.timeout,
-            self.cpu_time_limit,
-            self.output_limit,
-        ) {
-            Ok(task_result) => ExecutionStepResult::Single(task_result),
+    fn execute_single(
+        &self,
+        task: Task,
+        prewarmed: Option<TaskCgroup>,
+        spill: Option<&SpillDir>,
+    ) -> ExecutionStepResult {
+        let cgroup_start = Instant::now();
+        let task_cgroup = match prewarmed {
+            Some(task_cgroup) => Ok(task_cgroup),
+            None => self.cgroup.create_task_cgroup(),
+        };
+        let cgroup_setup_usec = elapsed_usec(cgroup_start);
+
+        let landlock = self.container.config().landlock_rules();
+        match task_cgroup.and_then(|task_cgroup| {
+            Self::execute_single_task(
+                task,
+                task_cgroup,
+                self.timeout,
+                self.cpu_time_limit,
+                (self.output_limit, spill),
+                landlock.as_ref(),
+            )
+        }) {
+            Ok(mut task_result) => {
+                task_result.stats_mut().timings.cgroup_setup_usec = cgroup_setup_usec;
+                ExecutionStepResult::Single(task_result)
+            }
             Err(e) => ExecutionStepResult::Single(TaskResult::Failed {
                 error: format!("Task execution failed: {}", e),
+                info: e.info(),
                 stats: TaskResultStats::default(),
             }),
         }
     }
 
-    fn execute_parallel(&self, tasks: Vec<Task>) -> ExecutionStepResult {
+    fn execute_parallel(&self, tasks: Vec<Task>, spill: Option<&SpillDir>) -> ExecutionStepResult {
         // Cannot use std::thread::spawn after unshare(CLONE_NEWPID) because
         // the kernel rejects CLONE_THREAD when pid_ns_for_children differs
         // from the active PID namespace (EINVAL). Use fork + pipes instead.
         let mut children: Vec<(Pid, std::io::PipeReader)> = Vec::with_capacity(tasks.len());
+        let landlock = self.container.config().landlock_rules();
 
         for task in tasks {
             let pipe = match mk_pipe() {
@@ -172,6 +421,7 @@ impl Runtime {
                 Err(e) => {
                     return ExecutionStepResult::Parallel(vec![TaskResult::Failed {
                         error: format!("Failed to create pipe for parallel task: {}", e),
+                        info: e.info(),
                         stats: TaskResultStats::default(),
                     }]);
                 }
@@ -181,16 +431,26 @@ impl Runtime {
             match unsafe { fork() } {
                 Ok(ForkResult::Child) => {
                     drop(reader);
-                    let result = match Self::execute_single_task(
-                        task,
-                        &self.cgroup,
-                        self.timeout,
-                        self.cpu_time_limit,
-                        self.output_limit,
-                    ) {
-                        Ok(task_result) => task_result,
+                    let cgroup_start = Instant::now();
+                    let task_cgroup = self.cgroup.create_task_cgroup();
+                    let cgroup_setup_usec = elapsed_usec(cgroup_start);
+                    let result = match task_cgroup.and_then(|task_cgroup| {
+                        Self::execute_single_task(
+                            task,
+                            task_cgroup,
+                            self.timeout,
+                            self.cpu_time_limit,
+                            (self.output_limit, spill),
+                            landlock.as_ref(),
+                        )
+                    }) {
+                        Ok(mut task_result) => {
+                            task_result.stats_mut().timings.cgroup_setup_usec = cgroup_setup_usec;
+                            task_result
+                        }
                         Err(e) => TaskResult::Failed {
                             error: format!("Task execution failed: {}", e),
+                            info: e.info(),
                             stats: TaskResultStats::default(),
                         },
                     };
@@ -204,6 +464,7 @@ impl Runtime {
                 Err(e) => {
                     return ExecutionStepResult::Parallel(vec![TaskResult::Failed {
                         error: format!("Failed to fork parallel task: {}", e),
+                        info: FaberError::Fork { e }.info(),
                         stats: TaskResultStats::default(),
                     }]);
                 }
@@ -218,6 +479,7 @@ impl Runtime {
             let result: TaskResult =
                 serde_json::from_reader(reader).unwrap_or(TaskResult::Failed {
                     error: "Failed to read result from parallel task".to_string(),
+                    info: ErrorInfo::new(ErrorCode::TaskIo, ErrorStage::Execution),
                     stats: TaskResultStats::default(),
                 });
             let _ = waitpid(child, None);
@@ -229,24 +491,35 @@ impl Runtime {
 
     fn execute_single_task(
         task: Task,
-        cgroup: &Cgroup,
+        task_cgroup: TaskCgroup,
         timeout: std::time::Duration,
         cpu_time_limit: std::time::Duration,
-        output_limit: usize,
+        (output_limit, spill): (usize, Option<&SpillDir>),
+        landlock: Option<&LandlockRules>,
     ) -> Result<TaskResult> {
         use std::time::Instant;
 
         let start_time = Instant::now();
+        let termination = task.termination.unwrap_or_default();
+        let stop_signal = termination.signal()?;
+        let capture = task.capture.unwrap_or_default();
 
-        // Create task cgroup before fork
-        let task_cgroup = cgroup.create_task_cgroup()?;
-
+        let file_start = Instant::now();
         // Materialize files relative to the workspace without following links.
         // This happens before privilege dropping, so path resolution must fail closed.
         for (file_path, file_content) in task.files.clone().unwrap_or_default() {
-            Self::write_workspace_file(&file_path, file_content.as_bytes())?;
+            Self::write_workspace_file(Path::new("."), &file_path, file_content.as_bytes())?;
         }
 
+        // Each task opens its stdin file itself so that tasks sharing one
+        // read it from the start independently.
+        let stdin_file = task
+            .stdin_file_id()?
+            .map(Self::open_stdin_file)
+            .transpose()?;
+        let file_setup_usec = elapsed_usec(file_start);
+        let spawn_start = Instant::now();
+
         // Create pipes for stdout, stderr, stdin
         let (stdout_read, stdout_write) = pipe().map_err(|e| FaberError::MkPipe {
             e: std::io::Error::from_raw_os_error(e as i32),
@@ -300,13 +573,19 @@ impl Runtime {
                 unsafe {
                     libc::dup2(stdout_write.as_raw_fd(), libc::STDOUT_FILENO);
                     libc::dup2(stderr_write.as_raw_fd(), libc::STDERR_FILENO);
-                    libc::dup2(stdin_read.as_raw_fd(), libc::STDIN_FILENO);
+                    libc::dup2(
+                        stdin_file
+                            .as_ref()
+                            .map_or(stdin_read.as_raw_fd(), AsRawFd::as_raw_fd),
+                        libc::STDIN_FILENO,
+                    );
                 }
 
                 // Close original fds after dup2
                 drop(stdout_write);
                 drop(stderr_write);
                 drop(stdin_read);
+                drop(stdin_file);
 
                 // Apply security restrictions
                 if let Err(e) = Self::child_setup_security(
@@ -315,6 +594,7 @@ impl Runtime {
                     user_continue_read.into(),
                     proc_pid,
                     sandbox_profile,
+                    landlock,
                 ) {
                     eprintln!("Security setup failed: {}", e);
                     exit(126);
@@ -348,15 +628,15 @@ impl Runtime {
                     }
                 }
 
-                if !has_path {
-                    if let (Ok(k), Ok(v)) = (
+                if !has_path
+                    && let (Ok(k), Ok(v)) = (
                         CString::new("PATH"),
                         CString::new(
                             "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin",
                         ),
-                    ) {
-                        env_vars.push((k, v));
-                    }
+                    )
+                {
+                    env_vars.push((k, v));
                 }
 
                 // Build args
@@ -394,6 +674,7 @@ impl Runtime {
                 drop(stdout_write);
                 drop(stderr_write);
                 drop(stdin_read);
+                drop(stdin_file);
                 drop(user_ready_write);
                 drop(user_continue_read);
 
@@ -402,7 +683,9 @@ impl Runtime {
                     user_ready_read.into(),
                     user_continue_write.into(),
                 )?;
+                let spawn_usec = elapsed_usec(spawn_start);
 
+                let run_start = Instant::now();
                 let collected = Self::wait_and_collect_output(
                     child,
                     timeout,
@@ -410,10 +693,13 @@ impl Runtime {
                     stderr_read.into(),
                     stdin_write.into(),
                     task.stdin.unwrap_or_default().into_bytes(),
-                    output_limit,
+                    (output_limit, capture, spill),
                     &task_cgroup,
+                    (stop_signal, termination.grace_period()),
                 )?;
+                let run_usec = elapsed_usec(run_start);
 
+                let cleanup_start = Instant::now();
                 // Measure resources
                 let task_stats = match task_cgroup.measure_resources() {
                     Ok(stats) => stats,
@@ -423,6 +709,7 @@ impl Runtime {
                     }
                 };
 
+                let pressure = task_cgroup.measure_pressure(start_time.elapsed());
                 let events = task_cgroup.measure_events();
                 let cleanup_succeeded = match task_cgroup.cleanup() {
                     Ok(()) => true,
@@ -431,8 +718,13 @@ impl Runtime {
                         false
                     }
                 };
-                let outcome = if collected.timed_out {
+                let cleanup_usec = elapsed_usec(cleanup_start);
+                let outcome = if collected.cpu_time_exceeded {
+                    TaskOutcome::CpuTimeLimit
+                } else if collected.timed_out {
                     TaskOutcome::TimedOut
+                } else if collected.idle_timed_out {
+                    TaskOutcome::IdleTimeout
                 } else if collected.output_terminated {
                     TaskOutcome::OutputLimit
                 } else if events.oom_kill_count > 0 {
@@ -450,17 +742,50 @@ impl Runtime {
                 let stats = TaskResultStats {
                     execution_time_ms: start_time.elapsed().as_millis() as u64,
                     memory_peak_bytes: task_stats.memory_peak_bytes,
+                    memory_anon_bytes: task_stats
+                        .memory_anon_bytes
+                        .max(collected.memory_breakdown_peak.0),
+                    memory_file_bytes: task_stats
+                        .memory_file_bytes
+                        .max(collected.memory_breakdown_peak.1),
                     cpu_usage_usec: task_stats.cpu_usage_usec,
                     cpu_nr_throttled: task_stats.cpu_nr_throttled,
                     cpu_throttled_usec: task_stats.cpu_throttled_usec,
                     pids_peak: task_stats.pids_max,
+                    io_read_bytes: task_stats.io_read_bytes,
+                    io_write_bytes: task_stats.io_write_bytes,
+                    io_read_ops: task_stats.io_read_ops,
+                    io_write_ops: task_stats.io_write_ops,
                     stdout_truncated: collected.stdout_truncated,
                     stderr_truncated: collected.stderr_truncated,
+                    stdout_elided_bytes: collected.stdout_elided_bytes,
+                    stderr_elided_bytes: collected.stderr_elided_bytes,
+                    stdout_file: None,
+                    stderr_file: None,
+                    stdout_spill: collected.stdout_spill,
+                    stderr_spill: collected.stderr_spill,
                     outcome,
                     termination_signal: collected.termination_signal,
                     oom_kill_count: events.oom_kill_count,
+                    oom_count: events.oom_count,
+                    memory_high_count: events.memory_high_count,
+                    memory_max_count: events.memory_max_count,
                     pids_limit_hit_count: events.pids_limit_hit_count,
                     cleanup_succeeded,
+                    image_digest: None,
+                    landlock_enforced: landlock.is_some() && landlock::abi_version().is_some(),
+                    pressure,
+                    timeline: collected.timeline,
+                    process_usage: collected.process_usage,
+                    termination_phase: collected.termination_phase,
+                    merged_output: collected.merged_output,
+                    timings: TaskTimings {
+                        file_setup_usec,
+                        spawn_usec,
+                        run_usec,
+                        cleanup_usec,
+                        ..Default::default()
+                    },
                 };
 
                 Ok(TaskResult::Completed {
@@ -474,7 +799,8 @@ impl Runtime {
         }
     }
 
-    fn write_workspace_file(file_path: &str, content: &[u8]) -> Result<()> {
+    /// Checks that a submitted file path names something below the workspace.
+    pub(crate) fn workspace_file_path(file_path: &str) -> Result<&Path> {
         let path = Path::new(file_path);
         if file_path.is_empty()
             || path.is_absolute()
@@ -487,11 +813,40 @@ impl Runtime {
                 details: "paths must be normalized and relative to the workspace".to_string(),
             });
         }
+        Ok(path)
+    }
+
+    /// Opens the stdin file `id` where the container bound it.
+    fn open_stdin_file(id: &str) -> Result<std::fs::File> {
+        let path = ContainerConfig::stdin_file_target(id)?;
+        OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(&path)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => FaberError::InvalidTask {
+                    details: format!("stdin file '{id}' was not provided"),
+                },
+                _ => FaberError::Generic {
+                    message: format!("Failed to open stdin file '{id}': {e}"),
+                },
+            })
+    }
+
+    /// Writes a task file below `workspace` with openat2, refusing symlinks
+    /// anywhere in the path: earlier steps may have planted them, and the
+    /// file is written with the daemon's privileges.
+    pub(crate) fn write_workspace_file(
+        workspace: &Path,
+        file_path: &str,
+        content: &[u8],
+    ) -> Result<()> {
+        let path = Self::workspace_file_path(file_path)?;
 
         let workspace = OpenOptions::new()
             .read(true)
             .custom_flags(libc::O_DIRECTORY | libc::O_CLOEXEC)
-            .open(".")
+            .open(workspace)
             .map_err(|e| FaberError::WriteFile {
                 e,
                 details: "Failed to open the task workspace".to_string(),
@@ -576,14 +931,14 @@ impl Runtime {
         user_continue: PipeReader,
         proc_pid: u32,
         sandbox_profile: SandboxProfile,
+        landlock: Option<&LandlockRules>,
     ) -> std::io::Result<()> {
         let unshare_flags = CloneFlags::CLONE_NEWNS;
 
-        unshare(unshare_flags).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        unshare(unshare_flags).map_err(std::io::Error::other)?;
 
         // mask_paths unmounts and masks proc/sys with tmpfs for security
-        Container::mask_paths()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+        Container::mask_paths().map_err(|e| std::io::Error::other(e.to_string()))?;
 
         // Mount a fresh proc filesystem in the child's PID namespace
         // This is critical: the child is PID 1 in the new PID namespace,
@@ -602,6 +957,11 @@ impl Runtime {
         Self::clear_linux_capability_sets()?;
         Self::drop_posix_capabilities()?;
         Self::set_no_new_privileges()?;
+        // Landlock only narrows what the mounts already allow, so a mount
+        // accidentally left writable still cannot be written to.
+        if let Some(rules) = landlock {
+            rules.restrict_self()?;
+        }
         Self::apply_seccomp_filter(sandbox_profile)?;
 
         Ok(())
@@ -655,7 +1015,7 @@ impl Runtime {
         Ok(())
     }
 
-    fn set_nonblocking(fd: i32) -> std::io::Result<()> {
+    pub(crate) fn set_nonblocking(fd: i32) -> std::io::Result<()> {
         let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
         if flags < 0 {
             return Err(std::io::Error::last_os_error());
@@ -666,22 +1026,23 @@ impl Runtime {
         Ok(())
     }
 
+    /// Reads everything currently available from `reader` into `output`
+    /// and, if set, the merged stream. Returns whether the pipe is still open.
     fn drain_pipe(
         reader: &mut PipeReader,
-        output: &mut Vec<u8>,
-        output_limit: usize,
-        truncated: &mut bool,
+        output: &mut OutputBuffer,
+        stream: OutputStream,
+        mut merged: Option<&mut MergedBuffer>,
+        elapsed: Duration,
     ) -> std::io::Result<bool> {
         let mut chunk = [0u8; 8192];
         loop {
             match reader.read(&mut chunk) {
                 Ok(0) => return Ok(false),
                 Ok(bytes_read) => {
-                    let remaining = output_limit.saturating_sub(output.len());
-                    let retained = remaining.min(bytes_read);
-                    output.extend_from_slice(&chunk[..retained]);
-                    if retained < bytes_read {
-                        *truncated = true;
+                    output.push(&chunk[..bytes_read]);
+                    if let Some(merged) = merged.as_deref_mut() {
+                        merged.push(stream, elapsed, &chunk[..bytes_read]);
                     }
                 }
                 Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => return Ok(true),
@@ -707,11 +1068,10 @@ impl Runtime {
         mut stderr_reader: PipeReader,
         stdin_writer: PipeWriter,
         stdin: Vec<u8>,
-        output_limit: usize,
+        (output_limit, capture, spill): (usize, OutputCapture, Option<&SpillDir>),
         task_cgroup: &TaskCgroup,
+        (stop_signal, grace_period): (Signal, Duration),
     ) -> Result<CollectedOutput> {
-        use std::time::Instant;
-
         Self::set_nonblocking(stdout_reader.as_raw_fd()).map_err(|error| FaberError::Generic {
             message: format!("Failed to make stdout nonblocking: {error}"),
         })?;
@@ -722,144 +1082,294 @@ impl Runtime {
             message: format!("Failed to make stdin nonblocking: {error}"),
         })?;
 
-        let start_time = Instant::now();
-        let mut stdout = Vec::with_capacity(output_limit.min(8192));
-        let mut stderr = Vec::with_capacity(output_limit.min(8192));
+        let stream_limit = capture.stream_limit(output_limit);
+        let mut stdout = OutputBuffer::new(stream_limit, &capture, spill);
+        let mut stderr = OutputBuffer::new(stream_limit, &capture, spill);
+        let mut merged = capture
+            .merged
+            .then(|| MergedBuffer::new(SpillDir::merged_limit(spill, output_limit), &capture));
         let mut stdout_open = true;
         let mut stderr_open = true;
-        let mut stdin_writer = Some(stdin_writer);
+        let mut stdin_writer = (!stdin.is_empty()).then_some(stdin_writer);
         let mut stdin_offset = 0;
         let mut exit_code = None;
-        let mut stdout_truncated = false;
-        let mut stderr_truncated = false;
         let mut output_terminated = false;
         let mut timed_out = false;
+        let mut cpu_time_exceeded = false;
+        let mut idle_timed_out = false;
         let mut termination_signal = None;
+        let mut process_usage = None;
+        let mut termination_phase = None;
+
+        let started = Instant::now();
+        let mut supervisor = Supervisor::new(child, timeout)?;
+        let cpu_time_budget = task_cgroup.cpu_time_budget();
+        let idle_timeout = task_cgroup.idle_timeout();
+        let mut timeline = task_cgroup.sample_interval().map(ResourceTimeline::new);
+        let mut memory_breakdown_peak = (0, 0);
+        // The task makes progress while it writes output or keeps using CPU.
+        let (mut last_progress, mut last_check, mut last_cpu_usage) =
+            (started, started, Duration::ZERO);
+        let sample_interval = [
+            cpu_time_budget.map(|_| CPU_TIME_SAMPLE_INTERVAL),
+            task_cgroup.sample_interval(),
+            idle_timeout.map(|timeout| {
+                (timeout / 10).clamp(CPU_TIME_SAMPLE_INTERVAL, Duration::from_secs(1))
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        if let Some(interval) = sample_interval {
+            supervisor.sample_every(interval)?;
+        }
+        supervisor.watch(&stdout_reader, SupervisorEvent::Stdout)?;
+        supervisor.watch(&stderr_reader, SupervisorEvent::Stderr)?;
+        if let Some(writer) = stdin_writer.as_ref() {
+            supervisor.watch(writer, SupervisorEvent::Stdin)?;
+        }
 
-        loop {
-            if stdin_offset == stdin.len() {
-                stdin_writer = None;
-            }
-
-            let mut poll_fds = Vec::with_capacity(3);
-            if stdout_open {
-                poll_fds.push(libc::pollfd {
-                    fd: stdout_reader.as_raw_fd(),
-                    events: libc::POLLIN | libc::POLLHUP,
-                    revents: 0,
-                });
-            }
-            if stderr_open {
-                poll_fds.push(libc::pollfd {
-                    fd: stderr_reader.as_raw_fd(),
-                    events: libc::POLLIN | libc::POLLHUP,
-                    revents: 0,
-                });
-            }
-            if let Some(writer) = stdin_writer.as_ref() {
-                poll_fds.push(libc::pollfd {
-                    fd: writer.as_raw_fd(),
-                    events: libc::POLLOUT | libc::POLLHUP,
-                    revents: 0,
-                });
-            }
-
-            let poll_result =
-                unsafe { libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as libc::nfds_t, 10) };
-            if poll_result < 0
-                && std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted
-            {
-                return Err(FaberError::Generic {
-                    message: format!(
-                        "Failed to poll task pipes: {}",
-                        std::io::Error::last_os_error()
-                    ),
-                });
-            }
-
-            if stdout_open {
-                stdout_open = Self::drain_pipe(
-                    &mut stdout_reader,
-                    &mut stdout,
-                    output_limit,
-                    &mut stdout_truncated,
-                )
-                .map_err(|error| FaberError::Generic {
-                    message: format!("Failed to read task stdout: {error}"),
-                })?;
-            }
-            if stderr_open {
-                stderr_open = Self::drain_pipe(
-                    &mut stderr_reader,
-                    &mut stderr,
-                    output_limit,
-                    &mut stderr_truncated,
-                )
-                .map_err(|error| FaberError::Generic {
-                    message: format!("Failed to read task stderr: {error}"),
-                })?;
-            }
-
-            if let Some(writer) = stdin_writer.as_mut() {
-                match writer.write(&stdin[stdin_offset..]) {
-                    Ok(0) => stdin_writer = None,
-                    Ok(bytes_written) => stdin_offset += bytes_written,
-                    Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {}
-                    Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
-                    Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => {
-                        stdin_writer = None;
+        let mut ready = Vec::with_capacity(6);
+        while exit_code.is_none() || stdout_open || stderr_open {
+            supervisor.wait(&mut ready)?;
+
+            for event in &ready {
+                match event {
+                    SupervisorEvent::Stdout => {
+                        last_progress = Instant::now();
+                        stdout_open = Self::drain_pipe(
+                            &mut stdout_reader,
+                            &mut stdout,
+                            OutputStream::Stdout,
+                            merged.as_mut(),
+                            started.elapsed(),
+                        )
+                        .map_err(|error| FaberError::Generic {
+                            message: format!("Failed to read task stdout: {error}"),
+                        })?;
+                        if !stdout_open {
+                            supervisor.unwatch(&stdout_reader);
+                        }
                     }
-                    Err(error) => {
-                        return Err(FaberError::Generic {
-                            message: format!("Failed to write task stdin: {error}"),
-                        });
+                    SupervisorEvent::Stderr => {
+                        last_progress = Instant::now();
+                        stderr_open = Self::drain_pipe(
+                            &mut stderr_reader,
+                            &mut stderr,
+                            OutputStream::Stderr,
+                            merged.as_mut(),
+                            started.elapsed(),
+                        )
+                        .map_err(|error| FaberError::Generic {
+                            message: format!("Failed to read task stderr: {error}"),
+                        })?;
+                        if !stderr_open {
+                            supervisor.unwatch(&stderr_reader);
+                        }
                     }
-                }
-            }
-
-            if !output_terminated && (stdout_truncated || stderr_truncated) {
-                task_cgroup.kill_all_processes()?;
-                stdin_writer = None;
-                output_terminated = true;
-            }
-
-            if exit_code.is_none() {
-                match waitpid(child, Some(WaitPidFlag::WNOHANG)) {
-                    Ok(WaitStatus::StillAlive) => {}
-                    Ok(status) => {
-                        if let Some((code, signal)) = Self::wait_status_result(status) {
-                            exit_code = Some(code);
-                            termination_signal = signal;
+                    SupervisorEvent::Stdin => {
+                        let Some(writer) = stdin_writer.as_mut() else {
+                            continue;
+                        };
+                        match writer.write(&stdin[stdin_offset..]) {
+                            Ok(0) => supervisor.release(&mut stdin_writer),
+                            Ok(bytes_written) => {
+                                stdin_offset += bytes_written;
+                                if stdin_offset == stdin.len() {
+                                    supervisor.release(&mut stdin_writer);
+                                }
+                            }
+                            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {}
+                            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                            Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => {
+                                supervisor.release(&mut stdin_writer);
+                            }
+                            Err(error) => {
+                                return Err(FaberError::Generic {
+                                    message: format!("Failed to write task stdin: {error}"),
+                                });
+                            }
+                        }
+                    }
+                    SupervisorEvent::Exited => {
+                        match Self::reap_with_usage(child) {
+                            Ok((WaitStatus::StillAlive, _)) => continue,
+                            Ok((status, usage)) => {
+                                process_usage = Some(usage);
+                                if let Some((code, signal)) = Self::wait_status_result(status) {
+                                    exit_code = Some(code);
+                                    termination_signal = signal;
+                                }
+                            }
+                            Err(nix::errno::Errno::ECHILD) => exit_code = Some(-1),
+                            Err(error) => return Err(FaberError::WaitPid { e: error }),
+                        }
+                        if exit_code.is_some() {
+                            supervisor.unwatch_exit();
+                        }
+                    }
+                    SupervisorEvent::Deadline => {
+                        supervisor.release(&mut stdin_writer);
+                        if termination_phase.is_some() {
+                            // The grace period is over.
+                            task_cgroup.kill_all_processes()?;
+                            supervisor.unwatch_deadline();
+                            termination_phase = Some(TerminationPhase::Kill);
+                        } else {
+                            timed_out = true;
+                            termination_phase = Some(Self::begin_termination(
+                                task_cgroup,
+                                &mut supervisor,
+                                stop_signal,
+                                grace_period,
+                            )?);
+                        }
+                    }
+                    SupervisorEvent::Sample => {
+                        supervisor.acknowledge_sample();
+                        let elapsed = started.elapsed();
+                        let (anon, file) = task_cgroup.memory_breakdown();
+                        memory_breakdown_peak = (
+                            memory_breakdown_peak.0.max(anon),
+                            memory_breakdown_peak.1.max(file),
+                        );
+                        if let Some(timeline) = timeline.as_mut()
+                            && timeline.is_due(elapsed)
+                        {
+                            timeline.record(task_cgroup.sample(elapsed));
+                        }
+                        if termination_phase.is_none()
+                            && let Some(budget) = cpu_time_budget
+                            && task_cgroup.cpu_usage() >= budget
+                        {
+                            supervisor.release(&mut stdin_writer);
+                            cpu_time_exceeded = true;
+                            termination_phase = Some(Self::begin_termination(
+                                task_cgroup,
+                                &mut supervisor,
+                                stop_signal,
+                                grace_period,
+                            )?);
+                        }
+                        if termination_phase.is_none()
+                            && let Some(idle_timeout) = idle_timeout
+                        {
+                            let now = Instant::now();
+                            let cpu_usage = task_cgroup.cpu_usage();
+                            if cpu_usage.saturating_sub(last_cpu_usage) * IDLE_CPU_DIVISOR
+                                >= now - last_check
+                            {
+                                last_progress = now;
+                            }
+                            (last_check, last_cpu_usage) = (now, cpu_usage);
+
+                            if now - last_progress >= idle_timeout {
+                                supervisor.release(&mut stdin_writer);
+                                idle_timed_out = true;
+                                termination_phase = Some(Self::begin_termination(
+                                    task_cgroup,
+                                    &mut supervisor,
+                                    stop_signal,
+                                    grace_period,
+                                )?);
+                            }
                         }
                     }
-                    Err(nix::errno::Errno::ECHILD) => exit_code = Some(-1),
-                    Err(error) => return Err(FaberError::WaitPid { e: error }),
                 }
             }
 
-            if exit_code.is_some() && !stdout_open && !stderr_open {
-                break;
-            }
-
-            if !timed_out && start_time.elapsed() > timeout {
-                task_cgroup.kill_all_processes()?;
-                stdin_writer = None;
-                timed_out = true;
+            if termination_phase.is_none()
+                && capture.stops_at_limit()
+                && (stdout.is_truncated() || stderr.is_truncated())
+            {
+                supervisor.release(&mut stdin_writer);
+                output_terminated = true;
+                termination_phase = Some(Self::begin_termination(
+                    task_cgroup,
+                    &mut supervisor,
+                    stop_signal,
+                    grace_period,
+                )?);
             }
         }
 
+        let (stdout_truncated, stderr_truncated) = (stdout.is_truncated(), stderr.is_truncated());
+        let (stdout_elided_bytes, stderr_elided_bytes) = (stdout.elided(), stderr.elided());
+        let (stdout, stdout_spill) = stdout.finish();
+        let (stderr, stderr_spill) = stderr.finish();
         Ok(CollectedOutput {
             stdout,
             stderr,
-            exit_code: exit_code.unwrap_or(-1),
             stdout_truncated,
             stderr_truncated,
+            stdout_elided_bytes,
+            stderr_elided_bytes,
+            stdout_spill,
+            stderr_spill,
+            merged_output: merged.map(MergedBuffer::into_output),
+            exit_code: exit_code.unwrap_or(-1),
             termination_signal,
             timed_out,
+            cpu_time_exceeded,
+            idle_timed_out,
             output_terminated,
+            timeline: timeline
+                .map(ResourceTimeline::into_samples)
+                .unwrap_or_default(),
+            memory_breakdown_peak,
+            process_usage,
+            termination_phase,
         })
     }
 
+    /// Starts stopping a task that exceeded a limit: sends `signal` and lets
+    /// the deadline bound `grace_period`, or kills the cgroup at once when
+    /// there is no grace period.
+    fn begin_termination(
+        task_cgroup: &TaskCgroup,
+        supervisor: &mut Supervisor,
+        signal: Signal,
+        grace_period: Duration,
+    ) -> Result<TerminationPhase> {
+        supervisor.stop_sampling();
+        let grace_period = grace_period.min(MAX_GRACE_PERIOD);
+        if grace_period.is_zero() {
+            task_cgroup.kill_all_processes()?;
+            supervisor.unwatch_deadline();
+            return Ok(TerminationPhase::Kill);
+        }
+        task_cgroup.signal_processes(signal);
+        supervisor.rearm_deadline(grace_period)?;
+        Ok(TerminationPhase::Signal)
+    }
+
+    /// Reaps `child` if it has exited, like `waitpid` with `WNOHANG`, and
+    /// returns the resource usage `wait4` reports for it.
+    fn reap_with_usage(child: Pid) -> nix::Result<(WaitStatus, ProcessUsage)> {
+        let mut status = 0;
+        let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+        let reaped = unsafe {
+            libc::wait4(
+                child.as_raw(),
+                &mut status,
+                libc::WNOHANG,
+                usage.as_mut_ptr(),
+            )
+        };
+        match reaped {
+            -1 => Err(nix::errno::Errno::last()),
+            0 => Ok((WaitStatus::StillAlive, ProcessUsage::default())),
+            pid => {
+                // wait4 fills in the usage of every child it reaps.
+                let usage = unsafe { usage.assume_init() };
+                Ok((
+                    WaitStatus::from_raw(Pid::from_raw(pid), status)?,
+                    ProcessUsage::from(&usage),
+                ))
+            }
+        }
+    }
+
     fn configure_child_user_namespace(
         child: Pid,
         mut user_ready: PipeReader,
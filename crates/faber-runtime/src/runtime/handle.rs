@@ -0,0 +1,223 @@
+use std::{
+    io::{PipeReader, PipeWriter},
+    os::fd::OwnedFd,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use nix::{
+    sys::{signal::Signal, wait::waitpid},
+    unistd::Pid,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, unix::AsyncFd},
+    net::unix::pipe,
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use super::pool::PoolJob;
+use crate::{
+    cgroup::Cgroup,
+    container::Container,
+    prelude::*,
+    result::RuntimeResult,
+    utils::{elapsed_usec, pidfd_open, pidfd_send_signal},
+};
+
+/// Progress reported by a spawned execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RuntimeEvent {
+    /// The container is set up and the first step is about to start.
+    ContainerReady,
+    /// The step at `index` in the task group started.
+    StepStarted { index: usize },
+    /// The step at `index` in the task group finished.
+    StepFinished { index: usize },
+    /// The execution was cancelled through its handle.
+    Cancelled,
+}
+
+/// The forked process running an execution, as seen from the runtime.
+pub(crate) struct ExecutionProcess {
+    pub(crate) pid: Pid,
+    pub(crate) container: Container,
+    /// Prefix of the execution's task cgroup names.
+    pub(crate) scope: String,
+    /// A job still to be sent to a pre-warmed container.
+    pub(crate) job: Option<(PipeWriter, PoolJob)>,
+    pub(crate) result: PipeReader,
+    pub(crate) events: PipeReader,
+}
+
+/// A running execution started with [`Runtime::spawn`](crate::Runtime::spawn).
+///
+/// Dropping the handle does not stop the execution; its container is still
+/// cleaned up in the background once it finishes.
+pub struct RuntimeHandle {
+    result: JoinHandle<Result<RuntimeResult>>,
+    events: mpsc::UnboundedReceiver<RuntimeEvent>,
+    cancel: Arc<CancelState>,
+}
+
+struct CancelState {
+    /// Signalled instead of the PID, which may be reused once the
+    /// supervisor has reaped the execution.
+    pidfd: OwnedFd,
+    scope: String,
+    cancelled: AtomicBool,
+}
+
+impl RuntimeHandle {
+    /// Supervises `process` on the current tokio runtime.
+    pub(crate) fn start(process: ExecutionProcess) -> Result<Self> {
+        let pidfd = pidfd_open(process.pid).map_err(|e| FaberError::Generic {
+            message: format!("Failed to open pidfd for execution: {e}"),
+        })?;
+        let async_error = |e: std::io::Error| FaberError::Generic {
+            message: format!("Failed to register execution with the async runtime: {e}"),
+        };
+        let cancel_pidfd = pidfd.try_clone().map_err(async_error)?;
+        let pidfd = AsyncFd::new(pidfd).map_err(async_error)?;
+        let result =
+            pipe::Receiver::from_owned_fd(OwnedFd::from(process.result)).map_err(async_error)?;
+        let events =
+            pipe::Receiver::from_owned_fd(OwnedFd::from(process.events)).map_err(async_error)?;
+        let job = match process.job {
+            Some((writer, job)) => Some((
+                pipe::Sender::from_owned_fd(OwnedFd::from(writer)).map_err(async_error)?,
+                job,
+            )),
+            None => None,
+        };
+
+        let cancel = Arc::new(CancelState {
+            pidfd: cancel_pidfd,
+            scope: process.scope.clone(),
+            cancelled: AtomicBool::new(false),
+        });
+        let (event_sender, event_receiver) = mpsc::unbounded_channel();
+        let result = tokio::spawn(Self::supervise(
+            process.pid,
+            pidfd,
+            process.container,
+            process.scope,
+            job,
+            result,
+            events,
+            event_sender,
+            cancel.clone(),
+        ));
+
+        Ok(Self {
+            result,
+            events: event_receiver,
+            cancel,
+        })
+    }
+
+    /// Waits for the execution to finish. Returns
+    /// [`FaberError::Cancelled`] if it was cancelled first.
+    pub async fn wait(self) -> Result<RuntimeResult> {
+        self.result.await.map_err(|e| FaberError::Generic {
+            message: format!("Execution supervisor failed: {e}"),
+        })?
+    }
+
+    /// Returns the next lifecycle event, or `None` once the execution has
+    /// finished and every event has been received.
+    pub async fn next_event(&mut self) -> Option<RuntimeEvent> {
+        self.events.recv().await
+    }
+
+    /// Kills the execution and every process in its task cgroups.
+    pub fn cancel(&self) {
+        if self.cancel.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        Cgroup::kill_scope(&self.cancel.scope);
+        let _ = pidfd_send_signal(&self.cancel.pidfd, Signal::SIGKILL);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn supervise(
+        pid: Pid,
+        pidfd: AsyncFd<OwnedFd>,
+        container: Container,
+        scope: String,
+        job: Option<(pipe::Sender, PoolJob)>,
+        mut result: pipe::Receiver,
+        events: pipe::Receiver,
+        event_sender: mpsc::UnboundedSender<RuntimeEvent>,
+        cancel: Arc<CancelState>,
+    ) -> Result<RuntimeResult> {
+        let start = Instant::now();
+        let forward_events = async {
+            let mut lines = BufReader::new(events).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Ok(event) = serde_json::from_str(&line) {
+                    let _ = event_sender.send(event);
+                }
+            }
+        };
+        let exchange = async {
+            if let Some((mut sender, job)) = job {
+                let job = serde_json::to_vec(&Some(job)).map_err(|e| FaberError::ParseResult {
+                    e,
+                    details: "Failed to serialize job for a pre-warmed container".to_string(),
+                })?;
+                // A failed send surfaces below as an unparsable result.
+                let _ = sender.write_all(&job).await;
+            }
+
+            let mut output = Vec::new();
+            result
+                .read_to_end(&mut output)
+                .await
+                .map_err(|e| FaberError::Generic {
+                    message: format!("Failed to read execution result: {e}"),
+                })?;
+            Ok(output)
+        };
+        let ((), output) = tokio::join!(forward_events, exchange);
+
+        let _ = pidfd.readable().await;
+        waitpid(pid, None).map_err(|e| FaberError::WaitPid { e })?;
+
+        let cleanup_start = Instant::now();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = container.cleanup() {
+                eprintln!("Failed to cleanup container: {}", e);
+            }
+            // Only left behind when the execution was killed.
+            Cgroup::remove_scope(&scope);
+        })
+        .await
+        .map_err(|e| FaberError::Generic {
+            message: format!("Container cleanup failed: {e}"),
+        })?;
+
+        if cancel.cancelled.load(Ordering::SeqCst) {
+            let _ = event_sender.send(RuntimeEvent::Cancelled);
+            return Err(FaberError::Cancelled);
+        }
+
+        let cleanup_usec = elapsed_usec(cleanup_start);
+
+        let mut runtime_result: RuntimeResult =
+            serde_json::from_slice(&output?).map_err(|e| FaberError::ParseResult {
+                e,
+                details: "Failed to parse results from child process".to_string(),
+            })?;
+        if let Some(timings) = runtime_result.timings_mut() {
+            timings.cleanup_usec = cleanup_usec;
+            timings.total_usec = elapsed_usec(start);
+        }
+        Ok(runtime_result)
+    }
+}
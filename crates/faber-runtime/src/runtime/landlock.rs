@@ -0,0 +1,177 @@
+use std::{
+    ffi::CString,
+    fs::File,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::{Path, PathBuf},
+};
+
+use nix::libc;
+
+// Filesystem access rights from <linux/landlock.h>, by the ABI version that
+// introduced them.
+const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+const ACCESS_FS_ABI_1: u64 = (1 << 13) - 1;
+const ACCESS_FS_REFER: u64 = 1 << 13;
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+
+/// Rights that may be granted on a file rather than a directory.
+const ACCESS_FILE: u64 = ACCESS_FS_EXECUTE
+    | ACCESS_FS_WRITE_FILE
+    | ACCESS_FS_READ_FILE
+    | ACCESS_FS_TRUNCATE
+    | ACCESS_FS_IOCTL_DEV;
+const ACCESS_READ: u64 = ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+const ACCESS_DEVICES: u64 =
+    ACCESS_READ | ACCESS_FS_WRITE_FILE | ACCESS_FS_TRUNCATE | ACCESS_FS_IOCTL_DEV;
+
+const CREATE_RULESET_VERSION: u32 = 1 << 0;
+const RULE_PATH_BENEATH: libc::c_int = 1;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+/// Returns the Landlock ABI version supported by the running kernel, or
+/// `None` if Landlock is unavailable or disabled.
+pub(crate) fn abi_version() -> Option<u32> {
+    let version = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            CREATE_RULESET_VERSION,
+        )
+    };
+    (version > 0).then_some(version as u32)
+}
+
+/// Filesystem access a task keeps once its Landlock ruleset is enforced.
+/// Paths are as seen inside the container.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LandlockRules {
+    read: Vec<PathBuf>,
+    execute: Vec<PathBuf>,
+    write: Vec<PathBuf>,
+}
+
+impl LandlockRules {
+    /// Allows reading `path` and, unless `executable` is false, running
+    /// programs from it.
+    pub(crate) fn read(mut self, path: impl Into<PathBuf>, executable: bool) -> Self {
+        let path = path.into();
+        if executable {
+            self.execute.push(path.clone());
+        }
+        self.read.push(path);
+        self
+    }
+
+    /// Allows every operation, including running programs, beneath `path`.
+    pub(crate) fn write(mut self, path: impl Into<PathBuf>) -> Self {
+        self.write.push(path.into());
+        self
+    }
+
+    /// Restricts the calling thread and its future children to these rules.
+    ///
+    /// Returns `Ok(false)` without restricting anything if the kernel does
+    /// not support Landlock. Requires `no_new_privs` to be set first.
+    pub(crate) fn restrict_self(&self) -> std::io::Result<bool> {
+        let Some(abi) = abi_version() else {
+            return Ok(false);
+        };
+        let mut handled = ACCESS_FS_ABI_1;
+        if abi >= 2 {
+            handled |= ACCESS_FS_REFER;
+        }
+        if abi >= 3 {
+            handled |= ACCESS_FS_TRUNCATE;
+        }
+        if abi >= 5 {
+            handled |= ACCESS_FS_IOCTL_DEV;
+        }
+
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        let ruleset = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr,
+                std::mem::size_of::<RulesetAttr>(),
+                0u32,
+            )
+        };
+        if ruleset < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let ruleset = unsafe { OwnedFd::from_raw_fd(ruleset as i32) };
+
+        for path in &self.read {
+            Self::add_rule(&ruleset, path, ACCESS_READ & handled)?;
+        }
+        for path in &self.execute {
+            Self::add_rule(&ruleset, path, (ACCESS_READ | ACCESS_FS_EXECUTE) & handled)?;
+        }
+        for path in &self.write {
+            Self::add_rule(&ruleset, path, handled)?;
+        }
+        // Standard streams may be redirected to device nodes such as /dev/null.
+        Self::add_rule(&ruleset, Path::new("/dev"), ACCESS_DEVICES & handled)?;
+        Self::add_rule(&ruleset, Path::new("/proc"), ACCESS_READ & handled)?;
+
+        if unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0u32) } < 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(true)
+    }
+
+    /// Grants `access` beneath `path`; paths missing from the container are
+    /// skipped.
+    fn add_rule(ruleset: &OwnedFd, path: &Path, access: u64) -> std::io::Result<()> {
+        let path_cstr = CString::new(path.as_os_str().as_bytes())?;
+        let fd = unsafe { libc::open(path_cstr.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Ok(());
+        }
+        let parent = unsafe { File::from_raw_fd(fd) };
+        let allowed_access = if parent.metadata()?.is_dir() {
+            access
+        } else {
+            access & ACCESS_FILE
+        };
+
+        let attr = PathBeneathAttr {
+            allowed_access,
+            parent_fd: parent.as_raw_fd(),
+        };
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                RULE_PATH_BENEATH,
+                &attr,
+                0u32,
+            )
+        };
+        if result < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
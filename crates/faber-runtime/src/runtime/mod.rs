@@ -1,5 +1,15 @@
 mod builder;
 mod core;
+mod handle;
+mod landlock;
+mod output;
+mod pool;
+mod supervisor;
 
-pub use builder::RuntimeBuilder;
+pub use builder::{ContainerPoolBuilder, RuntimeBuilder};
+pub(crate) use core::CollectedOutput;
 pub use core::Runtime;
+pub use handle::{RuntimeEvent, RuntimeHandle};
+pub(crate) use landlock::LandlockRules;
+pub(crate) use output::{MergedBuffer, OutputBuffer, SpillDir};
+pub use pool::{ContainerPool, PoolStats};
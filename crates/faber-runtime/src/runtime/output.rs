@@ -0,0 +1,621 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    os::{fd::OwnedFd, unix::fs::OpenOptionsExt},
+    path::PathBuf,
+    time::Duration,
+};
+
+use nix::{
+    fcntl::{OFlag, openat},
+    libc,
+    sys::stat::Mode,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    container::OutputSpill,
+    prelude::*,
+    result::{MergedOutput, OutputChunk, OutputStream, SpilledOutput},
+    task::{OutputCapture, OutputRetention},
+    utils::generate_random_string,
+};
+
+/// Splits `limit` into the bytes kept from the start and from the end of a
+/// stream.
+fn split_limit(limit: usize, retention: OutputRetention) -> (usize, usize) {
+    match retention {
+        OutputRetention::Head => (limit, 0),
+        OutputRetention::HeadAndTail => (limit - limit / 2, limit / 2),
+    }
+}
+
+/// The directory of an [`OutputSpill`], opened while the host filesystem is
+/// visible so that files can still be created in it from inside the
+/// container.
+pub(crate) struct SpillDir {
+    path: PathBuf,
+    dir: OwnedFd,
+    inline_limit: usize,
+}
+
+impl SpillDir {
+    pub(crate) fn open(spill: &OutputSpill) -> Result<Self> {
+        let open_error = |e| FaberError::Generic {
+            message: format!(
+                "Failed to open output spill directory {}: {e}",
+                spill.dir.display()
+            ),
+        };
+        fs::create_dir_all(&spill.dir).map_err(open_error)?;
+        let dir = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECTORY | libc::O_CLOEXEC)
+            .open(&spill.dir)
+            .map_err(open_error)?;
+        Ok(Self {
+            path: spill.dir.clone(),
+            dir: dir.into(),
+            inline_limit: spill.inline_limit,
+        })
+    }
+
+    /// The limit of a merged stream, which is always returned inline.
+    pub(crate) fn merged_limit(spill: Option<&Self>, output_limit: usize) -> usize {
+        spill.map_or(output_limit, |spill| output_limit.min(spill.inline_limit))
+    }
+
+    fn create(&self) -> Option<SpillFile> {
+        let name = format!("{}.out", generate_random_string(24));
+        match openat(
+            &self.dir,
+            name.as_str(),
+            OFlag::O_WRONLY | OFlag::O_CREAT | OFlag::O_EXCL | OFlag::O_APPEND | OFlag::O_CLOEXEC,
+            Mode::S_IRUSR | Mode::S_IWUSR,
+        ) {
+            Ok(file) => Some(SpillFile {
+                file: file.into(),
+                path: self.path.join(name),
+                hasher: Sha256::new(),
+                size: 0,
+            }),
+            Err(e) => {
+                eprintln!("Failed to create output spill file: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// A spill file being written, hashed as it grows.
+struct SpillFile {
+    file: File,
+    path: PathBuf,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl SpillFile {
+    /// Appends `bytes`, or leaves the file as it was if they cannot all be
+    /// written.
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        if let Err(e) = self.file.write_all(bytes) {
+            let _ = self.file.set_len(self.size);
+            return Err(e);
+        }
+        self.hasher.update(bytes);
+        self.size += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn finish(self) -> SpilledOutput {
+        SpilledOutput {
+            path: self.path,
+            size: self.size,
+            digest: format!("sha256:{}", hex::encode(self.hasher.finalize())),
+        }
+    }
+}
+
+/// One captured output stream, holding at most the output limit. With a
+/// spill directory, the head moves to a spill file once it outgrows the
+/// inline limit and keeps growing there; only the tail stays in memory.
+pub(crate) struct OutputBuffer<'a> {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    head_len: usize,
+    head_limit: usize,
+    tail_limit: usize,
+    elided: u64,
+    spill_dir: Option<&'a SpillDir>,
+    spill: Option<SpillFile>,
+}
+
+impl<'a> OutputBuffer<'a> {
+    pub(crate) fn new(
+        limit: usize,
+        capture: &OutputCapture,
+        spill_dir: Option<&'a SpillDir>,
+    ) -> Self {
+        let (head_limit, tail_limit) = split_limit(limit, capture.retain);
+        Self {
+            head: Vec::with_capacity(head_limit.min(8192)),
+            tail: VecDeque::new(),
+            head_len: 0,
+            head_limit,
+            tail_limit,
+            elided: 0,
+            spill_dir,
+            spill: None,
+        }
+    }
+
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        let room = self.head_limit.saturating_sub(self.head_len);
+        let (head, rest) = bytes.split_at(room.min(bytes.len()));
+        self.push_head(head);
+        self.tail.extend(rest);
+        let overflow = self.tail.len().saturating_sub(self.tail_limit);
+        self.tail.drain(..overflow);
+        self.elided += overflow as u64;
+    }
+
+    fn push_head(&mut self, bytes: &[u8]) {
+        if self.spill.is_some() {
+            self.spill_head(bytes);
+            return;
+        }
+        self.head.extend_from_slice(bytes);
+        self.head_len = self.head.len();
+        if let Some(spill_dir) = self.spill_dir
+            && self.head.len() > spill_dir.inline_limit
+        {
+            // Without a file the output stays in memory, as if there were no
+            // spill directory.
+            self.spill_dir = None;
+            self.spill = spill_dir.create();
+            if self.spill.is_some() {
+                let head = std::mem::take(&mut self.head);
+                self.head_len = 0;
+                self.spill_head(&head);
+            }
+        }
+    }
+
+    fn spill_head(&mut self, bytes: &[u8]) {
+        let Some(spill) = self.spill.as_mut() else {
+            return;
+        };
+        if let Err(e) = spill.write(bytes) {
+            // Keep what the file holds and drop the rest like output past
+            // the limit.
+            eprintln!("Failed to write output spill file: {e}");
+            self.head_limit = self.head_len;
+            self.elided += bytes.len() as u64;
+            return;
+        }
+        self.head_len += bytes.len();
+    }
+
+    /// Whether output had to be dropped to stay within the limit.
+    pub(crate) fn is_truncated(&self) -> bool {
+        self.elided > 0
+    }
+
+    pub(crate) fn elided(&self) -> u64 {
+        self.elided
+    }
+
+    /// The retained output, inline or in a spill file. Output whose tail
+    /// alone takes it past the inline limit is spilled here.
+    pub(crate) fn finish(mut self) -> (Vec<u8>, Option<SpilledOutput>) {
+        let spill = self.spill.take();
+        let spill_dir = self.spill_dir.take();
+        let bytes = self.into_bytes();
+        let spill = spill.or_else(|| {
+            spill_dir
+                .filter(|spill_dir| bytes.len() > spill_dir.inline_limit)
+                .and_then(SpillDir::create)
+        });
+        let Some(mut spill) = spill else {
+            return (bytes, None);
+        };
+        if let Err(e) = spill.write(&bytes) {
+            eprintln!("Failed to write output spill file: {e}");
+        }
+        (Vec::new(), Some(spill.finish()))
+    }
+
+    /// The output held in memory, with a marker where bytes were elided
+    /// between the head and the tail.
+    fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = self.head;
+        if self.elided > 0 && self.tail_limit > 0 {
+            bytes.extend_from_slice(
+                format!("\n[... {} bytes elided ...]\n", self.elided).as_bytes(),
+            );
+        }
+        bytes.extend(self.tail);
+        bytes
+    }
+}
+
+/// Most chunks a [`MergedBuffer`] keeps, split between head and tail like
+/// the byte limit, so that many tiny writes cannot grow it past the limit.
+const MAX_MERGED_CHUNKS: usize = 4096;
+
+struct RawChunk {
+    stream: OutputStream,
+    elapsed_ms: u64,
+    data: Vec<u8>,
+}
+
+impl RawChunk {
+    /// Appends `bytes` if they continue this chunk: same stream, read in the
+    /// same millisecond.
+    fn extend(&mut self, stream: OutputStream, elapsed_ms: u64, bytes: &[u8]) -> bool {
+        if self.stream != stream || self.elapsed_ms != elapsed_ms {
+            return false;
+        }
+        self.data.extend_from_slice(bytes);
+        true
+    }
+}
+
+/// Length of `bytes` without a UTF-8 sequence cut off at its end.
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    for (back, &byte) in bytes.iter().rev().take(3).enumerate() {
+        let width = match byte {
+            0x80..=0xbf => continue,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => break,
+        };
+        if back + 1 < width {
+            return bytes.len() - back - 1;
+        }
+        break;
+    }
+    bytes.len()
+}
+
+/// Decodes contiguous chunks, carrying a character split between two reads
+/// of a stream over to that stream's next chunk.
+fn decode_chunks(chunks: impl IntoIterator<Item = RawChunk>) -> Vec<OutputChunk> {
+    let mut decoded: Vec<OutputChunk> = Vec::new();
+    let mut pending = [Vec::new(), Vec::new()];
+    let slot = |stream| match stream {
+        OutputStream::Stdout => 0,
+        OutputStream::Stderr => 1,
+    };
+    for chunk in chunks {
+        let mut data = std::mem::take(&mut pending[slot(chunk.stream)]);
+        data.extend(chunk.data);
+        let complete = complete_utf8_len(&data);
+        pending[slot(chunk.stream)] = data.split_off(complete);
+        if !data.is_empty() {
+            decoded.push(OutputChunk {
+                stream: chunk.stream,
+                elapsed_ms: chunk.elapsed_ms,
+                data: String::from_utf8_lossy(&data).into_owned(),
+            });
+        }
+    }
+    // Whatever is still pending was cut off for good.
+    for (stream, rest) in [OutputStream::Stdout, OutputStream::Stderr]
+        .into_iter()
+        .zip(pending)
+        .filter(|(_, rest)| !rest.is_empty())
+    {
+        let rest = String::from_utf8_lossy(&rest);
+        match decoded
+            .iter_mut()
+            .rev()
+            .find(|chunk| chunk.stream == stream)
+        {
+            Some(chunk) => chunk.data.push_str(&rest),
+            None => decoded.push(OutputChunk {
+                stream,
+                elapsed_ms: 0,
+                data: rest.into_owned(),
+            }),
+        }
+    }
+    decoded
+}
+
+/// Stdout and stderr interleaved in read order, bounded like a single
+/// [`OutputBuffer`]. Reads from the same stream within one millisecond are
+/// merged into one chunk.
+pub(crate) struct MergedBuffer {
+    head: Vec<RawChunk>,
+    tail: VecDeque<RawChunk>,
+    head_bytes: usize,
+    tail_bytes: usize,
+    head_limit: usize,
+    tail_limit: usize,
+    head_chunk_limit: usize,
+    tail_chunk_limit: usize,
+    elided: u64,
+}
+
+impl MergedBuffer {
+    pub(crate) fn new(limit: usize, capture: &OutputCapture) -> Self {
+        let (head_limit, tail_limit) = split_limit(limit, capture.retain);
+        let (head_chunk_limit, tail_chunk_limit) = split_limit(MAX_MERGED_CHUNKS, capture.retain);
+        Self {
+            head: Vec::new(),
+            tail: VecDeque::new(),
+            head_bytes: 0,
+            tail_bytes: 0,
+            head_limit,
+            tail_limit,
+            head_chunk_limit,
+            tail_chunk_limit,
+            elided: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, stream: OutputStream, elapsed: Duration, bytes: &[u8]) {
+        let elapsed_ms = elapsed.as_millis() as u64;
+        let mut room = self.head_limit - self.head_bytes;
+        let merges = self
+            .head
+            .last()
+            .is_some_and(|last| last.stream == stream && last.elapsed_ms == elapsed_ms);
+        if !merges && self.head.len() == self.head_chunk_limit {
+            room = 0;
+        }
+        let (head, rest) = bytes.split_at(room.min(bytes.len()));
+        if !head.is_empty() {
+            self.head_bytes += head.len();
+            if !self
+                .head
+                .last_mut()
+                .is_some_and(|last| last.extend(stream, elapsed_ms, head))
+            {
+                self.head.push(RawChunk {
+                    stream,
+                    elapsed_ms,
+                    data: head.to_vec(),
+                });
+            }
+        }
+        if rest.is_empty() {
+            return;
+        }
+        if self.tail_limit == 0 {
+            self.elided += rest.len() as u64;
+            return;
+        }
+
+        self.tail_bytes += rest.len();
+        if !self
+            .tail
+            .back_mut()
+            .is_some_and(|last| last.extend(stream, elapsed_ms, rest))
+        {
+            self.tail.push_back(RawChunk {
+                stream,
+                elapsed_ms,
+                data: rest.to_vec(),
+            });
+        }
+        while self.tail.len() > self.tail_chunk_limit {
+            let Some(oldest) = self.tail.pop_front() else {
+                break;
+            };
+            self.tail_bytes -= oldest.data.len();
+            self.elided += oldest.data.len() as u64;
+        }
+        while self.tail_bytes > self.tail_limit {
+            let Some(oldest) = self.tail.front_mut() else {
+                break;
+            };
+            let dropped = (self.tail_bytes - self.tail_limit).min(oldest.data.len());
+            oldest.data.drain(..dropped);
+            if oldest.data.is_empty() {
+                self.tail.pop_front();
+            }
+            self.tail_bytes -= dropped;
+            self.elided += dropped as u64;
+        }
+    }
+
+    pub(crate) fn into_output(self) -> MergedOutput {
+        // Head and tail are decoded apart: a character split at the elision
+        // does not continue in the tail.
+        let mut chunks = decode_chunks(self.head);
+        chunks.extend(decode_chunks(self.tail));
+        MergedOutput {
+            chunks,
+            elided_bytes: self.elided,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(retain: OutputRetention) -> OutputCapture {
+        OutputCapture {
+            retain,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keeps_head_and_tail_around_an_elision_marker() {
+        let mut buffer = OutputBuffer::new(8, &capture(OutputRetention::HeadAndTail), None);
+        buffer.push(b"0123456789");
+        buffer.push(b"abcdef");
+
+        assert!(buffer.is_truncated());
+        assert_eq!(buffer.elided(), 8);
+        assert_eq!(buffer.into_bytes(), b"0123\n[... 8 bytes elided ...]\ncdef");
+    }
+
+    #[test]
+    fn output_within_the_limit_is_kept_whole() {
+        let mut buffer = OutputBuffer::new(8, &capture(OutputRetention::HeadAndTail), None);
+        buffer.push(b"0123");
+        buffer.push(b"4567");
+
+        assert!(!buffer.is_truncated());
+        assert_eq!(buffer.into_bytes(), b"01234567");
+    }
+
+    #[test]
+    fn output_past_the_inline_limit_is_spilled_raw() {
+        let dir = std::env::temp_dir().join(format!("faber-spill-{}", generate_random_string(8)));
+        let spill_dir = SpillDir::open(&OutputSpill {
+            dir: dir.clone(),
+            inline_limit: 4,
+        })
+        .unwrap();
+        let mut buffer =
+            OutputBuffer::new(16, &capture(OutputRetention::HeadAndTail), Some(&spill_dir));
+        buffer.push(b"\xff\x00\xfe");
+        buffer.push(b"3456789ab");
+        buffer.push(b"cdefghij");
+        assert!(buffer.head.is_empty());
+
+        let (inline, spilled) = buffer.finish();
+        let spilled = spilled.unwrap();
+        let content = fs::read(&spilled.path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(inline.is_empty());
+        assert_eq!(
+            content,
+            b"\xff\x00\xfe34567\n[... 4 bytes elided ...]\ncdefghij"
+        );
+        assert_eq!(spilled.size, content.len() as u64);
+        assert_eq!(
+            spilled.digest,
+            format!("sha256:{}", hex::encode(Sha256::digest(&content)))
+        );
+    }
+
+    #[test]
+    fn output_within_the_inline_limit_is_not_spilled() {
+        let dir = std::env::temp_dir().join(format!("faber-spill-{}", generate_random_string(8)));
+        let spill_dir = SpillDir::open(&OutputSpill {
+            dir: dir.clone(),
+            inline_limit: 4,
+        })
+        .unwrap();
+        let mut buffer = OutputBuffer::new(8, &capture(OutputRetention::Head), Some(&spill_dir));
+        buffer.push(b"0123");
+
+        let (inline, spilled) = buffer.finish();
+        let files = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(inline, b"0123");
+        assert!(spilled.is_none());
+        assert_eq!(files, 0);
+    }
+
+    #[test]
+    fn merged_stream_keeps_read_order_and_trims_the_oldest_tail_chunk() {
+        let mut merged = MergedBuffer::new(8, &capture(OutputRetention::HeadAndTail));
+        merged.push(OutputStream::Stdout, Duration::from_millis(1), b"out1");
+        merged.push(OutputStream::Stderr, Duration::from_millis(2), b"err1");
+        merged.push(OutputStream::Stdout, Duration::from_millis(3), b"out2");
+        merged.push(OutputStream::Stderr, Duration::from_millis(4), b"e2");
+
+        let output = merged.into_output();
+        let chunks: Vec<_> = output
+            .chunks
+            .iter()
+            .map(|chunk| (chunk.stream, chunk.elapsed_ms, chunk.data.as_str()))
+            .collect();
+        assert_eq!(
+            chunks,
+            [
+                (OutputStream::Stdout, 1, "out1"),
+                (OutputStream::Stdout, 3, "t2"),
+                (OutputStream::Stderr, 4, "e2"),
+            ]
+        );
+        assert_eq!(output.elided_bytes, 6);
+    }
+
+    #[test]
+    fn merged_stream_joins_reads_within_a_millisecond() {
+        let mut merged = MergedBuffer::new(64, &capture(OutputRetention::HeadAndTail));
+        merged.push(OutputStream::Stdout, Duration::from_micros(100), b"a");
+        merged.push(OutputStream::Stdout, Duration::from_micros(900), b"b");
+        merged.push(OutputStream::Stderr, Duration::from_micros(950), b"c");
+        merged.push(OutputStream::Stdout, Duration::from_millis(2), b"d");
+
+        let chunks: Vec<_> = merged
+            .into_output()
+            .chunks
+            .into_iter()
+            .map(|chunk| (chunk.stream, chunk.elapsed_ms, chunk.data))
+            .collect();
+        assert_eq!(
+            chunks,
+            [
+                (OutputStream::Stdout, 0, "ab".to_string()),
+                (OutputStream::Stderr, 0, "c".to_string()),
+                (OutputStream::Stdout, 2, "d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn merged_stream_bounds_the_number_of_chunks() {
+        let mut merged = MergedBuffer::new(1 << 20, &capture(OutputRetention::HeadAndTail));
+        for ms in 0..3 * MAX_MERGED_CHUNKS as u64 {
+            merged.push(OutputStream::Stdout, Duration::from_millis(ms), b"x");
+        }
+
+        let output = merged.into_output();
+        assert_eq!(output.chunks.len(), MAX_MERGED_CHUNKS);
+        assert_eq!(output.elided_bytes, 2 * MAX_MERGED_CHUNKS as u64);
+        assert_eq!(
+            output.chunks.last().unwrap().elapsed_ms,
+            3 * MAX_MERGED_CHUNKS as u64 - 1
+        );
+    }
+
+    #[test]
+    fn merged_stream_keeps_characters_split_across_reads() {
+        let mut merged = MergedBuffer::new(64, &capture(OutputRetention::HeadAndTail));
+        let snowman = "\u{2603}".as_bytes();
+        merged.push(
+            OutputStream::Stdout,
+            Duration::from_millis(1),
+            &snowman[..1],
+        );
+        merged.push(OutputStream::Stderr, Duration::from_millis(2), b"err");
+        merged.push(
+            OutputStream::Stdout,
+            Duration::from_millis(3),
+            &snowman[1..],
+        );
+        merged.push(
+            OutputStream::Stderr,
+            Duration::from_millis(4),
+            &snowman[..2],
+        );
+
+        let chunks: Vec<_> = merged
+            .into_output()
+            .chunks
+            .into_iter()
+            .map(|chunk| (chunk.stream, chunk.data))
+            .collect();
+        assert_eq!(
+            chunks,
+            [
+                (OutputStream::Stderr, "err\u{fffd}".to_string()),
+                (OutputStream::Stdout, "\u{2603}".to_string()),
+            ]
+        );
+    }
+}
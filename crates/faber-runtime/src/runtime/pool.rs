@@ -0,0 +1,453 @@
+use std::{
+    collections::VecDeque,
+    io::{PipeReader, PipeWriter, Read, Write},
+    os::fd::AsRawFd,
+    process::exit,
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use nix::{
+    sys::wait::{WaitPidFlag, WaitStatus, waitpid},
+    unistd::{ForkResult, Pid, fork},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    core::Runtime,
+    handle::{ExecutionProcess, RuntimeEvent},
+    output::SpillDir,
+};
+use crate::{
+    cgroup::{Cgroup, CgroupConfig, Cpuset},
+    container::{Container, ContainerConfig},
+    prelude::*,
+    result::RuntimeResult,
+    task::TaskGroup,
+    utils::{close_inherited_fds, elapsed_usec, mk_pipe},
+};
+
+/// How long the refill thread sleeps when every shape is full.
+const IDLE_INTERVAL: Duration = Duration::from_millis(50);
+/// Minimum back-off after a pre-warmed container fails to start.
+const FAILURE_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The per-request part of an execution, sent to a pre-warmed container once
+/// it has been claimed.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PoolJob {
+    pub(crate) task_group: TaskGroup,
+    pub(crate) timeout: Duration,
+    pub(crate) cpu_time_limit: Duration,
+    pub(crate) output_limit: usize,
+    /// CPUs leased for this execution, applied to the pre-warmed cgroup.
+    pub(crate) cpuset: Option<Cpuset>,
+}
+
+/// Counters describing how well the pool keeps up with demand.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PoolStats {
+    /// Containers set up and waiting to be claimed.
+    pub ready: usize,
+    /// Executions that claimed a pre-warmed container.
+    pub hits: u64,
+    /// Executions that found no pre-warmed container and set one up inline.
+    pub misses: u64,
+    /// Pre-warmed containers started since the pool was built.
+    pub spawned: u64,
+    /// Pre-warmed containers that failed during setup.
+    pub spawn_failures: u64,
+}
+
+/// A pool of set up, never used containers, each with a task cgroup already
+/// created for its first sequential task.
+///
+/// Containers are kept per shape: the container and cgroup configuration
+/// they were built from. An execution whose configuration matches a shape
+/// claims one of its containers, runs in it and destroys it afterwards; a
+/// background thread replaces claimed containers at a bounded rate.
+pub struct ContainerPool {
+    shapes: Mutex<Vec<PoolShape>>,
+    size: usize,
+    refill_interval: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    spawned: AtomicU64,
+    spawn_failures: AtomicU64,
+}
+
+struct PoolShape {
+    container: ContainerConfig,
+    cgroup: CgroupConfig,
+    ready: VecDeque<WarmContainer>,
+}
+
+impl ContainerPool {
+    pub(crate) fn start(
+        shapes: Vec<(ContainerConfig, CgroupConfig)>,
+        size: usize,
+        refill_rate: u32,
+    ) -> Arc<Self> {
+        let pool = Arc::new(Self {
+            shapes: Mutex::new(
+                shapes
+                    .into_iter()
+                    .map(|(container, cgroup)| PoolShape {
+                        container,
+                        cgroup,
+                        ready: VecDeque::with_capacity(size),
+                    })
+                    .collect(),
+            ),
+            size,
+            refill_interval: Duration::from_secs(1) / refill_rate.max(1),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            spawned: AtomicU64::new(0),
+            spawn_failures: AtomicU64::new(0),
+        });
+
+        let weak = Arc::downgrade(&pool);
+        thread::Builder::new()
+            .name("faber-pool-refill".to_string())
+            .spawn(move || Self::refill_loop(weak))
+            .expect("failed to spawn container pool refill thread");
+
+        pool
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let ready = self
+            .shapes
+            .lock()
+            .map(|shapes| shapes.iter().map(|shape| shape.ready.len()).sum())
+            .unwrap_or(0);
+
+        PoolStats {
+            ready,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            spawned: self.spawned.load(Ordering::Relaxed),
+            spawn_failures: self.spawn_failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Takes a live pre-warmed container matching `container` and `cgroup`,
+    /// recording a hit or a miss.
+    pub(crate) fn claim(
+        &self,
+        container: &ContainerConfig,
+        cgroup: &CgroupConfig,
+    ) -> Option<WarmContainer> {
+        let mut dead = Vec::new();
+        let claimed = self.shapes.lock().ok().and_then(|mut shapes| {
+            let shape = shapes.iter_mut().find(|shape| {
+                shape.container.same_shape(container) && shape.cgroup.same_shape(cgroup)
+            })?;
+            while let Some(warm) = shape.ready.pop_front() {
+                if warm.is_alive() {
+                    return Some(warm);
+                }
+                dead.push(warm);
+            }
+            None
+        });
+
+        for warm in dead {
+            warm.reap();
+        }
+
+        let counter = if claimed.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        claimed
+    }
+
+    fn refill_loop(pool: Weak<Self>) {
+        loop {
+            let Some(pool) = pool.upgrade() else {
+                return;
+            };
+            let pause = match pool.refill_one() {
+                Some(true) => pool.refill_interval,
+                Some(false) => pool.refill_interval.max(FAILURE_BACKOFF),
+                None => IDLE_INTERVAL,
+            };
+            drop(pool);
+            thread::sleep(pause);
+        }
+    }
+
+    /// Starts one container for the first shape below the target size.
+    /// Returns whether it started, or `None` when every shape is full.
+    fn refill_one(&self) -> Option<bool> {
+        let (index, container, cgroup) = {
+            let shapes = self.shapes.lock().ok()?;
+            let (index, shape) = shapes
+                .iter()
+                .enumerate()
+                .find(|(_, shape)| shape.ready.len() < self.size)?;
+            (index, shape.container.renewed(), shape.cgroup.clone())
+        };
+
+        if let Err(e) = Cgroup::ensure_faber_cgroup_hierarchy() {
+            eprintln!("Failed to pre-warm container: {}", e);
+            self.spawn_failures.fetch_add(1, Ordering::Relaxed);
+            return Some(false);
+        }
+
+        let scope = container.id.clone();
+        match WarmContainer::spawn(Container::new(container), Cgroup::new(cgroup).scoped(scope)) {
+            Ok(warm) => {
+                self.spawned.fetch_add(1, Ordering::Relaxed);
+                match self.shapes.lock() {
+                    Ok(mut shapes) => shapes[index].ready.push_back(warm),
+                    Err(_) => warm.discard(),
+                }
+                Some(true)
+            }
+            Err(e) => {
+                eprintln!("Failed to pre-warm container: {}", e);
+                self.spawn_failures.fetch_add(1, Ordering::Relaxed);
+                Some(false)
+            }
+        }
+    }
+}
+
+impl Drop for ContainerPool {
+    fn drop(&mut self) {
+        let shapes = match self.shapes.get_mut() {
+            Ok(shapes) => shapes,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        for warm in shapes.iter_mut().flat_map(|shape| shape.ready.drain(..)) {
+            warm.discard();
+        }
+    }
+}
+
+impl std::fmt::Debug for ContainerPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContainerPool")
+            .field("size", &self.size)
+            .field("refill_interval", &self.refill_interval)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// A forked process that has set up its container and a task cgroup and is
+/// blocked until it receives a job or is discarded.
+pub(crate) struct WarmContainer {
+    pid: Pid,
+    container: Container,
+    job: PipeWriter,
+    status: PipeReader,
+    events: PipeReader,
+}
+
+impl WarmContainer {
+    fn spawn(container: Container, cgroup: Cgroup) -> Result<Self> {
+        let (job_reader, job_writer) = mk_pipe()?;
+        let (mut status_reader, status_writer) = mk_pipe()?;
+        let (events_reader, events_writer) = mk_pipe()?;
+
+        match unsafe { fork() } {
+            Ok(ForkResult::Child) => {
+                drop(job_writer);
+                drop(status_reader);
+                drop(events_reader);
+                // This process outlives the execution that forked it, so drop
+                // pipe ends that other executions wait on for EOF.
+                close_inherited_fds(&[
+                    job_reader.as_raw_fd(),
+                    status_writer.as_raw_fd(),
+                    events_writer.as_raw_fd(),
+                ]);
+                let _ = Runtime::set_nonblocking(events_writer.as_raw_fd());
+
+                exit(Self::serve(
+                    container,
+                    cgroup,
+                    job_reader,
+                    status_writer,
+                    events_writer,
+                ));
+            }
+            Ok(ForkResult::Parent { child }) => {
+                drop(job_reader);
+                drop(status_writer);
+                drop(events_writer);
+
+                let mut ready = [0u8; 1];
+                if status_reader.read_exact(&mut ready).is_err() {
+                    let _ = waitpid(child, None);
+                    if let Err(e) = container.cleanup() {
+                        eprintln!("Failed to cleanup container: {}", e);
+                    }
+                    return Err(FaberError::WarmContainer {
+                        details: "container exited during setup".to_string(),
+                    });
+                }
+
+                Ok(Self {
+                    pid: child,
+                    container,
+                    job: job_writer,
+                    status: status_reader,
+                    events: events_reader,
+                })
+            }
+            Err(e) => Err(FaberError::Fork { e }),
+        }
+    }
+
+    /// Body of the pre-warmed process; returns its exit code.
+    fn serve(
+        container: Container,
+        cgroup: Cgroup,
+        job_reader: PipeReader,
+        mut status_writer: PipeWriter,
+        events_writer: PipeWriter,
+    ) -> i32 {
+        // Opened before setup pivots away from the host filesystem.
+        let spill = match container
+            .config()
+            .output_spill
+            .as_ref()
+            .map(SpillDir::open)
+            .transpose()
+        {
+            Ok(spill) => spill,
+            Err(e) => {
+                eprintln!("Pre-warmed container setup failed: {}", e);
+                return 1;
+            }
+        };
+        if let Err(e) = container.setup() {
+            eprintln!("Pre-warmed container setup failed: {}", e);
+            return 1;
+        }
+        let task_cgroup = match cgroup.create_task_cgroup() {
+            Ok(task_cgroup) => task_cgroup,
+            Err(e) => {
+                eprintln!("Pre-warmed task cgroup creation failed: {}", e);
+                return 1;
+            }
+        };
+        if status_writer.write_all(b"R").is_err() {
+            let _ = task_cgroup.cleanup();
+            return 1;
+        }
+
+        // Read a single value rather than up to EOF: executions forked later
+        // may hold a copy of the job pipe's write end.
+        let job = serde_json::Deserializer::from_reader(job_reader)
+            .into_iter::<Option<PoolJob>>()
+            .next()
+            .and_then(|job| job.ok())
+            .flatten();
+        let Some(job) = job else {
+            let _ = task_cgroup.cleanup();
+            return 0;
+        };
+        if let Some(cpuset) = &job.cpuset
+            && let Err(e) = task_cgroup.apply_cpuset(cpuset)
+        {
+            let _ = task_cgroup.cleanup();
+            let _ = serde_json::to_writer(
+                status_writer,
+                &RuntimeResult::ContainerSetupFailed {
+                    error: e.to_string(),
+                    info: e.info().in_stage(ErrorStage::ContainerSetup),
+                },
+            );
+            return 0;
+        }
+
+        let runtime = Runtime {
+            task_group: job.task_group,
+            container,
+            cgroup: cgroup.with_cpuset(job.cpuset),
+            timeout: job.timeout,
+            cpu_time_limit: job.cpu_time_limit,
+            output_limit: job.output_limit,
+            pool: None,
+            events: Some(events_writer),
+        };
+        runtime.emit(RuntimeEvent::ContainerReady);
+        let result = runtime.run_task_group(Some(task_cgroup), spill.as_ref());
+        let _ = serde_json::to_writer(status_writer, &result);
+        0
+    }
+
+    /// Hands `job` to the container and waits for its result. The container
+    /// is destroyed afterwards either way.
+    pub(crate) fn run(mut self, job: &PoolJob) -> Result<RuntimeResult> {
+        // Nobody listens to events on the blocking path.
+        drop(self.events);
+
+        let sent = serde_json::to_writer(&mut self.job, &Some(job));
+        drop(self.job);
+
+        // Read while the child serializes, as in Runtime::execute.
+        let result = sent.and_then(|()| serde_json::from_reader(self.status));
+        waitpid(self.pid, None).map_err(|e| FaberError::WaitPid { e })?;
+
+        let cleanup_start = Instant::now();
+        if let Err(e) = self.container.cleanup() {
+            eprintln!("Failed to cleanup container: {}", e);
+        }
+        let cleanup_usec = elapsed_usec(cleanup_start);
+
+        let mut result: RuntimeResult = result.map_err(|e| FaberError::ParseResult {
+            e,
+            details: "Failed to exchange a job with a pre-warmed container".to_string(),
+        })?;
+        if let Some(timings) = result.timings_mut() {
+            timings.cleanup_usec = cleanup_usec;
+        }
+        Ok(result)
+    }
+
+    /// Hands the container over to an async supervisor, which sends `job`.
+    pub(crate) fn into_process(self, job: PoolJob) -> ExecutionProcess {
+        ExecutionProcess {
+            pid: self.pid,
+            scope: self.container.config().id.clone(),
+            container: self.container,
+            job: Some((self.job, job)),
+            result: self.status,
+            events: self.events,
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        matches!(
+            waitpid(self.pid, Some(WaitPidFlag::WNOHANG)),
+            Ok(WaitStatus::StillAlive)
+        )
+    }
+
+    /// Tells the container to tear itself down and removes its root.
+    fn discard(mut self) {
+        let _ = serde_json::to_writer(&mut self.job, &None::<PoolJob>);
+        self.reap();
+    }
+
+    fn reap(self) {
+        let _ = waitpid(self.pid, None);
+        if let Err(e) = self.container.cleanup() {
+            eprintln!("Failed to cleanup container: {}", e);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    os::fd::{AsFd, OwnedFd},
+    time::Duration,
+};
+
+use nix::{
+    errno::Errno,
+    sys::{
+        epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags, EpollTimeout},
+        time::TimeSpec,
+        timerfd::{ClockId, Expiration, TimerFd, TimerFlags, TimerSetTimeFlags},
+    },
+    unistd::Pid,
+};
+
+use crate::{prelude::*, utils::pidfd_open};
+
+/// What became ready while supervising a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SupervisorEvent {
+    Stdout,
+    Stderr,
+    Stdin,
+    Exited,
+    Deadline,
+    Sample,
+}
+
+impl SupervisorEvent {
+    const ALL: [Self; 6] = [
+        Self::Stdout,
+        Self::Stderr,
+        Self::Stdin,
+        Self::Exited,
+        Self::Deadline,
+        Self::Sample,
+    ];
+}
+
+/// Blocks until a task's pipes, its exit, or its deadline need attention.
+///
+/// The task is watched through a pidfd and the wall-clock limit through a
+/// timerfd, so an idle task costs no wakeups at all unless periodic sampling
+/// is requested.
+pub(crate) struct Supervisor {
+    epoll: Epoll,
+    pidfd: Option<OwnedFd>,
+    timer: Option<TimerFd>,
+    sampler: Option<TimerFd>,
+}
+
+impl Supervisor {
+    pub(crate) fn new(child: Pid, timeout: Duration) -> Result<Self> {
+        let error = |e: Errno, what: &str| FaberError::Generic {
+            message: format!("Failed to {what} for task supervision: {e}"),
+        };
+
+        let epoll =
+            Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC).map_err(|e| error(e, "create epoll"))?;
+
+        let pidfd = pidfd_open(child).map_err(|e| error(e, "open pidfd"))?;
+
+        let timer = TimerFd::new(ClockId::CLOCK_MONOTONIC, TimerFlags::TFD_CLOEXEC)
+            .map_err(|e| error(e, "create timerfd"))?;
+        // A zero expiration would disarm the timer instead of firing it.
+        let deadline = TimeSpec::from_duration(timeout.max(Duration::from_nanos(1)));
+        timer
+            .set(Expiration::OneShot(deadline), TimerSetTimeFlags::empty())
+            .map_err(|e| error(e, "arm timerfd"))?;
+
+        Self::add(&epoll, &pidfd, SupervisorEvent::Exited)?;
+        Self::add(&epoll, &timer, SupervisorEvent::Deadline)?;
+
+        Ok(Self {
+            epoll,
+            pidfd: Some(pidfd),
+            timer: Some(timer),
+            sampler: None,
+        })
+    }
+
+    /// Reports [`SupervisorEvent::Sample`] every `interval` until
+    /// [`Supervisor::stop_sampling`] is called.
+    pub(crate) fn sample_every(&mut self, interval: Duration) -> Result<()> {
+        let error = |e: Errno, what: &str| FaberError::Generic {
+            message: format!("Failed to {what} for task sampling: {e}"),
+        };
+
+        let sampler = TimerFd::new(ClockId::CLOCK_MONOTONIC, TimerFlags::TFD_CLOEXEC)
+            .map_err(|e| error(e, "create timerfd"))?;
+        let interval = TimeSpec::from_duration(interval.max(Duration::from_nanos(1)));
+        sampler
+            .set(Expiration::Interval(interval), TimerSetTimeFlags::empty())
+            .map_err(|e| error(e, "arm timerfd"))?;
+        Self::add(&self.epoll, &sampler, SupervisorEvent::Sample)?;
+        self.sampler = Some(sampler);
+        Ok(())
+    }
+
+    /// Consumes a sampling tick so the timer is not reported again until the
+    /// next interval elapses.
+    pub(crate) fn acknowledge_sample(&self) {
+        if let Some(sampler) = &self.sampler {
+            // The timer has expired, so this read returns without blocking.
+            let _ = sampler.wait();
+        }
+    }
+
+    pub(crate) fn stop_sampling(&mut self) {
+        if let Some(sampler) = self.sampler.take() {
+            self.unwatch(&sampler);
+        }
+    }
+
+    pub(crate) fn watch(&self, fd: impl AsFd, event: SupervisorEvent) -> Result<()> {
+        Self::add(&self.epoll, fd, event)
+    }
+
+    fn add(epoll: &Epoll, fd: impl AsFd, event: SupervisorEvent) -> Result<()> {
+        let flags = match event {
+            SupervisorEvent::Stdin => EpollFlags::EPOLLOUT,
+            _ => EpollFlags::EPOLLIN,
+        };
+        epoll
+            .add(fd, EpollEvent::new(flags, event as u64))
+            .map_err(|e| FaberError::Generic {
+                message: format!("Failed to watch task {event:?}: {e}"),
+            })
+    }
+
+    pub(crate) fn unwatch(&self, fd: impl AsFd) {
+        let _ = self.epoll.delete(fd);
+    }
+
+    /// Stops watching `fd`, if still open, and closes it.
+    pub(crate) fn release<T: AsFd>(&self, fd: &mut Option<T>) {
+        if let Some(fd) = fd.take() {
+            self.unwatch(fd);
+        }
+    }
+
+    /// Stops watching the task's exit once it has been reaped.
+    pub(crate) fn unwatch_exit(&mut self) {
+        if let Some(pidfd) = self.pidfd.take() {
+            self.unwatch(&pidfd);
+        }
+    }
+
+    /// Moves the deadline to `after` from now, e.g. to bound a grace period,
+    /// watching it again if it had already been handled.
+    pub(crate) fn rearm_deadline(&mut self, after: Duration) -> Result<()> {
+        let error = |e: Errno, what: &str| FaberError::Generic {
+            message: format!("Failed to {what} for task supervision: {e}"),
+        };
+
+        let timer = match self.timer.take() {
+            Some(timer) => timer,
+            None => {
+                let timer = TimerFd::new(ClockId::CLOCK_MONOTONIC, TimerFlags::TFD_CLOEXEC)
+                    .map_err(|e| error(e, "create timerfd"))?;
+                Self::add(&self.epoll, &timer, SupervisorEvent::Deadline)?;
+                timer
+            }
+        };
+        let deadline = TimeSpec::from_duration(after.max(Duration::from_nanos(1)));
+        timer
+            .set(Expiration::OneShot(deadline), TimerSetTimeFlags::empty())
+            .map_err(|e| error(e, "arm timerfd"))?;
+        self.timer = Some(timer);
+        Ok(())
+    }
+
+    /// Stops watching the deadline once it has been handled.
+    pub(crate) fn unwatch_deadline(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            self.unwatch(&timer);
+        }
+    }
+
+    /// Waits for at least one event and appends the ready ones to `ready`.
+    pub(crate) fn wait(&self, ready: &mut Vec<SupervisorEvent>) -> Result<()> {
+        let mut events = [EpollEvent::empty(); SupervisorEvent::ALL.len()];
+        let count = loop {
+            match self.epoll.wait(&mut events, EpollTimeout::NONE) {
+                Ok(count) => break count,
+                Err(Errno::EINTR) => continue,
+                Err(e) => {
+                    return Err(FaberError::Generic {
+                        message: format!("Failed to wait for task events: {e}"),
+                    });
+                }
+            }
+        };
+
+        ready.clear();
+        ready.extend(events[..count].iter().filter_map(|event| {
+            SupervisorEvent::ALL
+                .into_iter()
+                .find(|candidate| *candidate as u64 == event.data())
+        }));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, process::Command, time::Instant};
+
+    use super::*;
+
+    fn spawn_sleep(seconds: &str) -> (std::process::Child, Pid) {
+        let child = Command::new("sleep").arg(seconds).spawn().unwrap();
+        let pid = Pid::from_raw(child.id() as i32);
+        (child, pid)
+    }
+
+    #[test]
+    fn reports_exit_without_waiting_for_the_deadline() {
+        let (mut child, pid) = spawn_sleep("0.05");
+        let supervisor = Supervisor::new(pid, Duration::from_secs(10)).unwrap();
+        let started = Instant::now();
+
+        let mut ready = Vec::new();
+        supervisor.wait(&mut ready).unwrap();
+
+        assert_eq!(ready, [SupervisorEvent::Exited]);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn reports_the_deadline_for_a_running_task() {
+        let (mut child, pid) = spawn_sleep("10");
+        let supervisor = Supervisor::new(pid, Duration::from_millis(50)).unwrap();
+
+        let mut ready = Vec::new();
+        supervisor.wait(&mut ready).unwrap();
+
+        assert_eq!(ready, [SupervisorEvent::Deadline]);
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn reports_a_rearmed_deadline_again() {
+        let (mut child, pid) = spawn_sleep("10");
+        let mut supervisor = Supervisor::new(pid, Duration::from_millis(10)).unwrap();
+        let mut ready = Vec::new();
+        supervisor.wait(&mut ready).unwrap();
+        supervisor.unwatch_deadline();
+
+        let started = Instant::now();
+        supervisor
+            .rearm_deadline(Duration::from_millis(50))
+            .unwrap();
+        supervisor.wait(&mut ready).unwrap();
+
+        assert_eq!(ready, [SupervisorEvent::Deadline]);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn reports_readable_pipes() {
+        let (mut child, pid) = spawn_sleep("10");
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        let supervisor = Supervisor::new(pid, Duration::from_secs(10)).unwrap();
+        supervisor.watch(&reader, SupervisorEvent::Stdout).unwrap();
+
+        writer.write_all(b"output").unwrap();
+        let mut ready = Vec::new();
+        supervisor.wait(&mut ready).unwrap();
+
+        assert_eq!(ready, [SupervisorEvent::Stdout]);
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn reports_samples_until_stopped() {
+        let (mut child, pid) = spawn_sleep("10");
+        let mut supervisor = Supervisor::new(pid, Duration::from_secs(10)).unwrap();
+        supervisor.sample_every(Duration::from_millis(10)).unwrap();
+
+        let mut ready = Vec::new();
+        for _ in 0..3 {
+            supervisor.wait(&mut ready).unwrap();
+            assert_eq!(ready, [SupervisorEvent::Sample]);
+            supervisor.acknowledge_sample();
+        }
+
+        supervisor.stop_sampling();
+        child.kill().unwrap();
+        supervisor.wait(&mut ready).unwrap();
+        assert_eq!(ready, [SupervisorEvent::Exited]);
+        child.wait().unwrap();
+    }
+}
@@ -1,8 +1,14 @@
+use nix::sys::signal::Signal;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
+
+use crate::error::FaberError;
 
 pub type TaskGroup = Vec<ExecutionStep>;
 
+// Like `ExecutionStepResult`, boxing the single task would only make callers'
+// patterns awkward for a value built once per step.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum ExecutionStep {
     Single(Task),
@@ -60,10 +66,134 @@ pub struct Task {
     pub args: Option<Vec<String>>,
     pub env: Option<HashMap<String, String>>,
     pub stdin: Option<String>,
+    /// A file registered with the container, read by the task as stdin
+    /// instead of `stdin`.
+    #[serde(default)]
+    pub stdin_file_id: Option<String>,
     pub files: Option<HashMap<String, String>>,
     pub working_dir: Option<String>,
     #[serde(default)]
     pub sandbox_profile: Option<SandboxProfile>,
+    /// How the task is stopped once it exceeds a limit; killed at once if
+    /// unset.
+    #[serde(default)]
+    pub termination: Option<TerminationPolicy>,
+    /// How stdout and stderr are kept once they exceed the output limit;
+    /// the head is kept and the task stopped if unset.
+    #[serde(default)]
+    pub capture: Option<OutputCapture>,
+}
+
+impl Task {
+    /// The file the task reads as stdin, if it names one instead of inline
+    /// `stdin`.
+    pub(crate) fn stdin_file_id(&self) -> crate::prelude::Result<Option<&str>> {
+        match (&self.stdin, &self.stdin_file_id) {
+            (Some(_), Some(_)) => Err(FaberError::InvalidTask {
+                details: "stdin and stdin_file_id cannot both be set".to_string(),
+            }),
+            (_, file_id) => Ok(file_id.as_deref()),
+        }
+    }
+}
+
+/// How a task's output is captured. Each stream keeps at most the runtime's
+/// output limit, or `limit_bytes` if set; `retain` picks which part of a
+/// longer stream survives and `keep_running` lets the task finish instead of
+/// stopping it at the limit. [`OutputRetention::HeadAndTail`] always lets
+/// the task finish, since its tail is only the end of the stream once the
+/// task has exited. With `merged`, stdout and stderr are also returned as one
+/// timestamped stream in the order they were read, bounded by the runtime's
+/// output limit.
+///
+/// `limit_bytes` is taken as given; callers that accept tasks from users
+/// must bound it, as the API does.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutputCapture {
+    #[serde(default)]
+    pub retain: OutputRetention,
+    #[serde(default)]
+    pub keep_running: bool,
+    #[serde(default)]
+    pub merged: bool,
+    #[serde(default)]
+    pub limit_bytes: Option<usize>,
+}
+
+impl OutputCapture {
+    /// The limit of each stream, given the runtime's `output_limit`.
+    pub(crate) fn stream_limit(&self, output_limit: usize) -> usize {
+        self.limit_bytes.unwrap_or(output_limit)
+    }
+
+    /// Whether the task is stopped once its output exceeds the limit.
+    pub(crate) fn stops_at_limit(&self) -> bool {
+        !self.keep_running && self.retain == OutputRetention::Head
+    }
+}
+
+/// Which part of a stream longer than the output limit is kept.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputRetention {
+    /// The first bytes, up to the limit.
+    #[default]
+    Head,
+    /// The first and last half of the limit, joined by a marker naming how
+    /// many bytes were elided in between.
+    HeadAndTail,
+}
+
+/// How a task that exceeded its timeout, CPU time budget or output limit is
+/// stopped: `signal` is sent to all of its processes, which then have
+/// `grace_period_ms` to exit before the whole cgroup is killed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TerminationPolicy {
+    #[serde(default = "TerminationPolicy::default_signal")]
+    pub signal: i32,
+    #[serde(default)]
+    pub grace_period_ms: u64,
+}
+
+impl Default for TerminationPolicy {
+    fn default() -> Self {
+        Self {
+            signal: Self::default_signal(),
+            grace_period_ms: 0,
+        }
+    }
+}
+
+impl TerminationPolicy {
+    fn default_signal() -> i32 {
+        nix::libc::SIGTERM
+    }
+
+    pub(crate) fn grace_period(&self) -> Duration {
+        Duration::from_millis(self.grace_period_ms)
+    }
+
+    /// The signal to stop the task with. Signals whose default action stops,
+    /// continues or ignores the process are rejected, as they would leave
+    /// the task running until the grace period ends.
+    pub fn signal(&self) -> crate::prelude::Result<Signal> {
+        let signal = Signal::try_from(self.signal).map_err(|_| FaberError::InvalidTask {
+            details: format!("unknown termination signal {}", self.signal),
+        })?;
+        match signal {
+            Signal::SIGSTOP
+            | Signal::SIGTSTP
+            | Signal::SIGTTIN
+            | Signal::SIGTTOU
+            | Signal::SIGCONT
+            | Signal::SIGCHLD
+            | Signal::SIGURG
+            | Signal::SIGWINCH => Err(FaberError::InvalidTask {
+                details: format!("termination signal {signal} does not end a process"),
+            }),
+            signal => Ok(signal),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -72,11 +202,20 @@ pub struct TaskStats {
     pub cpu_nr_throttled: u64,
     pub cpu_throttled_usec: u64,
     pub memory_peak_bytes: u64,
+    pub memory_anon_bytes: u64,
+    pub memory_file_bytes: u64,
     pub pids_max: u64,
+    pub io_read_bytes: u64,
+    pub io_write_bytes: u64,
+    pub io_read_ops: u64,
+    pub io_write_ops: u64,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct TaskCgroupEvents {
     pub oom_kill_count: u64,
+    pub oom_count: u64,
+    pub memory_high_count: u64,
+    pub memory_max_count: u64,
     pub pids_limit_hit_count: u64,
 }
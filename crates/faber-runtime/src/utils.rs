@@ -1,9 +1,15 @@
 use std::{
     io::{PipeReader, PipeWriter, pipe},
-    os::fd::RawFd,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    time::Instant,
 };
 
-use nix::unistd::close;
+use nix::{
+    errno::Errno,
+    libc,
+    sys::signal::Signal,
+    unistd::{Pid, close},
+};
 
 use crate::prelude::*;
 
@@ -19,6 +25,11 @@ pub fn generate_random_string(size: u8) -> String {
         .collect()
 }
 
+/// Microseconds since `start`, for phase timings.
+pub fn elapsed_usec(start: Instant) -> u64 {
+    start.elapsed().as_micros() as u64
+}
+
 pub fn mk_pipe() -> Result<(PipeReader, PipeWriter)> {
     pipe().map_err(|e| FaberError::MkPipe {
         e,
@@ -31,3 +42,44 @@ pub fn close_fd(fd: RawFd) -> Result<()> {
 
     Ok(())
 }
+
+/// Closes every descriptor above stderr except `keep`. Long-lived forked
+/// children use this so they do not hold pipe ends belonging to other
+/// executions open.
+pub fn close_inherited_fds(keep: &[RawFd]) {
+    let Ok(entries) = std::fs::read_dir("/proc/self/fd") else {
+        return;
+    };
+    let fds: Vec<RawFd> = entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+
+    for fd in fds {
+        if fd > 2 && !keep.contains(&fd) {
+            let _ = close(fd);
+        }
+    }
+}
+
+/// Opens a pidfd that becomes readable once `pid` exits.
+pub fn pidfd_open(pid: Pid) -> nix::Result<OwnedFd> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid.as_raw(), 0) };
+    let fd = Errno::result(fd)?;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
+
+/// Sends `signal` to the process behind `pidfd`. Unlike a PID, the pidfd
+/// cannot come to refer to another process once this one has been reaped;
+/// the call then fails with `ESRCH`.
+pub fn pidfd_send_signal(pidfd: &OwnedFd, signal: Signal) -> nix::Result<()> {
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_pidfd_send_signal,
+            pidfd.as_raw_fd(),
+            signal as libc::c_int,
+            std::ptr::null::<libc::siginfo_t>(),
+            0,
+        )
+    };
+    Errno::result(result).map(drop)
+}
@@ -0,0 +1,102 @@
+use faber_runtime::{CpuAllocatorBuilder, CpusetPartition, FaberError};
+use std::time::Duration;
+
+#[tokio::test]
+async fn leases_hold_exclusive_cores_until_dropped() {
+    let cpus = CpuAllocatorBuilder::default()
+        .with_cpu_list("2-5")
+        .unwrap()
+        .with_cores_per_execution(2)
+        .build()
+        .unwrap();
+
+    let first = cpus.acquire().await;
+    let second = cpus.acquire().await;
+    assert_eq!(first.cpus(), [2, 3]);
+    assert_eq!(second.cpus(), [4, 5]);
+    assert_eq!(cpus.stats().free, 0);
+
+    drop(first);
+    let third = cpus.acquire().await;
+    assert_eq!(third.cpus(), [2, 3]);
+}
+
+#[tokio::test]
+async fn executions_wait_for_a_free_slot_instead_of_sharing() {
+    let cpus = CpuAllocatorBuilder::default()
+        .with_cpus(vec![7])
+        .build()
+        .unwrap();
+    let held = cpus.acquire().await;
+
+    let waiter = tokio::spawn({
+        let cpus = cpus.clone();
+        async move { cpus.acquire().await.cpus().to_vec() }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiter.is_finished());
+    let stats = cpus.stats();
+    assert_eq!((stats.slots, stats.free, stats.waiting), (1, 0, 1));
+
+    drop(held);
+    assert_eq!(waiter.await.unwrap(), [7]);
+    assert_eq!(cpus.stats().waiting, 0);
+    assert_eq!(cpus.stats().free, 1);
+}
+
+#[tokio::test]
+async fn cancelled_waiters_are_no_longer_counted() {
+    let cpus = CpuAllocatorBuilder::default()
+        .with_cpus(vec![7])
+        .build()
+        .unwrap();
+    let _held = cpus.acquire().await;
+
+    let waited = tokio::time::timeout(Duration::from_millis(50), cpus.acquire()).await;
+    assert!(waited.is_err());
+    assert_eq!(cpus.stats().waiting, 0);
+}
+
+#[test]
+fn leftover_cores_do_not_form_a_slot() {
+    let cpus = CpuAllocatorBuilder::default()
+        .with_cpu_list("0-4")
+        .unwrap()
+        .with_cores_per_execution(2)
+        .build()
+        .unwrap();
+
+    assert_eq!(cpus.stats().slots, 2);
+}
+
+#[test]
+fn invalid_cpu_pools_are_rejected() {
+    for list in ["a", "3-1", "1-"] {
+        assert!(
+            matches!(
+                CpuAllocatorBuilder::default().with_cpu_list(list),
+                Err(FaberError::InvalidCgroupConfig { .. })
+            ),
+            "{list}"
+        );
+    }
+    assert!(
+        CpuAllocatorBuilder::default()
+            .with_cpu_list("0,1")
+            .unwrap()
+            .with_cores_per_execution(3)
+            .build()
+            .is_err()
+    );
+    assert!(
+        CpuAllocatorBuilder::default()
+            .with_cores_per_execution(0)
+            .build()
+            .is_err()
+    );
+    assert!("exclusive".parse::<CpusetPartition>().is_err());
+    assert_eq!(
+        "isolated".parse::<CpusetPartition>().unwrap(),
+        CpusetPartition::Isolated
+    );
+}
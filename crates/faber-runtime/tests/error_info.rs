@@ -0,0 +1,81 @@
+use faber_runtime::{ErrorCode, ErrorInfo, ErrorStage, FaberError, TaskResult, TaskResultStats};
+use nix::errno::Errno;
+
+#[test]
+fn transient_os_errors_are_retryable() {
+    let info = FaberError::Fork { e: Errno::EAGAIN }.info();
+
+    assert_eq!(
+        info,
+        ErrorInfo {
+            code: ErrorCode::Process,
+            stage: ErrorStage::TaskSetup,
+            errno: Some(Errno::EAGAIN as i32),
+            retryable: true,
+        }
+    );
+    assert!(
+        !FaberError::Mount {
+            e: Errno::EPERM,
+            details: String::new(),
+        }
+        .is_retryable()
+    );
+}
+
+#[test]
+fn submission_errors_stay_in_validation() {
+    let info = FaberError::InvalidTask {
+        details: "bad".to_string(),
+    }
+    .info()
+    .in_stage(ErrorStage::ContainerSetup);
+
+    assert_eq!(info.code, ErrorCode::InvalidTask);
+    assert_eq!(info.stage, ErrorStage::Validation);
+    assert!(info.code.is_client_error());
+    assert!(!info.retryable);
+}
+
+#[test]
+fn operator_configuration_errors_are_not_client_errors() {
+    let info = FaberError::InvalidMount {
+        details: "target passes through a symlink".to_string(),
+    }
+    .info();
+
+    assert_eq!(info.code, ErrorCode::Configuration);
+    assert_eq!(info.stage, ErrorStage::ContainerSetup);
+    assert!(!info.code.is_client_error());
+}
+
+#[test]
+fn failed_results_serialize_their_error_info() {
+    let result = TaskResult::Failed {
+        error: "Failed to fork".to_string(),
+        info: FaberError::Fork { e: Errno::ENOMEM }
+            .info()
+            .in_stage(ErrorStage::ContainerSetup),
+        stats: TaskResultStats::default(),
+    };
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(
+        json["error_info"],
+        serde_json::json!({
+            "code": "process",
+            "stage": "container_setup",
+            "errno": Errno::ENOMEM as i32,
+            "retryable": true,
+        })
+    );
+
+    // Results recorded before error codes existed still parse.
+    let legacy: TaskResult =
+        serde_json::from_value(serde_json::json!({"error": "boom", "stats": json["stats"]}))
+            .unwrap();
+    let TaskResult::Failed { info, .. } = legacy else {
+        panic!("Expected a failed result, got {legacy:?}");
+    };
+    assert_eq!(info, ErrorInfo::default());
+}
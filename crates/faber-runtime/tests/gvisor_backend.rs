@@ -0,0 +1,134 @@
+use faber_runtime::{
+    ContainerConfigBuilder, ExecutionBackend, ExecutionStep, ExecutionStepResult, GvisorBackend,
+    RuntimeResult, Task, TaskOutcome, TaskResult, WorkspaceTemplate,
+};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("faber-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("failed to create temporary directory");
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn shell_task(script: &str) -> Task {
+    Task {
+        cmd: "/bin/sh".to_string(),
+        args: Some(vec!["-c".to_string(), script.to_string()]),
+        env: None,
+        stdin: None,
+        files: None,
+        working_dir: None,
+        sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
+    }
+}
+
+fn single(result: &RuntimeResult) -> &TaskResult {
+    match result {
+        RuntimeResult::Success(steps, _) => match &steps[0] {
+            ExecutionStepResult::Single(task_result) => task_result,
+            other => panic!("Expected a single task result, got {:?}", other),
+        },
+        other => panic!("Expected success result, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn workspace_templates_are_rejected_before_a_sandbox_starts() {
+    let layer = TempDir::new("gvisor-layer");
+    let bundles = TempDir::new("gvisor-rejected");
+    let template = WorkspaceTemplate::from_layers(vec![layer.0.clone()]).unwrap();
+
+    let result = GvisorBackend::new("/nonexistent/runsc")
+        .with_bundle_dir(&bundles.0)
+        .execute(
+            vec![ExecutionStep::Single(shell_task("true"))],
+            ContainerConfigBuilder::new()
+                .with_workspace_template(&template)
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(result, RuntimeResult::ContainerSetupFailed { .. }));
+}
+
+#[tokio::test]
+async fn a_missing_runsc_fails_the_task_and_removes_its_bundle() {
+    let bundles = TempDir::new("gvisor-missing");
+
+    let result = GvisorBackend::new(bundles.0.join("runsc"))
+        .with_bundle_dir(&bundles.0)
+        .execute(
+            vec![ExecutionStep::Single(shell_task("true"))],
+            ContainerConfigBuilder::new().build(),
+        )
+        .await
+        .unwrap();
+
+    match single(&result) {
+        TaskResult::Failed { error, .. } => assert!(error.contains("runsc"), "{error}"),
+        other => panic!("Expected a failed task, got {:?}", other),
+    }
+    assert_eq!(std::fs::read_dir(&bundles.0).unwrap().count(), 0);
+}
+
+/// Needs root and `runsc`; set `FABER_TEST_RUNSC` to its path to run.
+#[tokio::test]
+async fn runsc_tasks_share_a_workspace_without_network() {
+    let Ok(runsc) = std::env::var("FABER_TEST_RUNSC") else {
+        eprintln!("FABER_TEST_RUNSC is not set; skipping");
+        return;
+    };
+    let backend = GvisorBackend::new(runsc).with_timeout(Duration::from_secs(10));
+    let mut write = shell_task("cat input.txt > copy.txt; cat");
+    write.stdin = Some("from stdin".to_string());
+    write.files = Some(HashMap::from([(
+        "input.txt".to_string(),
+        "from a file\n".to_string(),
+    )]));
+
+    let result = backend
+        .execute(
+            vec![
+                ExecutionStep::Single(write),
+                ExecutionStep::Single(shell_task(
+                    "cat copy.txt; ls /sys/class/net; id -u; exec sleep 30",
+                )),
+            ],
+            ContainerConfigBuilder::new().build(),
+        )
+        .await
+        .unwrap();
+
+    let RuntimeResult::Success(steps, _) = result else {
+        panic!("Expected success result, got {:?}", result);
+    };
+    let outputs: Vec<_> = steps
+        .iter()
+        .map(|step| match step {
+            ExecutionStepResult::Single(TaskResult::Completed { stdout, stats, .. }) => {
+                (stdout.as_str(), &stats.outcome)
+            }
+            other => panic!("Expected a completed task, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(outputs[0], ("from stdin", &TaskOutcome::Exited));
+    assert_eq!(
+        outputs[1],
+        ("from a file\nlo\n65534\n", &TaskOutcome::TimedOut)
+    );
+}
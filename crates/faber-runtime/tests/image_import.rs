@@ -0,0 +1,240 @@
+use faber_runtime::ImageStore;
+use sha2::{Digest, Sha256};
+use std::{
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+};
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("faber-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("failed to create temporary directory");
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, content) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_mtime(0);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, *content)
+            .expect("failed to append archive entry");
+    }
+    builder.into_inner().expect("failed to finish archive")
+}
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(bytes).expect("failed to compress layer");
+    encoder.finish().expect("failed to finish compression")
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn write_blob(layout: &Path, bytes: &[u8]) -> String {
+    let digest = sha256(bytes);
+    let blobs = layout.join("blobs/sha256");
+    std::fs::create_dir_all(&blobs).expect("failed to create blob directory");
+    std::fs::write(blobs.join(&digest), bytes).expect("failed to write blob");
+    format!("sha256:{digest}")
+}
+
+fn base_and_upper_layers() -> (Vec<u8>, Vec<u8>) {
+    let base = archive(&[
+        ("etc/os-release", b"ID=faber\n"),
+        ("etc/motd", b"hello\n"),
+        ("opt/cache/stale", b"stale\n"),
+    ]);
+    let upper = archive(&[
+        ("etc/issue", b"replaced\n"),
+        ("etc/.wh.issue", b""),
+        ("var/log/old", b"old\n"),
+        ("var/.wh.log", b""),
+        ("etc/.wh.motd", b""),
+        ("opt/cache/.wh..wh..opq", b""),
+        ("opt/cache/fresh", b"fresh\n"),
+    ]);
+    (base, upper)
+}
+
+fn write_oci_layout(layout: &Path) -> String {
+    let (base, upper) = base_and_upper_layers();
+    let upper = gzip(&upper);
+    let base_digest = write_blob(layout, &base);
+    let upper_digest = write_blob(layout, &upper);
+    let config_digest = write_blob(layout, b"{}");
+    let manifest = serde_json::json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.manifest.v1+json",
+        "config": {
+            "mediaType": "application/vnd.oci.image.config.v1+json",
+            "digest": config_digest,
+            "size": 2
+        },
+        "layers": [
+            {
+                "mediaType": "application/vnd.oci.image.layer.v1.tar",
+                "digest": base_digest,
+                "size": base.len()
+            },
+            {
+                "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip",
+                "digest": upper_digest,
+                "size": upper.len()
+            }
+        ]
+    })
+    .to_string();
+    let manifest_digest = write_blob(layout, manifest.as_bytes());
+    let index = serde_json::json!({
+        "schemaVersion": 2,
+        "manifests": [{
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "digest": manifest_digest,
+            "size": manifest.len()
+        }]
+    });
+    std::fs::write(layout.join("index.json"), index.to_string()).unwrap();
+    std::fs::write(
+        layout.join("oci-layout"),
+        r#"{"imageLayoutVersion":"1.0.0"}"#,
+    )
+    .unwrap();
+    manifest_digest
+}
+
+fn opaque(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+    let mut value = [0u8; 1];
+    let size = unsafe {
+        nix::libc::lgetxattr(
+            path.as_ptr(),
+            c"trusted.overlay.opaque".as_ptr(),
+            value.as_mut_ptr().cast(),
+            value.len(),
+        )
+    };
+    size == 1 && value == *b"y"
+}
+
+#[test]
+fn oci_layouts_unpack_into_content_addressed_layers_with_overlay_whiteouts() {
+    let layout = TempDir::new("oci-layout");
+    let store = TempDir::new("oci-store");
+    let manifest_digest = write_oci_layout(&layout.0);
+
+    let image = ImageStore::new(&store.0)
+        .import(&layout.0)
+        .expect("failed to import OCI layout");
+
+    assert_eq!(image.digest(), manifest_digest);
+    assert_eq!(image.layers().len(), 2);
+    for layer in image.layers() {
+        assert!(layer.starts_with(store.0.join("layers/sha256")));
+    }
+
+    let (base, upper) = (&image.layers()[0], &image.layers()[1]);
+    assert_eq!(
+        std::fs::read_to_string(base.join("etc/os-release")).unwrap(),
+        "ID=faber\n"
+    );
+
+    let whiteout = std::fs::symlink_metadata(upper.join("etc/motd")).unwrap();
+    assert!(whiteout.file_type().is_char_device());
+    assert!(!upper.join("etc/.wh.motd").exists());
+    for hidden in ["etc/issue", "var/log"] {
+        let whiteout = std::fs::symlink_metadata(upper.join(hidden)).unwrap();
+        assert!(whiteout.file_type().is_char_device(), "{hidden}");
+    }
+    assert!(opaque(&upper.join("opt/cache")));
+    assert!(upper.join("opt/cache/fresh").is_file());
+
+    let reimported = ImageStore::new(&store.0)
+        .import(&layout.0)
+        .expect("failed to re-import OCI layout");
+    assert_eq!(reimported.layers(), image.layers());
+}
+
+#[test]
+fn docker_save_tarballs_use_the_config_digest_as_image_id() {
+    let archive_dir = TempDir::new("docker-save");
+    let store = TempDir::new("docker-store");
+    let (base, upper) = base_and_upper_layers();
+    let config = br#"{"architecture":"amd64"}"#;
+    let manifest = serde_json::json!([{
+        "Config": "config.json",
+        "RepoTags": ["faber/gcc:13"],
+        "Layers": ["base/layer.tar", "upper/layer.tar"]
+    }])
+    .to_string();
+
+    let tarball = archive_dir.0.join("image.tar");
+    std::fs::write(
+        &tarball,
+        archive(&[
+            ("manifest.json", manifest.as_bytes()),
+            ("config.json", config.as_slice()),
+            ("base/layer.tar", base.as_slice()),
+            ("upper/layer.tar", upper.as_slice()),
+        ]),
+    )
+    .unwrap();
+
+    let image = ImageStore::new(&store.0)
+        .import(&tarball)
+        .expect("failed to import docker save archive");
+
+    assert_eq!(image.digest(), format!("sha256:{}", sha256(config)));
+    assert_eq!(
+        image.layers()[1],
+        store.0.join("layers/sha256").join(sha256(&upper))
+    );
+    assert!(
+        std::fs::read_dir(&store.0)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .all(|entry| !entry.file_name().to_string_lossy().starts_with("staging-")),
+        "staging directory leaked"
+    );
+}
+
+#[test]
+fn layers_with_mismatched_digests_are_rejected() {
+    let layout = TempDir::new("oci-corrupt");
+    let store = TempDir::new("oci-corrupt-store");
+    write_oci_layout(&layout.0);
+
+    let blobs = layout.0.join("blobs/sha256");
+    let (base, _) = base_and_upper_layers();
+    std::fs::write(blobs.join(sha256(&base)), b"tampered").unwrap();
+
+    assert!(ImageStore::new(&store.0).import(&layout.0).is_err());
+    assert!(
+        std::fs::read_dir(store.0.join("layers/sha256"))
+            .map(|entries| entries.count() == 0)
+            .unwrap_or(true),
+        "a corrupt layer or its staging directory was published"
+    );
+}
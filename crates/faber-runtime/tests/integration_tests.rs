@@ -1,4 +1,8 @@
-use faber_runtime::{RuntimeBuilder, Task, TaskGroup};
+use faber_runtime::{
+    CgroupConfigBuilder, ContainerConfigBuilder, ContainerPoolBuilder, CpuAllocatorBuilder,
+    Environment, ExecutionBackend, FaberError, IoMax, MountSpec, NamespaceBackend, RuntimeBuilder,
+    RuntimeEvent, Task, TaskGroup, TerminationPhase, TerminationPolicy, WorkspaceTemplate,
+};
 use std::collections::HashMap;
 
 fn create_test_task(cmd: &str, args: Vec<&str>) -> Task {
@@ -10,6 +14,9 @@ fn create_test_task(cmd: &str, args: Vec<&str>) -> Task {
         files: None,
         working_dir: None,
         sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
     }
 }
 
@@ -28,7 +35,7 @@ fn test_basic_command_execution() {
     assert!(result.is_ok(), "Runtime execution failed: {:?}", result);
 
     match result.unwrap() {
-        faber_runtime::RuntimeResult::Success(results) => {
+        faber_runtime::RuntimeResult::Success(results, _) => {
             assert_eq!(results.len(), 1);
             match &results[0] {
                 faber_runtime::ExecutionStepResult::Single(task_result) => match task_result {
@@ -66,6 +73,9 @@ fn test_pid_namespace_isolation() {
         files: None,
         working_dir: None,
         sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
     };
 
     let task_group: TaskGroup = vec![faber_runtime::ExecutionStep::Single(task)];
@@ -78,7 +88,7 @@ fn test_pid_namespace_isolation() {
     assert!(result.is_ok(), "Runtime execution failed: {:?}", result);
 
     match result.unwrap() {
-        faber_runtime::RuntimeResult::Success(results) => {
+        faber_runtime::RuntimeResult::Success(results, _) => {
             match &results[0] {
                 faber_runtime::ExecutionStepResult::Single(task_result) => {
                     match task_result {
@@ -127,7 +137,7 @@ fn test_toolchain_mounts_are_read_only() {
         .build();
 
     let result = runtime.execute().expect("Runtime execution failed");
-    let faber_runtime::RuntimeResult::Success(results) = result else {
+    let faber_runtime::RuntimeResult::Success(results, _) = result else {
         panic!("Expected successful runtime result");
     };
     let faber_runtime::ExecutionStepResult::Single(task_result) = &results[0] else {
@@ -170,6 +180,9 @@ fn test_hostname_isolation() {
         files: None,
         working_dir: None,
         sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
     };
 
     let task_group: TaskGroup = vec![faber_runtime::ExecutionStep::Single(task)];
@@ -182,7 +195,7 @@ fn test_hostname_isolation() {
     assert!(result.is_ok(), "Runtime execution failed: {:?}", result);
 
     match result.unwrap() {
-        faber_runtime::RuntimeResult::Success(results) => match &results[0] {
+        faber_runtime::RuntimeResult::Success(results, _) => match &results[0] {
             faber_runtime::ExecutionStepResult::Single(task_result) => match task_result {
                 faber_runtime::TaskResult::Completed {
                     stdout,
@@ -220,7 +233,7 @@ fn test_sequential_execution() {
     assert!(result.is_ok(), "Runtime execution failed: {:?}", result);
 
     match result.unwrap() {
-        faber_runtime::RuntimeResult::Success(results) => {
+        faber_runtime::RuntimeResult::Success(results, _) => {
             assert_eq!(results.len(), 3, "Expected 3 results");
 
             for (i, step_result) in results.iter().enumerate() {
@@ -265,7 +278,7 @@ fn test_parallel_execution() {
     assert!(result.is_ok(), "Runtime execution failed: {:?}", result);
 
     match result.unwrap() {
-        faber_runtime::RuntimeResult::Success(results) => {
+        faber_runtime::RuntimeResult::Success(results, _) => {
             assert_eq!(results.len(), 1);
             match &results[0] {
                 faber_runtime::ExecutionStepResult::Parallel(task_results) => {
@@ -308,6 +321,9 @@ fn test_file_operations() {
         files: Some(files),
         working_dir: None,
         sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
     };
 
     let task_group: TaskGroup = vec![faber_runtime::ExecutionStep::Single(task)];
@@ -320,7 +336,7 @@ fn test_file_operations() {
     assert!(result.is_ok(), "Runtime execution failed: {:?}", result);
 
     match result.unwrap() {
-        faber_runtime::RuntimeResult::Success(results) => match &results[0] {
+        faber_runtime::RuntimeResult::Success(results, _) => match &results[0] {
             faber_runtime::ExecutionStepResult::Single(task_result) => match task_result {
                 faber_runtime::TaskResult::Completed {
                     stdout,
@@ -351,6 +367,9 @@ fn test_network_isolation() {
         files: None,
         working_dir: None,
         sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
     };
 
     let task_group: TaskGroup = vec![faber_runtime::ExecutionStep::Single(task)];
@@ -363,7 +382,7 @@ fn test_network_isolation() {
     assert!(result.is_ok(), "Runtime execution failed: {:?}", result);
 
     match result.unwrap() {
-        faber_runtime::RuntimeResult::Success(results) => match &results[0] {
+        faber_runtime::RuntimeResult::Success(results, _) => match &results[0] {
             faber_runtime::ExecutionStepResult::Single(task_result) => match task_result {
                 faber_runtime::TaskResult::Completed {
                     stdout,
@@ -405,6 +424,9 @@ fn test_resource_limits() {
         files: None,
         working_dir: None,
         sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
     };
 
     let task_group: TaskGroup = vec![faber_runtime::ExecutionStep::Single(task)];
@@ -417,7 +439,7 @@ fn test_resource_limits() {
     assert!(result.is_ok(), "Runtime execution failed: {:?}", result);
 
     match result.unwrap() {
-        faber_runtime::RuntimeResult::Success(results) => {
+        faber_runtime::RuntimeResult::Success(results, _) => {
             match &results[0] {
                 faber_runtime::ExecutionStepResult::Single(task_result) => {
                     match task_result {
@@ -445,3 +467,660 @@ fn test_resource_limits() {
         other => panic!("Expected success result, got {:?}", other),
     }
 }
+
+#[test]
+fn test_resource_timeline_and_pressure_are_reported() {
+    let task = create_test_task(
+        "/bin/sh",
+        vec![
+            "-c",
+            "sleep 0.3 & sleep 0.3 & wait; while :; do :; done & sleep 0.3",
+        ],
+    );
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![faber_runtime::ExecutionStep::Single(task)])
+        .with_cgroup_config(
+            CgroupConfigBuilder::new()
+                .with_sample_interval(Some(std::time::Duration::from_millis(50)))
+                .build(),
+        )
+        .build()
+        .execute()
+        .expect("Runtime execution failed");
+
+    let stats = match result {
+        faber_runtime::RuntimeResult::Success(results, _) => match &results[0] {
+            faber_runtime::ExecutionStepResult::Single(faber_runtime::TaskResult::Completed {
+                stats,
+                ..
+            }) => stats.clone(),
+            other => panic!("Expected completed task result, got {:?}", other),
+        },
+        other => panic!("Expected success result, got {:?}", other),
+    };
+
+    let timeline = &stats.timeline;
+    assert!(timeline.len() >= 5, "{timeline:?}");
+    assert!(
+        timeline
+            .windows(2)
+            .all(|pair| pair[0].elapsed_ms < pair[1].elapsed_ms
+                && pair[0].cpu_usage_usec <= pair[1].cpu_usage_usec)
+    );
+    // The two sleeps and the shell, then the busy loop and a sleep.
+    assert!(
+        timeline.iter().any(|sample| sample.pids >= 3),
+        "{timeline:?}"
+    );
+    assert!(timeline.iter().all(|sample| sample.memory_bytes > 0));
+
+    if std::path::Path::new("/proc/pressure/cpu").exists() {
+        let pressure = stats.pressure.expect("PSI is enabled but not reported");
+        for stall in [pressure.cpu, pressure.memory, pressure.io] {
+            assert!((0.0..=100.0).contains(&stall.some_avg), "{pressure:?}");
+            assert!(stall.full_usec <= stall.some_usec, "{pressure:?}");
+        }
+    }
+}
+
+#[test]
+fn test_process_usage_excludes_unwaited_helpers() {
+    let run = |script: &str| {
+        let result = RuntimeBuilder::default()
+            .with_task_group(vec![faber_runtime::ExecutionStep::Single(
+                create_test_task("/bin/sh", vec!["-c", script]),
+            )])
+            .build()
+            .execute()
+            .expect("Runtime execution failed");
+        match result {
+            faber_runtime::RuntimeResult::Success(results, _) => match &results[0] {
+                faber_runtime::ExecutionStepResult::Single(
+                    faber_runtime::TaskResult::Completed { stats, .. },
+                ) => stats.clone(),
+                other => panic!("Expected completed task result, got {:?}", other),
+            },
+            other => panic!("Expected success result, got {:?}", other),
+        }
+    };
+
+    // The shell itself does the work.
+    let busy = run("i=0; while [ $i -lt 200000 ]; do i=$((i+1)); done");
+    let usage = busy.process_usage.expect("process usage not reported");
+    assert!(usage.user_cpu_usec > 0, "{usage:?}");
+    assert!(usage.max_rss_bytes > 0, "{usage:?}");
+    assert!(usage.minor_page_faults > 0, "{usage:?}");
+    assert!(usage.user_cpu_usec + usage.system_cpu_usec <= busy.cpu_usage_usec + 10_000);
+
+    // A helper the shell never waits for is charged to the cgroup only.
+    let helper = run("while :; do :; done & sleep 0.5; kill $!");
+    let usage = helper.process_usage.expect("process usage not reported");
+    assert!(
+        (usage.user_cpu_usec + usage.system_cpu_usec) * 2 < helper.cpu_usage_usec,
+        "{usage:?} vs {} cgroup usec",
+        helper.cpu_usage_usec
+    );
+}
+
+#[test]
+fn test_termination_policy_gives_tasks_a_grace_period() {
+    let run = |script: &str, termination: Option<TerminationPolicy>| {
+        let mut task = create_test_task("/bin/sh", vec!["-c", script]);
+        task.termination = termination;
+        let result = RuntimeBuilder::default()
+            .with_task_group(vec![faber_runtime::ExecutionStep::Single(task)])
+            .with_timeout(std::time::Duration::from_millis(300))
+            .build()
+            .execute()
+            .expect("Runtime execution failed");
+        match result {
+            faber_runtime::RuntimeResult::Success(results, _) => match &results[0] {
+                faber_runtime::ExecutionStepResult::Single(
+                    faber_runtime::TaskResult::Completed { stdout, stats, .. },
+                ) => (stdout.clone(), stats.clone()),
+                other => panic!("Expected completed task result, got {:?}", other),
+            },
+            other => panic!("Expected success result, got {:?}", other),
+        }
+    };
+    let graceful = Some(TerminationPolicy {
+        signal: 15,
+        grace_period_ms: 2000,
+    });
+
+    // The task flushes its partial output on SIGTERM.
+    let (stdout, stats) = run(
+        "trap 'echo flushed; exit 3' TERM; echo partial; while :; do sleep 0.05; done",
+        graceful,
+    );
+    assert_eq!(stdout, "partial\nflushed\n");
+    assert_eq!(stats.outcome, faber_runtime::TaskOutcome::TimedOut);
+    assert_eq!(stats.termination_phase, Some(TerminationPhase::Signal));
+
+    // A task ignoring the signal is killed once the grace period is over.
+    let started = std::time::Instant::now();
+    let (_, stats) = run(
+        "trap '' TERM; while :; do sleep 0.05; done",
+        Some(TerminationPolicy {
+            signal: 15,
+            grace_period_ms: 200,
+        }),
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    assert_eq!(stats.termination_phase, Some(TerminationPhase::Kill));
+
+    // Without a policy the task is killed at once, and tasks that finish in
+    // time are not terminated at all.
+    let (stdout, stats) = run("trap 'echo flushed' TERM; sleep 5", None);
+    assert_eq!(stdout, "");
+    assert_eq!(stats.termination_phase, Some(TerminationPhase::Kill));
+    assert_eq!(run("true", graceful).1.termination_phase, None);
+}
+
+#[test]
+fn test_environment_rootfs_replaces_host_toolchain() {
+    use std::os::unix::fs::symlink;
+    use std::path::Path;
+
+    // A merged-/usr rootfs holding only a shell, its libraries and a marker in
+    // /etc proves the task's whole root is the environment, not the host.
+    let rootfs = std::env::temp_dir().join(format!("faber-env-{}", std::process::id()));
+    std::fs::create_dir_all(rootfs.join("etc")).expect("failed to create rootfs /etc");
+    for (link, target) in [
+        ("bin", "usr/bin"),
+        ("lib", "usr/lib"),
+        ("lib64", "usr/lib64"),
+    ] {
+        std::fs::create_dir_all(rootfs.join(target)).expect("failed to create rootfs");
+        symlink(target, rootfs.join(link)).expect("failed to create rootfs symlink");
+    }
+    std::fs::write(rootfs.join("etc/faber-env"), "environment\n").expect("failed to write marker");
+
+    let shell = std::fs::canonicalize("/bin/sh").expect("failed to resolve /bin/sh");
+    std::fs::copy(&shell, rootfs.join("usr/bin/sh")).expect("failed to copy shell");
+    let ldd = std::process::Command::new("ldd")
+        .arg(&shell)
+        .output()
+        .expect("failed to run ldd");
+    for library in String::from_utf8_lossy(&ldd.stdout)
+        .split_whitespace()
+        .filter(|word| word.starts_with('/'))
+    {
+        let target = rootfs.join(&library[1..]);
+        std::fs::create_dir_all(target.parent().unwrap()).expect("failed to create library dir");
+        std::fs::copy(Path::new(library), &target).expect("failed to copy library");
+    }
+
+    let environment = Environment::from_rootfs(&rootfs).expect("invalid environment");
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![faber_runtime::ExecutionStep::Single(
+            create_test_task(
+                "/bin/sh",
+                vec![
+                    "-c",
+                    "[ -L /bin ] && echo linked; read marker < /etc/faber-env && echo $marker; \
+                     [ -e /etc/passwd ] || echo no-host-etc",
+                ],
+            ),
+        )])
+        .with_container_config(
+            ContainerConfigBuilder::new()
+                .with_environment(&environment)
+                .build(),
+        )
+        .build()
+        .execute();
+
+    std::fs::remove_dir_all(&rootfs).expect("failed to remove rootfs");
+
+    let faber_runtime::RuntimeResult::Success(results, _) =
+        result.expect("Runtime execution failed")
+    else {
+        panic!("Expected successful runtime result");
+    };
+    let faber_runtime::ExecutionStepResult::Single(faber_runtime::TaskResult::Completed {
+        stdout,
+        exit_code,
+        ..
+    }) = &results[0]
+    else {
+        panic!("Expected completed task result, got {:?}", results[0]);
+    };
+
+    assert_eq!(*exit_code, 0);
+    assert_eq!(
+        stdout.split_whitespace().collect::<Vec<_>>(),
+        ["linked", "environment", "no-host-etc"]
+    );
+}
+
+#[test]
+fn test_environment_rejects_missing_rootfs() {
+    assert!(Environment::from_rootfs("/nonexistent/faber-rootfs").is_err());
+    assert!(Environment::from_rootfs("relative/rootfs").is_err());
+}
+
+#[test]
+fn test_workspace_template_is_shared_but_not_written() {
+    let base = std::env::temp_dir().join(format!("faber-workspace-{}", std::process::id()));
+    let common = base.join("common");
+    let starter = base.join("starter");
+    std::fs::create_dir_all(&common).expect("failed to create common layer");
+    std::fs::create_dir_all(&starter).expect("failed to create starter layer");
+    std::fs::write(common.join("main.c"), "common").expect("failed to write common file");
+    std::fs::write(starter.join("main.c"), "starter").expect("failed to write starter file");
+
+    let template = WorkspaceTemplate::from_layers(vec![common.clone(), starter.clone()])
+        .expect("invalid workspace template");
+    let run = || {
+        let result = RuntimeBuilder::default()
+            .with_task_group(vec![faber_runtime::ExecutionStep::Single(
+                create_test_task(
+                    "/bin/sh",
+                    vec![
+                        "-c",
+                        "cat /faber/main.c; ls /faber/out 2>/dev/null; echo written > /faber/out",
+                    ],
+                ),
+            )])
+            .with_container_config(
+                ContainerConfigBuilder::new()
+                    .with_workspace_template(&template)
+                    .build(),
+            )
+            .build()
+            .execute();
+
+        let faber_runtime::RuntimeResult::Success(results, _) =
+            result.expect("Runtime execution failed")
+        else {
+            panic!("Expected successful runtime result");
+        };
+        let faber_runtime::ExecutionStepResult::Single(faber_runtime::TaskResult::Completed {
+            stdout,
+            exit_code,
+            ..
+        }) = &results[0]
+        else {
+            panic!("Expected completed task result, got {:?}", results[0]);
+        };
+        assert_eq!(*exit_code, 0);
+        stdout.clone()
+    };
+
+    // The second run must not see the first run's write.
+    assert_eq!(run(), "starter");
+    assert_eq!(run(), "starter");
+    assert!(!starter.join("out").exists());
+    assert_eq!(
+        std::fs::read_to_string(starter.join("main.c")).unwrap(),
+        "starter"
+    );
+
+    std::fs::remove_dir_all(&base).expect("failed to remove workspace layers");
+}
+
+#[test]
+fn test_workspace_template_rejects_invalid_layers() {
+    assert!(WorkspaceTemplate::from_layers(Vec::new()).is_err());
+    assert!(WorkspaceTemplate::from_layers(vec!["relative/layer".into()]).is_err());
+    assert!(WorkspaceTemplate::from_layers(vec!["/nonexistent/faber-layer".into()]).is_err());
+}
+
+#[test]
+fn test_extra_mounts_apply_requested_flags() {
+    let dataset = std::env::temp_dir().join(format!("faber-dataset-{}", std::process::id()));
+    std::fs::create_dir_all(&dataset).expect("failed to create dataset");
+    std::fs::write(dataset.join("data.txt"), "dataset").expect("failed to write dataset");
+    // A submount of the bind source must be read-only inside the container too.
+    let nested = dataset.join("nested");
+    std::fs::create_dir_all(&nested).expect("failed to create nested mount point");
+    nix::mount::mount(
+        Some("tmpfs"),
+        &nested,
+        Some("tmpfs"),
+        nix::mount::MsFlags::empty(),
+        Some("size=1M"),
+    )
+    .expect("failed to mount nested tmpfs");
+
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![faber_runtime::ExecutionStep::Single(
+            create_test_task(
+                "/bin/sh",
+                vec![
+                    "-c",
+                    "cat /data/data.txt; touch /data/new 2>/dev/null || echo ' ro'; \
+                     touch /data/nested/new 2>/dev/null || echo 'nested ro'; \
+                     echo scratch > /scratch/file && cat /scratch/file",
+                ],
+            ),
+        )])
+        .with_container_config(
+            ContainerConfigBuilder::new()
+                .with_mount(MountSpec::bind(&dataset, "/data").expect("invalid bind mount"))
+                .with_mount(MountSpec::tmpfs("/scratch", "1M").expect("invalid tmpfs mount"))
+                .build(),
+        )
+        .build()
+        .execute();
+
+    nix::mount::umount2(&nested, nix::mount::MntFlags::MNT_DETACH)
+        .expect("failed to unmount nested tmpfs");
+    std::fs::remove_dir_all(&dataset).expect("failed to remove dataset");
+
+    let faber_runtime::RuntimeResult::Success(results, _) =
+        result.expect("Runtime execution failed")
+    else {
+        panic!("Expected successful runtime result");
+    };
+    let faber_runtime::ExecutionStepResult::Single(faber_runtime::TaskResult::Completed {
+        stdout,
+        exit_code,
+        ..
+    }) = &results[0]
+    else {
+        panic!("Expected completed task result, got {:?}", results[0]);
+    };
+
+    assert_eq!(*exit_code, 0);
+    assert_eq!(stdout, "dataset ro\nnested ro\nscratch\n");
+}
+
+#[test]
+fn test_io_limits_only_accept_block_devices() {
+    assert!(matches!(
+        IoMax::for_device("/dev/null"),
+        Err(FaberError::InvalidCgroupConfig { .. })
+    ));
+    assert!(matches!(
+        IoMax::for_device("/nonexistent/faber-disk"),
+        Err(FaberError::InvalidCgroupConfig { .. })
+    ));
+}
+
+/// Returns the whole-disk device numbers backing `path`, or `None` if it is
+/// not on a block device (e.g. tmpfs or overlayfs).
+fn backing_disk(path: &std::path::Path) -> Option<(u32, u32)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let device = std::fs::metadata(path).ok()?.dev();
+    let (major, minor) = (nix::sys::stat::major(device), nix::sys::stat::minor(device));
+    if major == 0 {
+        return None;
+    }
+    // io.max only accepts whole disks, so resolve a partition to its parent.
+    let sysfs = std::path::PathBuf::from(format!("/sys/dev/block/{major}:{minor}"));
+    let disk = if sysfs.join("partition").exists() {
+        sysfs.join("..")
+    } else {
+        sysfs
+    };
+    let numbers = std::fs::read_to_string(disk.join("dev")).ok()?;
+    let (major, minor) = numbers.trim().split_once(':')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[test]
+fn test_io_max_throttles_writes_and_io_stat_reports_them() {
+    let dataset = std::env::temp_dir().join(format!("faber-io-{}", std::process::id()));
+    std::fs::create_dir_all(&dataset).expect("failed to create bind mount source");
+    let Some((major, minor)) = backing_disk(&dataset) else {
+        std::fs::remove_dir_all(&dataset).ok();
+        eprintln!("{} is not on a block device; skipping", dataset.display());
+        return;
+    };
+    std::fs::set_permissions(
+        &dataset,
+        std::os::unix::fs::PermissionsExt::from_mode(0o777),
+    )
+    .expect("failed to open up bind mount source");
+
+    const WRITE_BPS: u64 = 2 * 1024 * 1024;
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![faber_runtime::ExecutionStep::Single(
+            create_test_task(
+                "/bin/dd",
+                vec![
+                    "if=/dev/zero",
+                    "of=/data/blob",
+                    "bs=1M",
+                    "count=4",
+                    "oflag=direct",
+                ],
+            ),
+        )])
+        .with_container_config(
+            ContainerConfigBuilder::new()
+                .with_mount(
+                    MountSpec::bind(&dataset, "/data")
+                        .expect("invalid bind mount")
+                        .read_only(false),
+                )
+                .build(),
+        )
+        .with_cgroup_config(
+            CgroupConfigBuilder::new()
+                .with_io_max(IoMax::new(major, minor).write_bps(WRITE_BPS))
+                .with_io_weight(50)
+                .expect("invalid io.weight")
+                .build(),
+        )
+        .build()
+        .execute();
+
+    std::fs::remove_dir_all(&dataset).expect("failed to remove bind mount source");
+
+    let faber_runtime::RuntimeResult::Success(results, _) =
+        result.expect("Runtime execution failed")
+    else {
+        panic!("Expected successful runtime result");
+    };
+    let faber_runtime::ExecutionStepResult::Single(faber_runtime::TaskResult::Completed {
+        exit_code,
+        stderr,
+        stats,
+        ..
+    }) = &results[0]
+    else {
+        panic!("Expected completed task result, got {:?}", results[0]);
+    };
+
+    assert_eq!(*exit_code, 0, "dd failed: {stderr}");
+    assert!(
+        stats.io_write_bytes >= 4 * 1024 * 1024,
+        "direct writes missing from io.stat: {stats:?}"
+    );
+    assert!(stats.io_write_ops > 0);
+    assert!(
+        stats.execution_time_ms >= 1000,
+        "4 MiB at 2 MiB/s finished too quickly: {stats:?}"
+    );
+}
+
+#[test]
+fn test_container_pool_serves_matching_runtimes() {
+    let pool = ContainerPoolBuilder::default()
+        .with_size(1)
+        .with_shape(
+            ContainerConfigBuilder::new().build(),
+            CgroupConfigBuilder::new().build(),
+        )
+        .build();
+    let wait_until_ready = || {
+        for _ in 0..250 {
+            if pool.stats().ready == 1 {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        panic!("pool never became ready: {:?}", pool.stats());
+    };
+    let run = |cgroup_config| {
+        let result = RuntimeBuilder::default()
+            .with_task_group(vec![faber_runtime::ExecutionStep::Single(
+                create_test_task("/bin/echo", vec!["warm"]),
+            )])
+            .with_cgroup_config(cgroup_config)
+            .with_pool(pool.clone())
+            .build()
+            .execute();
+        match result.expect("Runtime execution failed") {
+            faber_runtime::RuntimeResult::Success(results, _) => match &results[0] {
+                faber_runtime::ExecutionStepResult::Single(
+                    faber_runtime::TaskResult::Completed { stdout, .. },
+                ) => stdout.clone(),
+                other => panic!("Expected completed task result, got {:?}", other),
+            },
+            other => panic!("Expected success result, got {:?}", other),
+        }
+    };
+
+    wait_until_ready();
+    assert_eq!(run(CgroupConfigBuilder::new().build()), "warm\n");
+    assert_eq!(pool.stats().hits, 1);
+
+    // A different cgroup shape cannot use the pool but still runs inline.
+    assert_eq!(
+        run(CgroupConfigBuilder::new().with_pids(32).build()),
+        "warm\n"
+    );
+    assert_eq!(pool.stats().misses, 1);
+
+    // The claimed container is replaced in the background.
+    wait_until_ready();
+    assert_eq!(pool.stats().spawned, 2);
+}
+
+#[tokio::test]
+async fn test_cpu_allocator_pins_parallel_tasks_to_the_leased_core() {
+    let cpus = CpuAllocatorBuilder::default()
+        .with_cpus(vec![0])
+        .build()
+        .unwrap();
+    let backend = NamespaceBackend::default().with_cpu_allocator(cpus.clone());
+    let allowed = || {
+        create_test_task(
+            "/bin/sh",
+            vec!["-c", "grep Cpus_allowed_list /proc/self/status"],
+        )
+    };
+
+    let result = backend
+        .execute(
+            vec![faber_runtime::ExecutionStep::Parallel(vec![
+                allowed(),
+                allowed(),
+            ])],
+            ContainerConfigBuilder::new().build(),
+        )
+        .await
+        .expect("Runtime execution failed");
+
+    let faber_runtime::RuntimeResult::Success(results, _) = result else {
+        panic!("Expected success result, got {:?}", result);
+    };
+    let faber_runtime::ExecutionStepResult::Parallel(tasks) = &results[0] else {
+        panic!("Expected a parallel step, got {:?}", results[0]);
+    };
+    for task in tasks {
+        match task {
+            faber_runtime::TaskResult::Completed { stdout, .. } => {
+                assert_eq!(stdout.split_whitespace().last(), Some("0"), "{stdout}")
+            }
+            other => panic!("Expected completed task result, got {:?}", other),
+        }
+    }
+    // The lease is returned once the execution has finished.
+    assert_eq!(cpus.stats().free, 1);
+}
+
+#[test]
+fn test_runtime_reports_phase_timings() {
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![faber_runtime::ExecutionStep::Single(
+            create_test_task("/bin/sleep", vec!["0.05"]),
+        )])
+        .build()
+        .execute()
+        .expect("Runtime execution failed");
+
+    let faber_runtime::RuntimeResult::Success(results, timings) = result else {
+        panic!("Expected success result, got {:?}", result);
+    };
+    assert!(timings.container_setup_usec > 0);
+    assert!(timings.cleanup_usec > 0);
+    assert!(timings.steps_usec >= 50_000);
+    assert!(
+        timings.total_usec
+            >= timings.container_setup_usec + timings.steps_usec + timings.cleanup_usec
+    );
+
+    let faber_runtime::ExecutionStepResult::Single(faber_runtime::TaskResult::Completed {
+        stats,
+        ..
+    }) = &results[0]
+    else {
+        panic!("Expected a completed task, got {:?}", results[0]);
+    };
+    let task = stats.timings;
+    assert!(task.cgroup_setup_usec > 0);
+    assert!(task.spawn_usec > 0);
+    assert!(task.run_usec >= 50_000);
+    assert!(task.cleanup_usec > 0);
+    assert!(timings.steps_usec >= task.cgroup_setup_usec + task.spawn_usec + task.run_usec);
+}
+
+#[tokio::test]
+async fn test_spawned_runtime_reports_lifecycle_events() {
+    let mut handle = RuntimeBuilder::default()
+        .with_task_group(vec![
+            faber_runtime::ExecutionStep::Single(create_test_task("/bin/echo", vec!["one"])),
+            faber_runtime::ExecutionStep::Single(create_test_task("/bin/echo", vec!["two"])),
+        ])
+        .build()
+        .spawn()
+        .expect("Failed to spawn runtime");
+
+    let mut events = Vec::new();
+    while let Some(event) = handle.next_event().await {
+        events.push(event);
+    }
+    assert_eq!(
+        events,
+        vec![
+            RuntimeEvent::ContainerReady,
+            RuntimeEvent::StepStarted { index: 0 },
+            RuntimeEvent::StepFinished { index: 0 },
+            RuntimeEvent::StepStarted { index: 1 },
+            RuntimeEvent::StepFinished { index: 1 },
+        ]
+    );
+
+    match handle.wait().await.expect("Runtime execution failed") {
+        faber_runtime::RuntimeResult::Success(results, _) => assert_eq!(results.len(), 2),
+        other => panic!("Expected success result, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_cancelling_a_spawned_runtime_kills_its_tasks() {
+    let mut handle = RuntimeBuilder::default()
+        .with_task_group(vec![faber_runtime::ExecutionStep::Single(
+            create_test_task("/bin/sh", vec!["-c", "sleep 30 & wait"]),
+        )])
+        .build()
+        .spawn()
+        .expect("Failed to spawn runtime");
+
+    while let Some(event) = handle.next_event().await {
+        if event == (RuntimeEvent::StepStarted { index: 0 }) {
+            break;
+        }
+    }
+    handle.cancel();
+
+    let started = std::time::Instant::now();
+    assert!(matches!(handle.wait().await, Err(FaberError::Cancelled)));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
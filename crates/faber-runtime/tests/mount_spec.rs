@@ -0,0 +1,79 @@
+use faber_runtime::MountSpec;
+
+#[test]
+fn bind_specs_default_to_read_only() {
+    let parsed: MountSpec = "/usr/share:/opt/share".parse().unwrap();
+
+    assert_eq!(parsed, MountSpec::bind("/usr/share", "/opt/share").unwrap());
+    assert_eq!(
+        parsed,
+        MountSpec::bind("/usr/share", "/opt/share")
+            .unwrap()
+            .read_only(true)
+    );
+}
+
+#[test]
+fn options_override_the_defaults() {
+    let parsed: MountSpec = "/usr/share:/data:rw,noexec,dev".parse().unwrap();
+    let expected = MountSpec::bind("/usr/share", "/data")
+        .unwrap()
+        .read_only(false)
+        .noexec(true)
+        .nodev(false);
+
+    assert_eq!(parsed, expected);
+}
+
+#[test]
+fn tmpfs_specs_require_a_size() {
+    let parsed: MountSpec = "tmpfs:/scratch:size=64M,noexec".parse().unwrap();
+
+    assert_eq!(
+        parsed,
+        MountSpec::tmpfs("/scratch", "64M").unwrap().noexec(true)
+    );
+    assert!("tmpfs:/scratch".parse::<MountSpec>().is_err());
+}
+
+#[test]
+fn targets_must_stay_below_the_container_root() {
+    for target in ["/", "relative", "/opt/../etc", ""] {
+        assert!(
+            MountSpec::tmpfs(target, "1M").is_err(),
+            "accepted target {target:?}"
+        );
+    }
+}
+
+#[test]
+fn targets_must_not_shadow_container_paths() {
+    for target in ["/proc", "/dev/shm", "/tmp", "/sys/fs/cgroup", "/oldroot"] {
+        assert!(
+            MountSpec::tmpfs(target, "1M").is_err(),
+            "accepted target {target:?}"
+        );
+    }
+    assert!(MountSpec::tmpfs("/procfs", "1M").is_ok());
+}
+
+#[test]
+fn malformed_specs_are_rejected() {
+    assert!("/usr/share".parse::<MountSpec>().is_err());
+    assert!("relative:/opt/share".parse::<MountSpec>().is_err());
+    assert!(
+        "/nonexistent/faber-source:/opt"
+            .parse::<MountSpec>()
+            .is_err()
+    );
+    assert!(
+        "/usr/share:/opt/share:nosuchopt"
+            .parse::<MountSpec>()
+            .is_err()
+    );
+    assert!(
+        "/usr/share:/opt/share:size=1M"
+            .parse::<MountSpec>()
+            .is_err()
+    );
+}
@@ -0,0 +1,36 @@
+use faber_runtime::{check_cgroups, check_free_space};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn free_space_is_measured_on_the_nearest_existing_directory() {
+    let check = check_free_space("space", Path::new("/tmp/faber-missing/a/b"), 0);
+
+    assert!(check.ready, "{check:?}");
+    assert_eq!(check.name, "space");
+    assert!(check.detail.ends_with("free at /tmp"), "{}", check.detail);
+}
+
+#[test]
+fn too_little_free_space_fails() {
+    let check = check_free_space("space", Path::new("/"), u64::MAX);
+
+    assert!(!check.ready);
+    assert!(check.detail.contains("below"), "{}", check.detail);
+}
+
+#[test]
+fn checking_cgroups_creates_nothing() {
+    let own = std::fs::read_to_string("/proc/self/cgroup")
+        .unwrap()
+        .lines()
+        .find_map(|line| line.strip_prefix("0::").map(str::to_string))
+        .unwrap_or_default();
+    let faber = PathBuf::from("/sys/fs/cgroup")
+        .join(own.trim_start_matches('/'))
+        .join("faber");
+    let existed = faber.exists();
+
+    check_cgroups();
+
+    assert_eq!(faber.exists(), existed);
+}
@@ -1,6 +1,7 @@
 use faber_runtime::{
-    CgroupConfigBuilder, ContainerConfigBuilder, ExecutionStep, ExecutionStepResult,
-    RuntimeBuilder, RuntimeResult, SandboxProfile, Task, TaskOutcome, TaskResult,
+    CgroupConfigBuilder, ContainerConfigBuilder, ErrorCode, ErrorStage, ExecutionStep,
+    ExecutionStepResult, MountSpec, OutputCapture, OutputRetention, OutputStream, RuntimeBuilder,
+    RuntimeResult, SandboxProfile, Task, TaskOutcome, TaskResult,
 };
 use nix::libc;
 use serde::Deserialize;
@@ -637,6 +638,9 @@ fn task(cmd: &str, args: &[&str]) -> Task {
         files: None,
         working_dir: None,
         sandbox_profile: None,
+        termination: None,
+        capture: None,
+        stdin_file_id: None,
     }
 }
 
@@ -658,7 +662,7 @@ fn execute(tasks: Vec<Task>) -> Vec<ExecutionStepResult> {
         .execute()
         .expect("runtime execution failed");
 
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -708,10 +712,14 @@ fn container_setup_failures_remove_partial_roots_and_cgroups() {
         .execute()
         .expect("runtime controller failed");
 
-    let RuntimeResult::ContainerSetupFailed { error } = result else {
+    let RuntimeResult::ContainerSetupFailed { error, info } = result else {
         panic!("invalid mount unexpectedly produced a runtime: {result:?}");
     };
     assert!(error.contains("Container setup failed"));
+    assert_eq!(info.code, ErrorCode::Filesystem);
+    assert_eq!(info.stage, ErrorStage::ContainerSetup);
+    assert_eq!(info.errno, Some(libc::EINVAL));
+    assert!(!info.retryable);
     assert_eq!(
         container_roots(),
         roots_before,
@@ -1028,6 +1036,152 @@ fn filesystem_hides_outer_root_and_keeps_toolchains_read_only() {
     assert_eq!(*exit_code, 0, "filesystem boundary probe failed: {stderr}");
 }
 
+fn landlock_available() -> bool {
+    std::fs::read_to_string("/sys/kernel/security/lsm")
+        .is_ok_and(|lsms| lsms.trim().split(',').any(|lsm| lsm == "landlock"))
+}
+
+/// Runs `script` with a host directory bound writable at `/data` and a
+/// tmpfs at `/scratch`, as an operator might misconfigure them.
+fn execute_with_writable_mounts(
+    script: &str,
+    host_dir: &std::path::Path,
+    landlock: bool,
+) -> TaskResult {
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![ExecutionStep::Single(task(
+            "/bin/sh",
+            &["-c", script],
+        ))])
+        .with_container_config(
+            ContainerConfigBuilder::new()
+                .with_mount(
+                    MountSpec::bind(host_dir, "/data")
+                        .expect("invalid bind mount")
+                        .read_only(false),
+                )
+                .with_mount(MountSpec::tmpfs("/scratch", "1M").expect("invalid tmpfs mount"))
+                .with_landlock(landlock)
+                .build(),
+        )
+        .build()
+        .execute()
+        .expect("runtime execution failed");
+    let RuntimeResult::Success(results, _) = result else {
+        panic!("container setup failed: {result:?}");
+    };
+    assert_no_task_cgroups();
+    single_result(&results[0]).clone()
+}
+
+#[test]
+fn landlock_blocks_writes_outside_the_workspace_even_through_writable_mounts() {
+    let _guard = lock_security_tests();
+    if !landlock_available() {
+        eprintln!("Landlock is not enabled on this kernel; skipping");
+        return;
+    }
+    let host_dir = std::env::temp_dir().join(format!("faber-landlock-{}", std::process::id()));
+    std::fs::create_dir_all(&host_dir).expect("failed to create bind mount source");
+    std::fs::set_permissions(
+        &host_dir,
+        std::os::unix::fs::PermissionsExt::from_mode(0o777),
+    )
+    .expect("failed to open up bind mount source");
+    std::fs::write(host_dir.join("readme"), "readable\n").expect("failed to seed bind mount");
+
+    // Without Landlock the misconfigured mounts really are writable.
+    let unconfined = execute_with_writable_mounts(
+        "touch /data/unconfined && touch /scratch/unconfined",
+        &host_dir,
+        false,
+    );
+    let TaskResult::Completed {
+        exit_code, stats, ..
+    } = &unconfined
+    else {
+        panic!("unconfined probe did not complete: {unconfined:?}");
+    };
+    assert_eq!(*exit_code, 0, "mounts were not writable without Landlock");
+    assert!(!stats.landlock_enforced);
+
+    let confined = execute_with_writable_mounts(
+        "touch workspace-file && touch /tmp/tmp-file && cat /data/readme && \
+         ! touch /data/escape 2>/dev/null && \
+         ! touch /scratch/escape 2>/dev/null && \
+         ! mkdir /data/escape-dir 2>/dev/null && \
+         ! rm /data/unconfined 2>/dev/null",
+        &host_dir,
+        true,
+    );
+    let escaped = host_dir.join("escape").exists() || host_dir.join("escape-dir").exists();
+    let unconfined_kept = host_dir.join("unconfined").exists();
+    std::fs::remove_dir_all(&host_dir).expect("failed to remove bind mount source");
+
+    let TaskResult::Completed {
+        exit_code,
+        stdout,
+        stderr,
+        stats,
+    } = &confined
+    else {
+        panic!("Landlock probe did not complete: {confined:?}");
+    };
+    assert_eq!(*exit_code, 0, "Landlock probe failed: {stderr}");
+    assert_eq!(stdout, "readable\n");
+    assert!(stats.landlock_enforced);
+    assert!(!escaped, "a Landlock-confined task wrote to a bind mount");
+    assert!(unconfined_kept, "a Landlock-confined task removed a file");
+}
+
+#[test]
+fn landlock_keeps_toolchains_usable_across_parallel_tasks() {
+    let _guard = lock_security_tests();
+    if !landlock_available() {
+        eprintln!("Landlock is not enabled on this kernel; skipping");
+        return;
+    }
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![
+            ExecutionStep::Single(task_with_file(
+                "/usr/bin/gcc",
+                &["main.c", "-o", "main"],
+                "main.c",
+                "int main(void) { return 7; }\n",
+            )),
+            ExecutionStep::Parallel(vec![task("./main", &[]), task("./main", &[])]),
+        ])
+        .with_container_config(ContainerConfigBuilder::new().with_landlock(true).build())
+        .build()
+        .execute()
+        .expect("runtime execution failed");
+    let RuntimeResult::Success(results, _) = result else {
+        panic!("container setup failed: {result:?}");
+    };
+    assert_no_task_cgroups();
+
+    let TaskResult::Completed {
+        exit_code, stderr, ..
+    } = single_result(&results[0])
+    else {
+        panic!("compilation did not complete: {:?}", results[0]);
+    };
+    assert_eq!(*exit_code, 0, "compilation failed under Landlock: {stderr}");
+    let ExecutionStepResult::Parallel(runs) = &results[1] else {
+        panic!("expected a parallel step result");
+    };
+    for run in runs {
+        let TaskResult::Completed {
+            exit_code, stats, ..
+        } = run
+        else {
+            panic!("compiled program did not run: {run:?}");
+        };
+        assert_eq!(*exit_code, 7);
+        assert!(stats.landlock_enforced);
+    }
+}
+
 #[test]
 fn submitted_files_reject_absolute_and_parent_paths() {
     let _guard = lock_security_tests();
@@ -1037,10 +1191,12 @@ fn submitted_files_reject_absolute_and_parent_paths() {
     ]);
 
     for result in results {
-        let TaskResult::Failed { error, stats } = single_result(&result) else {
+        let TaskResult::Failed { error, info, stats } = single_result(&result) else {
             panic!("unsafe task file path was accepted: {result:?}");
         };
         assert_eq!(stats.outcome, TaskOutcome::InfrastructureFailure);
+        assert_eq!(info.code, ErrorCode::InvalidTask);
+        assert_eq!(info.stage, ErrorStage::Validation);
         assert!(
             error.contains("paths must be normalized and relative"),
             "unexpected path rejection: {error}"
@@ -1156,7 +1312,7 @@ fn parallel_symlink_swaps_cannot_redirect_submitted_files() {
         .build()
         .execute()
         .expect("runtime execution failed");
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -1215,7 +1371,7 @@ fn output_streams_are_drained_bounded_and_report_truncation() {
             .build()
             .execute()
             .expect("runtime execution failed");
-        let RuntimeResult::Success(results) = result else {
+        let RuntimeResult::Success(results, _) = result else {
             panic!("container setup failed: {result:?}");
         };
         assert_no_task_cgroups();
@@ -1241,6 +1397,67 @@ fn output_streams_are_drained_bounded_and_report_truncation() {
     }
 }
 
+#[test]
+fn head_and_tail_capture_lets_chatty_tasks_finish_with_a_merged_stream() {
+    let _guard = lock_security_tests();
+    const OUTPUT_LIMIT: usize = 4096;
+    let mut chatty = task(
+        "/bin/sh",
+        &["-c", "echo start >&2; seq 100000; echo done >&2"],
+    );
+    chatty.capture = Some(OutputCapture {
+        retain: OutputRetention::HeadAndTail,
+        keep_running: true,
+        merged: true,
+        ..Default::default()
+    });
+
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![ExecutionStep::Single(chatty)])
+        .with_output_limit(OUTPUT_LIMIT)
+        .with_timeout(std::time::Duration::from_secs(5))
+        .build()
+        .execute()
+        .expect("runtime execution failed");
+    let RuntimeResult::Success(results, _) = result else {
+        panic!("container setup failed: {result:?}");
+    };
+    assert_no_task_cgroups();
+
+    let TaskResult::Completed {
+        stdout,
+        stderr,
+        exit_code,
+        stats,
+    } = single_result(&results[0])
+    else {
+        panic!("chatty task did not produce a result: {:?}", results[0]);
+    };
+    assert_eq!(*exit_code, 0, "task was stopped at the output limit");
+    assert_eq!(stats.outcome, TaskOutcome::Exited);
+    assert_eq!(stats.termination_phase, None);
+    assert!(stdout.starts_with("1\n2\n3\n"));
+    assert!(stdout.ends_with("99999\n100000\n"));
+    assert!(stdout.contains(&format!(
+        "\n[... {} bytes elided ...]\n",
+        stats.stdout_elided_bytes
+    )));
+    assert_eq!(stderr, "start\ndone\n");
+    assert!(stats.stdout_truncated);
+
+    let merged = stats.merged_output.as_ref().expect("merged stream missing");
+    let (first, last) = (&merged.chunks[0], &merged.chunks[merged.chunks.len() - 1]);
+    assert_eq!(
+        (first.stream, first.data.as_str()),
+        (OutputStream::Stderr, "start\n")
+    );
+    assert_eq!(
+        (last.stream, last.data.as_str()),
+        (OutputStream::Stderr, "done\n")
+    );
+    assert!(merged.elided_bytes > 0);
+}
+
 #[test]
 fn stdin_and_stdout_progress_concurrently_without_pipe_deadlock() {
     let _guard = lock_security_tests();
@@ -1255,7 +1472,7 @@ fn stdin_and_stdout_progress_concurrently_without_pipe_deadlock() {
         .build()
         .execute()
         .expect("runtime execution failed");
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -1289,7 +1506,7 @@ fn parallel_large_results_do_not_deadlock_result_transport() {
         .build()
         .execute()
         .expect("runtime execution failed");
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -1326,7 +1543,7 @@ fn concurrent_tasks_use_distinct_cgroups_and_cleanup_all_of_them() {
         .build()
         .execute()
         .expect("runtime execution failed");
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -1505,7 +1722,7 @@ fn timeout_kills_the_complete_task_process_tree() {
         .build()
         .execute()
         .expect("runtime execution failed");
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -1529,6 +1746,95 @@ fn timeout_kills_the_complete_task_process_tree() {
     );
 }
 
+#[test]
+fn cpu_time_budget_covers_every_process_in_the_task() {
+    let _guard = lock_security_tests();
+    const BUDGET: std::time::Duration = std::time::Duration::from_secs(1);
+
+    // Four busy workers each stay well under the per-process RLIMIT_CPU.
+    let script = "for i in 1 2 3 4; do (while :; do :; done) & done; wait";
+    let started = std::time::Instant::now();
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![ExecutionStep::Single(task(
+            "/bin/sh",
+            &["-c", script],
+        ))])
+        .with_cgroup_config(
+            CgroupConfigBuilder::new()
+                .with_cpu("400000 100000".to_string())
+                .with_cpu_time_budget(BUDGET)
+                .build(),
+        )
+        .with_cpu_time_limit(std::time::Duration::from_secs(5))
+        .with_timeout(std::time::Duration::from_secs(10))
+        .build()
+        .execute()
+        .expect("runtime execution failed");
+    let RuntimeResult::Success(results, _) = result else {
+        panic!("container setup failed: {result:?}");
+    };
+    assert_no_task_cgroups();
+
+    let TaskResult::Completed { stats, .. } = single_result(&results[0]) else {
+        panic!("CPU budget probe did not complete: {:?}", results[0]);
+    };
+    assert_eq!(stats.outcome, TaskOutcome::CpuTimeLimit, "{stats:?}");
+    assert!(
+        stats.cpu_usage_usec >= BUDGET.as_micros() as u64,
+        "task stopped before spending its budget: {stats:?}"
+    );
+    assert!(
+        stats.cpu_usage_usec < 2 * BUDGET.as_micros() as u64,
+        "task overshot its CPU budget: {stats:?}"
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(8));
+}
+
+#[test]
+fn idle_timeout_stops_blocked_tasks_but_not_busy_or_chatty_ones() {
+    let _guard = lock_security_tests();
+    let run = |script: &str| {
+        let started = std::time::Instant::now();
+        let result = RuntimeBuilder::default()
+            .with_task_group(vec![ExecutionStep::Single(task(
+                "/bin/sh",
+                &["-c", script],
+            ))])
+            .with_cgroup_config(
+                CgroupConfigBuilder::new()
+                    .with_idle_timeout(std::time::Duration::from_millis(500))
+                    .build(),
+            )
+            .with_timeout(std::time::Duration::from_secs(10))
+            .build()
+            .execute()
+            .expect("runtime execution failed");
+        let RuntimeResult::Success(results, _) = result else {
+            panic!("container setup failed: {result:?}");
+        };
+        let TaskResult::Completed { stats, .. } = single_result(&results[0]) else {
+            panic!("idle probe did not complete: {:?}", results[0]);
+        };
+        (stats.outcome.clone(), started.elapsed())
+    };
+
+    // Blocked reading a pipe nobody writes to, and asleep.
+    for script in ["mkfifo never && read line < never", "sleep 60"] {
+        let (outcome, elapsed) = run(script);
+        assert_eq!(outcome, TaskOutcome::IdleTimeout, "{script}");
+        assert!(
+            elapsed < std::time::Duration::from_secs(3),
+            "{script}: {elapsed:?}"
+        );
+    }
+
+    // Steady CPU use or output keeps a task alive past the idle timeout.
+    let busy = "end=$(($(date +%s) + 2)); while [ $(date +%s) -lt $end ]; do :; done";
+    assert_eq!(run(busy).0, TaskOutcome::Exited);
+    let chatty = "for i in 1 2 3 4 5 6; do echo $i; sleep 0.2; done";
+    assert_eq!(run(chatty).0, TaskOutcome::Exited);
+}
+
 #[test]
 fn file_descriptor_file_size_stack_core_and_cpu_rlimits_are_enforced() {
     let _guard = lock_security_tests();
@@ -1558,7 +1864,7 @@ fn file_descriptor_file_size_stack_core_and_cpu_rlimits_are_enforced() {
         .build()
         .execute()
         .expect("runtime execution failed");
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -1619,7 +1925,7 @@ fn pids_cgroup_enforces_the_process_limit() {
         .build()
         .execute()
         .expect("runtime execution failed");
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -1693,7 +1999,7 @@ fn memory_cgroup_kills_a_process_that_exceeds_memory_max() {
         .build()
         .execute()
         .expect("runtime execution failed");
-    let RuntimeResult::Success(results) = result else {
+    let RuntimeResult::Success(results, _) = result else {
         panic!("container setup failed: {result:?}");
     };
     assert_no_task_cgroups();
@@ -1724,6 +2030,8 @@ fn memory_cgroup_kills_a_process_that_exceeds_memory_max() {
     assert_eq!(stats.outcome, TaskOutcome::OutOfMemory);
     assert_eq!(stats.termination_signal, Some(9));
     assert!(stats.oom_kill_count > 0);
+    assert!(stats.oom_count > 0);
+    assert!(stats.memory_max_count > 0);
     assert!(stats.cleanup_succeeded);
     assert!(
         stats.memory_peak_bytes >= MEMORY_LIMIT / 2,
@@ -1736,3 +2044,56 @@ fn memory_cgroup_kills_a_process_that_exceeds_memory_max() {
         stats
     );
 }
+
+#[test]
+fn memory_high_throttles_without_killing_and_page_cache_is_reported() {
+    let _guard = lock_security_tests();
+    const MEMORY_HIGH: u64 = 16 * 1024 * 1024;
+
+    let script = "dd if=/dev/zero of=/tmp/cache bs=1M count=8 2>/dev/null && \
+                  anon=$(head -c 24000000 /dev/zero | tr '\\0' a) && echo ${#anon}";
+    let result = RuntimeBuilder::default()
+        .with_task_group(vec![ExecutionStep::Single(task(
+            "/bin/sh",
+            &["-c", script],
+        ))])
+        .with_cgroup_config(
+            CgroupConfigBuilder::new()
+                .with_memory("256M".to_string())
+                .with_memory_high(MEMORY_HIGH.to_string())
+                .build(),
+        )
+        .build()
+        .execute()
+        .expect("runtime execution failed");
+    let RuntimeResult::Success(results, _) = result else {
+        panic!("container setup failed: {result:?}");
+    };
+    assert_no_task_cgroups();
+
+    let TaskResult::Completed {
+        exit_code,
+        stdout,
+        stderr,
+        stats,
+    } = single_result(&results[0])
+    else {
+        panic!("memory.high probe did not complete: {:?}", results[0]);
+    };
+    assert_eq!(*exit_code, 0, "memory.high probe failed: {stderr}");
+    assert_eq!(stdout, "24000000\n");
+    assert_eq!(stats.outcome, TaskOutcome::Exited);
+    assert!(
+        stats.memory_high_count > 0,
+        "usage never reached memory.high: {stats:?}"
+    );
+    assert_eq!(stats.oom_kill_count, 0);
+    assert!(
+        stats.memory_file_bytes >= 8 * 1024 * 1024,
+        "tmpfs page cache was not reported: {stats:?}"
+    );
+    assert!(
+        stats.memory_anon_bytes >= 8 * 1024 * 1024,
+        "anonymous memory held while running was not reported: {stats:?}"
+    );
+}
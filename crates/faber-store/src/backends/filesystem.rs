@@ -1,11 +1,14 @@
 use crate::config::StoreConfig;
 use crate::error::{StoreError, StoreResult};
 use crate::store::FileStore;
-use crate::types::{FileId, FileInfo, FileMetadata, StoredFile, UploadResult, compute_file_id};
+use crate::types::{
+    FileId, FileInfo, FileMetadata, StoreStats, StoredFile, UploadResult, compute_file_id,
+};
 use async_trait::async_trait;
 use bytes::Bytes;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use tempfile::NamedTempFile;
 use tokio::fs;
 use tracing::{debug, warn};
@@ -14,12 +17,15 @@ use tracing::{debug, warn};
 pub struct FilesystemStore {
     base_path: PathBuf,
     config: StoreConfig,
+    usage: DiskUsage,
 }
 
 impl FilesystemStore {
     pub fn new(path: String, config: StoreConfig) -> Self {
+        let base_path = PathBuf::from(path);
         Self {
-            base_path: PathBuf::from(path),
+            usage: DiskUsage::scan(&base_path),
+            base_path,
             config,
         }
     }
@@ -81,7 +87,20 @@ impl FileStore for FilesystemStore {
         temp_file.write_all(&content)?;
         temp_file.flush()?;
         temp_file.as_file().sync_all()?;
-        temp_file.persist(&file_path).map_err(|e| e.error)?;
+        // Another upload of the same content may have landed since the
+        // check above; only the one that creates the file counts it.
+        match temp_file.persist_noclobber(&file_path) {
+            Ok(_) => self.usage.add(size),
+            Err(e) if e.error.kind() == std::io::ErrorKind::AlreadyExists => {
+                debug!("File already exists: {}", file_id);
+                return Ok(UploadResult {
+                    file_id,
+                    size,
+                    already_exists: true,
+                });
+            }
+            Err(e) => return Err(e.error.into()),
+        }
 
         let metadata_json = serde_json::to_string(&metadata)?;
         let meta_parent = metadata_path.parent().ok_or_else(|| {
@@ -150,10 +169,12 @@ impl FileStore for FilesystemStore {
         let file_existed = file_path.exists();
 
         if file_existed {
+            let size = fs::metadata(&file_path).await?.len();
             fs::remove_file(&file_path).await?;
             if metadata_path.exists() {
                 fs::remove_file(&metadata_path).await?;
             }
+            self.usage.remove(size);
             debug!("Deleted file: {}", id);
             Ok(true)
         } else {
@@ -176,18 +197,18 @@ impl FileStore for FilesystemStore {
             if prefix_entry.file_type().await?.is_dir() {
                 let mut file_entries = fs::read_dir(prefix_entry.path()).await?;
                 while let Some(file_entry) = file_entries.next_entry().await? {
-                    if file_entry.file_type().await?.is_file() {
-                        if let Some(name) = file_entry.file_name().to_str() {
-                            let file_id = FileId::from(name);
-                            if let Ok(metadata) = self.get_metadata(&file_id).await {
-                                results.push(FileInfo {
-                                    id: file_id,
-                                    filename: metadata.filename,
-                                    content_type: metadata.content_type,
-                                    size: metadata.size,
-                                    created_at: metadata.created_at,
-                                });
-                            }
+                    if file_entry.file_type().await?.is_file()
+                        && let Some(name) = file_entry.file_name().to_str()
+                    {
+                        let file_id = FileId::from(name);
+                        if let Ok(metadata) = self.get_metadata(&file_id).await {
+                            results.push(FileInfo {
+                                id: file_id,
+                                filename: metadata.filename,
+                                content_type: metadata.content_type,
+                                size: metadata.size,
+                                created_at: metadata.created_at,
+                            });
                         }
                     }
                 }
@@ -213,4 +234,101 @@ impl FileStore for FilesystemStore {
 
         Ok(())
     }
+
+    async fn local_path(&self, id: &FileId) -> StoreResult<Option<PathBuf>> {
+        if !id.is_content_hash() {
+            return Err(StoreError::InvalidFileId(id.to_string()));
+        }
+        self.touch(id).await?;
+        Ok(Some(self.get_file_path(id)))
+    }
+
+    async fn stats(&self) -> StoreResult<Vec<StoreStats>> {
+        Ok(vec![self.usage.stats("filesystem")])
+    }
+
+    fn root_dir(&self) -> Option<&Path> {
+        Some(&self.base_path)
+    }
+
+    async fn check_writable(&self) -> StoreResult<()> {
+        check_writable_dir(&self.base_path).await
+    }
+}
+
+/// Files and bytes on disk, counted once when the store is opened and then
+/// kept up to date by `put` and `delete`, so that `stats` does not walk the
+/// store.
+#[derive(Debug, Default)]
+pub(crate) struct DiskUsage {
+    entries: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl DiskUsage {
+    /// Counts the content files under `base_path/files`. A store that has
+    /// not been written to yet, or cannot be read, starts out empty.
+    pub(crate) fn scan(base_path: &Path) -> Self {
+        let usage = Self::default();
+        let Ok(prefix_dirs) = std::fs::read_dir(base_path.join("files")) else {
+            return usage;
+        };
+        for prefix_entry in prefix_dirs.flatten() {
+            let Ok(file_entries) = std::fs::read_dir(prefix_entry.path()) else {
+                continue;
+            };
+            for file_entry in file_entries.flatten() {
+                let is_content = file_entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| FileId::from(name).is_content_hash());
+                if let Ok(metadata) = file_entry.metadata()
+                    && metadata.is_file()
+                    && is_content
+                {
+                    usage.add(metadata.len());
+                }
+            }
+        }
+        usage
+    }
+
+    pub(crate) fn add(&self, size: u64) {
+        self.entries.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub(crate) fn remove(&self, size: u64) {
+        let _ = self
+            .entries
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        let _ = self
+            .bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                Some(n.saturating_sub(size))
+            });
+    }
+
+    pub(crate) fn stats(&self, backend: &str) -> StoreStats {
+        StoreStats {
+            backend: backend.to_string(),
+            entries: self.entries.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Creates and removes a file in `path`, creating the directory if needed.
+/// Runs on the blocking pool, as syncing the probe can take a while.
+pub(crate) async fn check_writable_dir(path: &Path) -> StoreResult<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&path)?;
+        let mut probe = NamedTempFile::new_in(&path)?;
+        probe.write_all(b"ready")?;
+        probe.as_file().sync_all()?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| StoreError::StorageError(format!("Writability check failed: {e}")))?
 }
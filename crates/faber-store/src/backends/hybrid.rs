@@ -1,13 +1,18 @@
+use crate::backends::filesystem::{DiskUsage, check_writable_dir};
 use crate::config::StoreConfig;
 use crate::error::{StoreError, StoreResult};
 use crate::lru::LruCache;
 use crate::store::FileStore;
-use crate::types::{FileId, FileInfo, FileMetadata, StoredFile, UploadResult, compute_file_id};
+use crate::types::{
+    FileId, FileInfo, FileMetadata, StoreStats, StoredFile, UploadResult, compute_file_id,
+};
 use async_trait::async_trait;
 use bytes::Bytes;
 use dashmap::DashMap;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use tempfile::NamedTempFile;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use tracing::{debug, warn};
@@ -17,9 +22,9 @@ pub struct HybridStore {
     memory_cache: DashMap<FileId, StoredFile>,
     lru: Mutex<LruCache>,
     base_path: PathBuf,
-    max_memory_entries: usize,
     max_memory_size: u64,
     config: StoreConfig,
+    usage: DiskUsage,
 }
 
 impl HybridStore {
@@ -29,11 +34,12 @@ impl HybridStore {
         max_memory_size: u64,
         config: StoreConfig,
     ) -> Self {
+        let base_path = PathBuf::from(path);
         Self {
             memory_cache: DashMap::new(),
             lru: Mutex::new(LruCache::new(max_memory_entries, max_memory_size)),
-            base_path: PathBuf::from(path),
-            max_memory_entries,
+            usage: DiskUsage::scan(&base_path),
+            base_path,
             max_memory_size,
             config,
         }
@@ -62,23 +68,31 @@ impl HybridStore {
         Ok(())
     }
 
+    /// Returns whether this call created the file, rather than finding it
+    /// already on disk.
     async fn store_to_disk(
         &self,
         id: &FileId,
         content: &[u8],
         metadata: &FileMetadata,
-    ) -> StoreResult<()> {
+    ) -> StoreResult<bool> {
         self.ensure_prefix_dirs(id).await?;
 
         let file_path = self.get_file_path(id);
         let metadata_path = self.get_metadata_path(id);
 
-        let temp_path = file_path.with_extension("tmp");
-        let mut file = fs::File::create(&temp_path).await?;
-        file.write_all(content).await?;
-        file.sync_all().await?;
-        drop(file);
-        fs::rename(&temp_path, &file_path).await?;
+        let parent_dir = file_path.parent().ok_or_else(|| {
+            StoreError::StorageError("No parent directory for file path".to_string())
+        })?;
+        let mut temp_file = NamedTempFile::new_in(parent_dir)?;
+        temp_file.write_all(content)?;
+        temp_file.flush()?;
+        temp_file.as_file().sync_all()?;
+        let created = match temp_file.persist_noclobber(&file_path) {
+            Ok(_) => true,
+            Err(e) if e.error.kind() == std::io::ErrorKind::AlreadyExists => false,
+            Err(e) => return Err(e.error.into()),
+        };
 
         let metadata_json = serde_json::to_string(metadata)?;
         let meta_temp = metadata_path.with_extension("tmp");
@@ -88,7 +102,7 @@ impl HybridStore {
         drop(meta_file);
         fs::rename(&meta_temp, &metadata_path).await?;
 
-        Ok(())
+        Ok(created)
     }
 
     async fn load_from_disk(&self, id: &FileId) -> StoreResult<StoredFile> {
@@ -146,7 +160,9 @@ impl FileStore for HybridStore {
         metadata.size = size;
 
         // Always store to disk first
-        self.store_to_disk(&file_id, &content, &metadata).await?;
+        if self.store_to_disk(&file_id, &content, &metadata).await? {
+            self.usage.add(size);
+        }
 
         // Check if already in memory
         if self.memory_cache.contains_key(&file_id) {
@@ -274,10 +290,12 @@ impl FileStore for HybridStore {
         let existed = file_path.exists();
 
         if existed {
+            let size = fs::metadata(&file_path).await?.len();
             fs::remove_file(&file_path).await?;
             if metadata_path.exists() {
                 fs::remove_file(&metadata_path).await?;
             }
+            self.usage.remove(size);
             debug!("Deleted file: {}", id);
             Ok(true)
         } else {
@@ -300,18 +318,18 @@ impl FileStore for HybridStore {
             if prefix_entry.file_type().await?.is_dir() {
                 let mut file_entries = fs::read_dir(prefix_entry.path()).await?;
                 while let Some(file_entry) = file_entries.next_entry().await? {
-                    if file_entry.file_type().await?.is_file() {
-                        if let Some(name) = file_entry.file_name().to_str() {
-                            let file_id = FileId::from(name);
-                            if let Ok(metadata) = self.get_metadata(&file_id).await {
-                                results.push(FileInfo {
-                                    id: file_id,
-                                    filename: metadata.filename,
-                                    content_type: metadata.content_type,
-                                    size: metadata.size,
-                                    created_at: metadata.created_at,
-                                });
-                            }
+                    if file_entry.file_type().await?.is_file()
+                        && let Some(name) = file_entry.file_name().to_str()
+                    {
+                        let file_id = FileId::from(name);
+                        if let Ok(metadata) = self.get_metadata(&file_id).await {
+                            results.push(FileInfo {
+                                id: file_id,
+                                filename: metadata.filename,
+                                content_type: metadata.content_type,
+                                size: metadata.size,
+                                created_at: metadata.created_at,
+                            });
                         }
                     }
                 }
@@ -343,4 +361,34 @@ impl FileStore for HybridStore {
 
         Ok(())
     }
+
+    async fn local_path(&self, id: &FileId) -> StoreResult<Option<PathBuf>> {
+        if !id.is_content_hash() {
+            return Err(StoreError::InvalidFileId(id.to_string()));
+        }
+        self.touch(id).await?;
+        Ok(Some(self.get_file_path(id)))
+    }
+
+    /// The memory tier only caches files that are also on disk, so the
+    /// filesystem tier always covers every file.
+    async fn stats(&self) -> StoreResult<Vec<StoreStats>> {
+        let cached: Vec<u64> = self
+            .memory_cache
+            .iter()
+            .map(|entry| entry.metadata.size)
+            .collect();
+        Ok(vec![
+            StoreStats::from_files("memory", &cached),
+            self.usage.stats("filesystem"),
+        ])
+    }
+
+    fn root_dir(&self) -> Option<&Path> {
+        Some(&self.base_path)
+    }
+
+    async fn check_writable(&self) -> StoreResult<()> {
+        check_writable_dir(&self.base_path).await
+    }
 }
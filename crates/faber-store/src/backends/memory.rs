@@ -1,7 +1,9 @@
 use crate::config::StoreConfig;
 use crate::error::{StoreError, StoreResult};
 use crate::store::FileStore;
-use crate::types::{FileId, FileInfo, FileMetadata, StoredFile, UploadResult, compute_file_id};
+use crate::types::{
+    FileId, FileInfo, FileMetadata, StoreStats, StoredFile, UploadResult, compute_file_id,
+};
 use async_trait::async_trait;
 use bytes::Bytes;
 use dashmap::DashMap;
@@ -118,4 +120,9 @@ impl FileStore for MemoryStore {
 
         Ok(())
     }
+
+    async fn stats(&self) -> StoreResult<Vec<StoreStats>> {
+        let sizes: Vec<u64> = self.files.iter().map(|entry| entry.metadata.size).collect();
+        Ok(vec![StoreStats::from_files("memory", &sizes)])
+    }
 }
@@ -9,7 +9,9 @@ pub use config::{BackendConfig, StoreConfig, StoreConfigBuilder};
 pub use error::{StoreError, StoreResult};
 pub use lru::LruCache;
 pub use store::FileStore;
-pub use types::{FileId, FileInfo, FileMetadata, StoredFile, UploadResult, compute_file_id};
+pub use types::{
+    FileId, FileInfo, FileMetadata, StoreStats, StoredFile, UploadResult, compute_file_id,
+};
 
 #[cfg(feature = "memory")]
 pub use backends::MemoryStore;
@@ -103,6 +103,10 @@ impl LruCache {
         self.entries.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
     pub fn size(&self) -> u64 {
         self.current_size
     }
@@ -1,7 +1,8 @@
 use crate::error::StoreResult;
-use crate::types::{FileId, FileInfo, FileMetadata, StoredFile, UploadResult};
+use crate::types::{FileId, FileInfo, FileMetadata, StoreStats, StoredFile, UploadResult};
 use async_trait::async_trait;
 use bytes::Bytes;
+use std::path::{Path, PathBuf};
 
 #[async_trait]
 pub trait FileStore: Send + Sync {
@@ -18,4 +19,26 @@ pub trait FileStore: Send + Sync {
     async fn list(&self) -> StoreResult<Vec<FileInfo>>;
 
     async fn touch(&self, id: &FileId) -> StoreResult<()>;
+
+    /// Number of files and bytes held, per storage tier.
+    async fn stats(&self) -> StoreResult<Vec<StoreStats>>;
+
+    /// Directory files are kept under, for stores that keep them on the
+    /// local filesystem.
+    fn root_dir(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Checks that new files can be written right now.
+    async fn check_writable(&self) -> StoreResult<()> {
+        Ok(())
+    }
+
+    /// Where the file's content lives on the local filesystem, for stores
+    /// that keep it there, so that it can be read without loading it into
+    /// memory. Counts as an access.
+    async fn local_path(&self, id: &FileId) -> StoreResult<Option<PathBuf>> {
+        self.touch(id).await?;
+        Ok(None)
+    }
 }
@@ -8,6 +8,16 @@ impl FileId {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Whether this is an id [`compute_file_id`] could have produced, and so
+    /// safe to use as a path component.
+    pub fn is_content_hash(&self) -> bool {
+        self.0.len() == 64
+            && self
+                .0
+                .bytes()
+                .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+    }
 }
 
 impl std::fmt::Display for FileId {
@@ -71,10 +81,10 @@ impl FileMetadata {
     }
 
     pub fn is_expired(&self) -> bool {
-        if let Some(ttl) = self.ttl {
-            if let Ok(elapsed) = self.last_accessed.elapsed() {
-                return elapsed > ttl;
-            }
+        if let Some(ttl) = self.ttl
+            && let Ok(elapsed) = self.last_accessed.elapsed()
+        {
+            return elapsed > ttl;
         }
         false
     }
@@ -115,6 +125,30 @@ impl From<&StoredFile> for FileInfo {
     }
 }
 
+/// How much one tier of a store holds. Hybrid stores report their memory and
+/// filesystem tiers separately.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreStats {
+    pub backend: String,
+    pub entries: u64,
+    pub bytes: u64,
+}
+
+impl StoreStats {
+    pub(crate) fn from_files<'a>(backend: &str, sizes: impl IntoIterator<Item = &'a u64>) -> Self {
+        let mut stats = StoreStats {
+            backend: backend.to_string(),
+            entries: 0,
+            bytes: 0,
+        };
+        for size in sizes {
+            stats.entries += 1;
+            stats.bytes += size;
+        }
+        stats
+    }
+}
+
 pub fn compute_file_id(content: &[u8]) -> FileId {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
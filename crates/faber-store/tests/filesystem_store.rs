@@ -1,5 +1,8 @@
 use bytes::Bytes;
-use faber_store::{FileMetadata, FileStore, FilesystemStore, StoreConfig};
+use faber_store::{
+    FileId, FileMetadata, FileStore, FilesystemStore, StoreConfig, StoreError, StoreStats,
+    compute_file_id,
+};
 use tempfile::TempDir;
 
 #[tokio::test]
@@ -112,6 +115,96 @@ async fn test_filesystem_list() {
     assert!(ids.contains(&result2.file_id));
 }
 
+#[tokio::test]
+async fn test_filesystem_stats() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = FilesystemStore::new(
+        temp_dir.path().to_string_lossy().to_string(),
+        StoreConfig::default(),
+    );
+
+    let empty = store.stats().await.unwrap();
+    assert_eq!(empty[0].entries, 0);
+
+    store
+        .put(Bytes::from("File 1"), FileMetadata::new(6))
+        .await
+        .unwrap();
+    let second = store
+        .put(Bytes::from("File 22"), FileMetadata::new(7))
+        .await
+        .unwrap();
+
+    let stats = store.stats().await.unwrap();
+    assert_eq!(
+        stats,
+        vec![StoreStats {
+            backend: "filesystem".to_string(),
+            entries: 2,
+            bytes: 13,
+        }]
+    );
+
+    store.delete(&second.file_id).await.unwrap();
+    store.delete(&second.file_id).await.unwrap();
+    let stats = store.stats().await.unwrap();
+    assert_eq!((stats[0].entries, stats[0].bytes), (1, 6));
+}
+
+#[tokio::test]
+async fn test_filesystem_stats_count_existing_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_string_lossy().to_string();
+    FilesystemStore::new(path.clone(), StoreConfig::default())
+        .put(Bytes::from("File 1"), FileMetadata::new(6))
+        .await
+        .unwrap();
+
+    let reopened = FilesystemStore::new(path, StoreConfig::default());
+    reopened
+        .put(Bytes::from("File 1"), FileMetadata::new(6))
+        .await
+        .unwrap();
+    reopened
+        .put(Bytes::from("File 22"), FileMetadata::new(7))
+        .await
+        .unwrap();
+
+    let stats = reopened.stats().await.unwrap();
+    assert_eq!((stats[0].entries, stats[0].bytes), (2, 13));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_filesystem_concurrent_puts_count_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = std::sync::Arc::new(FilesystemStore::new(
+        temp_dir.path().to_string_lossy().to_string(),
+        StoreConfig::default(),
+    ));
+
+    let uploads: Vec<_> = (0..8)
+        .map(|_| {
+            let store = store.clone();
+            tokio::spawn(async move {
+                store
+                    .put(Bytes::from("Same content"), FileMetadata::new(12))
+                    .await
+                    .unwrap()
+            })
+        })
+        .collect();
+    let mut created = 0;
+    for upload in uploads {
+        if !upload.await.unwrap().already_exists {
+            created += 1;
+        }
+    }
+
+    assert_eq!(created, 1);
+    let stats = store.stats().await.unwrap();
+    assert_eq!((stats[0].entries, stats[0].bytes), (1, 12));
+}
+
 #[tokio::test]
 async fn test_filesystem_touch() {
     let temp_dir = TempDir::new().unwrap();
@@ -154,3 +247,31 @@ async fn test_filesystem_persistence() {
     let retrieved = store2.get(&file_id).await.unwrap();
     assert_eq!(retrieved.content, &content[..]);
 }
+
+#[tokio::test]
+async fn test_filesystem_local_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = FilesystemStore::new(
+        temp_dir.path().to_string_lossy().to_string(),
+        StoreConfig::default(),
+    );
+
+    let content = Bytes::from("Input data");
+    let result = store
+        .put(content.clone(), FileMetadata::new(content.len() as u64))
+        .await
+        .unwrap();
+
+    let path = store.local_path(&result.file_id).await.unwrap().unwrap();
+    assert!(path.starts_with(temp_dir.path()));
+    assert_eq!(std::fs::read(path).unwrap(), &content[..]);
+
+    assert!(matches!(
+        store.local_path(&FileId::from("../../etc/passwd")).await,
+        Err(StoreError::InvalidFileId(_))
+    ));
+    assert!(matches!(
+        store.local_path(&compute_file_id(b"missing")).await,
+        Err(StoreError::NotFound(_))
+    ));
+}
@@ -1,5 +1,5 @@
 use bytes::Bytes;
-use faber_store::{FileMetadata, FileStore, MemoryStore, StoreConfig, compute_file_id};
+use faber_store::{FileMetadata, FileStore, MemoryStore, StoreConfig, StoreStats, compute_file_id};
 use std::time::Duration;
 
 #[tokio::test]
@@ -92,6 +92,30 @@ async fn test_list() {
     assert!(ids.contains(&result2.file_id));
 }
 
+#[tokio::test]
+async fn test_stats() {
+    let store = MemoryStore::new(StoreConfig::default());
+    let content = Bytes::from("Counted");
+    store
+        .put(content.clone(), FileMetadata::new(content.len() as u64))
+        .await
+        .unwrap();
+    store
+        .put(content.clone(), FileMetadata::new(content.len() as u64))
+        .await
+        .unwrap();
+
+    let stats = store.stats().await.unwrap();
+    assert_eq!(
+        stats,
+        vec![StoreStats {
+            backend: "memory".to_string(),
+            entries: 1,
+            bytes: 7,
+        }]
+    );
+}
+
 #[tokio::test]
 async fn test_touch() {
     let store = MemoryStore::new(StoreConfig::default());
@@ -154,3 +178,21 @@ async fn test_file_id_is_sha256() {
         "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
     );
 }
+
+#[tokio::test]
+async fn test_local_path_is_unavailable() {
+    let store = MemoryStore::new(StoreConfig::default());
+    let content = Bytes::from("In memory only");
+    let result = store
+        .put(content.clone(), FileMetadata::new(content.len() as u64))
+        .await
+        .unwrap();
+
+    assert!(store.local_path(&result.file_id).await.unwrap().is_none());
+    assert!(
+        store
+            .local_path(&compute_file_id(b"missing"))
+            .await
+            .is_err()
+    );
+}
@@ -1,4 +1,10 @@
+use faber_runtime::CpusetPartition;
 use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A named workspace template and its layer directories, lowest first.
+pub type WorkspaceLayers = (String, Vec<PathBuf>);
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -8,6 +14,49 @@ pub struct Config {
     pub api_key: String,
     pub cache_enabled: bool,
     pub store_backend: StoreBackend,
+    pub environments: Vec<(String, PathBuf)>,
+    pub images: Vec<(String, PathBuf)>,
+    pub image_store_path: PathBuf,
+    pub workspaces: Vec<WorkspaceLayers>,
+    pub mounts: Vec<(String, String)>,
+    pub pool_size: usize,
+    pub pool_refill_rate: u32,
+    pub execution_backend: ExecutionBackendKind,
+    pub runsc_path: Option<PathBuf>,
+    pub landlock: bool,
+    /// CPUs handed out to executions, as a kernel CPU list such as `2-7`.
+    pub cpus: Option<String>,
+    pub cores_per_execution: usize,
+    pub cpuset_partition: CpusetPartition,
+    /// How often running tasks are sampled for their resource timeline.
+    pub sample_interval: Option<Duration>,
+    /// CPU time all of a task's processes may use together.
+    pub cpu_time_budget: Option<Duration>,
+    /// How long a task may make no progress before it is stopped.
+    pub idle_timeout: Option<Duration>,
+    /// Largest output limit a task may ask for, in bytes per stream.
+    pub max_output_limit: usize,
+    /// Longest grace period a task may ask for before it is killed.
+    pub max_grace_period_ms: u64,
+    /// Output longer than this many bytes is moved to the file store.
+    pub inline_output_limit: usize,
+    /// Where tasks' output past the inline limit is written before it is
+    /// moved to the file store.
+    pub output_spill_dir: PathBuf,
+    /// Free space `/ready` requires for containers and stored files.
+    pub ready_min_free_bytes: u64,
+    /// Share of recent executions that may fail during setup before
+    /// `/ready` fails.
+    pub ready_max_setup_failure_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionBackendKind {
+    Namespace,
+    /// Runs tasks as plain host processes; development only.
+    Process,
+    /// Runs each task in a gVisor sandbox; requires `FABER_RUNSC`.
+    Gvisor,
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +81,30 @@ impl Config {
             api_key: Self::load_api_key()?,
             cache_enabled: Self::load_cache_enabled(),
             store_backend: Self::load_store_backend(),
+            environments: Self::load_named_paths("FABER_ENVIRONMENTS")?,
+            images: Self::load_named_paths("FABER_IMAGES")?,
+            image_store_path: Self::load_image_store_path(),
+            workspaces: Self::load_workspaces()?,
+            mounts: Self::load_mounts()?,
+            pool_size: Self::load_pool_size()?,
+            pool_refill_rate: Self::load_pool_refill_rate()?,
+            execution_backend: Self::load_execution_backend()?,
+            runsc_path: env::var("FABER_RUNSC").ok().map(PathBuf::from),
+            landlock: Self::load_landlock(),
+            cpus: env::var("FABER_CPUS")
+                .ok()
+                .filter(|cpus| !cpus.trim().is_empty()),
+            cores_per_execution: Self::load_cores_per_execution()?,
+            cpuset_partition: Self::load_cpuset_partition()?,
+            sample_interval: Self::load_sample_interval()?,
+            cpu_time_budget: Self::load_cpu_time_budget()?,
+            idle_timeout: Self::load_idle_timeout()?,
+            max_output_limit: Self::load_byte_count("FABER_MAX_OUTPUT_LIMIT", 16 * 1024 * 1024)?,
+            max_grace_period_ms: Self::load_max_grace_period_ms()?,
+            inline_output_limit: Self::load_byte_count("FABER_INLINE_OUTPUT_LIMIT", 1024 * 1024)?,
+            output_spill_dir: Self::load_output_spill_dir(),
+            ready_min_free_bytes: Self::load_ready_min_free_bytes()?,
+            ready_max_setup_failure_rate: Self::load_ready_max_setup_failure_rate()?,
         })
     }
 
@@ -60,6 +133,12 @@ impl Config {
             .unwrap_or(false)
     }
 
+    fn load_landlock() -> bool {
+        env::var("FABER_LANDLOCK")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false)
+    }
+
     fn load_store_backend() -> StoreBackend {
         match env::var("FABER_STORE_BACKEND").unwrap_or_default().as_str() {
             "filesystem" => {
@@ -87,4 +166,175 @@ impl Config {
             _ => StoreBackend::Memory,
         }
     }
+
+    /// Parses a comma-separated list of `name=/path` pairs, e.g.
+    /// `FABER_ENVIRONMENTS=gcc-13=/var/lib/faber/rootfs/gcc-13` or
+    /// `FABER_IMAGES=gcc-13=/var/lib/faber/images/gcc-13.tar`.
+    fn load_named_paths(
+        var: &str,
+    ) -> Result<Vec<(String, PathBuf)>, Box<dyn std::error::Error + Send + Sync>> {
+        let Ok(value) = env::var(var) else {
+            return Ok(Vec::new());
+        };
+
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (name, path) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid {var} entry '{entry}': expected name=/path"))?;
+                Ok((name.trim().to_string(), PathBuf::from(path.trim())))
+            })
+            .collect()
+    }
+
+    /// Parses `FABER_WORKSPACES`, where each template lists its layer
+    /// directories lowest first, separated by `:`, e.g.
+    /// `starter=/srv/faber/common:/srv/faber/starter`.
+    fn load_workspaces() -> Result<Vec<WorkspaceLayers>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self::load_named_paths("FABER_WORKSPACES")?
+            .into_iter()
+            .map(|(name, layers)| {
+                let layers = layers
+                    .to_string_lossy()
+                    .split(':')
+                    .map(PathBuf::from)
+                    .collect();
+                (name, layers)
+            })
+            .collect())
+    }
+
+    /// Parses a semicolon-separated list of `name=source:target[:options]`
+    /// mount specifications, e.g.
+    /// `FABER_MOUNTS=toolchains=/opt/toolchains:/opt/toolchains:ro;scratch=tmpfs:/scratch:size=64M`.
+    /// The specifications themselves are validated by `MountSpec`.
+    fn load_mounts() -> Result<Vec<(String, String)>, Box<dyn std::error::Error + Send + Sync>> {
+        let Ok(value) = env::var("FABER_MOUNTS") else {
+            return Ok(Vec::new());
+        };
+
+        value
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (name, spec) = entry.split_once('=').ok_or_else(|| {
+                    format!("Invalid FABER_MOUNTS entry '{entry}': expected name=source:target")
+                })?;
+                Ok((name.trim().to_string(), spec.trim().to_string()))
+            })
+            .collect()
+    }
+
+    fn load_pool_size() -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let pool_size_str = env::var("FABER_POOL_SIZE").unwrap_or_else(|_| "0".to_string());
+        pool_size_str.parse::<usize>().map_err(|e| e.into())
+    }
+
+    fn load_pool_refill_rate() -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+        let refill_rate_str =
+            env::var("FABER_POOL_REFILL_RATE").unwrap_or_else(|_| "4".to_string());
+        refill_rate_str.parse::<u32>().map_err(|e| e.into())
+    }
+
+    fn load_cores_per_execution() -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let cores_str = env::var("FABER_CORES_PER_EXECUTION").unwrap_or_else(|_| "1".to_string());
+        cores_str.parse::<usize>().map_err(|e| e.into())
+    }
+
+    fn load_cpuset_partition() -> Result<CpusetPartition, Box<dyn std::error::Error + Send + Sync>>
+    {
+        match env::var("FABER_CPUSET_PARTITION") {
+            Ok(partition) => partition.parse::<CpusetPartition>().map_err(|e| {
+                format!(
+                    "Invalid FABER_CPUSET_PARTITION: {e}: expected 'member', 'root' or 'isolated'"
+                )
+                .into()
+            }),
+            Err(_) => Ok(CpusetPartition::default()),
+        }
+    }
+
+    fn load_sample_interval() -> Result<Option<Duration>, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let interval_str = env::var("FABER_SAMPLE_INTERVAL_MS").unwrap_or_else(|_| "0".to_string());
+        let interval_ms = interval_str.parse::<u64>()?;
+        Ok((interval_ms > 0).then(|| Duration::from_millis(interval_ms)))
+    }
+
+    fn load_cpu_time_budget() -> Result<Option<Duration>, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let budget_str = env::var("FABER_CPU_TIME_BUDGET_MS").unwrap_or_else(|_| "0".to_string());
+        let budget_ms = budget_str.parse::<u64>()?;
+        Ok((budget_ms > 0).then(|| Duration::from_millis(budget_ms)))
+    }
+
+    fn load_idle_timeout() -> Result<Option<Duration>, Box<dyn std::error::Error + Send + Sync>> {
+        let timeout_str = env::var("FABER_IDLE_TIMEOUT_MS").unwrap_or_else(|_| "0".to_string());
+        let timeout_ms = timeout_str.parse::<u64>()?;
+        Ok((timeout_ms > 0).then(|| Duration::from_millis(timeout_ms)))
+    }
+
+    fn load_max_grace_period_ms() -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let grace_str =
+            env::var("FABER_MAX_GRACE_PERIOD_MS").unwrap_or_else(|_| "5000".to_string());
+        Ok(grace_str.parse::<u64>()?)
+    }
+
+    /// Parses a byte count that defaults to 1 MiB, the runtime's output limit.
+    fn load_byte_count(
+        name: &str,
+        default: usize,
+    ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let bytes_str = env::var(name).unwrap_or_else(|_| default.to_string());
+        Ok(bytes_str.parse::<usize>()?)
+    }
+
+    fn load_ready_min_free_bytes() -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let bytes_str = env::var("FABER_READY_MIN_FREE_BYTES")
+            .unwrap_or_else(|_| (256 * 1024 * 1024).to_string());
+        Ok(bytes_str.parse::<u64>()?)
+    }
+
+    fn load_ready_max_setup_failure_rate() -> Result<f64, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let rate_str =
+            env::var("FABER_READY_MAX_SETUP_FAILURE_RATE").unwrap_or_else(|_| "0.5".to_string());
+        let rate = rate_str.parse::<f64>()?;
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(format!(
+                "Invalid FABER_READY_MAX_SETUP_FAILURE_RATE '{rate_str}': expected a value from 0 to 1"
+            )
+            .into());
+        }
+        Ok(rate)
+    }
+
+    fn load_execution_backend()
+    -> Result<ExecutionBackendKind, Box<dyn std::error::Error + Send + Sync>> {
+        match env::var("FABER_BACKEND").unwrap_or_default().as_str() {
+            "" | "namespace" => Ok(ExecutionBackendKind::Namespace),
+            "process" => Ok(ExecutionBackendKind::Process),
+            "gvisor" => Ok(ExecutionBackendKind::Gvisor),
+            other => Err(format!(
+                "Invalid FABER_BACKEND '{other}': expected 'namespace', 'process' or 'gvisor'"
+            )
+            .into()),
+        }
+    }
+
+    fn load_image_store_path() -> PathBuf {
+        env::var("FABER_IMAGE_STORE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("/var/lib/faber/images"))
+    }
+
+    fn load_output_spill_dir() -> PathBuf {
+        env::var("FABER_OUTPUT_SPILL_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("/var/lib/faber/output"))
+    }
 }
@@ -1,9 +1,15 @@
 use faber_api::axum;
-use faber_api::{ServeConfig, build_router, serve};
+use faber_api::{ExecutionConfig, ServeConfig, build_router, serve};
+use faber_runtime::{
+    CgroupConfigBuilder, ContainerConfigBuilder, ContainerPoolBuilder, CpuAllocatorBuilder,
+    Environment, ExecutionBackend, GvisorBackend, ImageStore, MountSpec, NamespaceBackend,
+    ProcessBackend, WorkspaceTemplate,
+};
 use faber_store::StoreConfig;
+use std::sync::Arc;
 
 mod config;
-use config::{Config, StoreBackend};
+use config::{Config, ExecutionBackendKind, StoreBackend};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -23,7 +29,137 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     let file_store = faber_store::create_store(store_config);
 
-    let router = build_router(config.api_key.clone(), config.cache_enabled, file_store);
+    let mut execution_config = ExecutionConfig {
+        landlock: config.landlock,
+        max_output_limit: config.max_output_limit,
+        max_grace_period_ms: config.max_grace_period_ms,
+        inline_output_limit: config.inline_output_limit,
+        output_spill_dir: config.output_spill_dir.clone(),
+        ready_min_free_bytes: config.ready_min_free_bytes,
+        ready_max_setup_failure_rate: config.ready_max_setup_failure_rate,
+        ..Default::default()
+    };
+    for (name, rootfs) in &config.environments {
+        let environment = Environment::from_rootfs(rootfs)?;
+        execution_config
+            .environments
+            .insert(name.clone(), environment);
+    }
+
+    let image_store = ImageStore::new(&config.image_store_path);
+    for (name, source) in &config.images {
+        if execution_config.environments.contains_key(name) {
+            return Err(format!("Environment '{name}' is defined more than once").into());
+        }
+        let image = image_store.import(source)?;
+        println!("📦 Imported image {} as '{}'", image.digest(), name);
+        execution_config
+            .environments
+            .insert(name.clone(), Environment::from_image(image));
+    }
+
+    for (name, layers) in &config.workspaces {
+        let template = WorkspaceTemplate::from_layers(layers.clone())?;
+        execution_config.workspaces.insert(name.clone(), template);
+    }
+
+    for (name, spec) in &config.mounts {
+        let mount: MountSpec = spec.parse()?;
+        execution_config.mounts.insert(name.clone(), mount);
+    }
+
+    let mut cgroup_config = CgroupConfigBuilder::new().with_sample_interval(config.sample_interval);
+    if let Some(budget) = config.cpu_time_budget {
+        cgroup_config = cgroup_config.with_cpu_time_budget(budget);
+    }
+    if let Some(idle_timeout) = config.idle_timeout {
+        cgroup_config = cgroup_config.with_idle_timeout(idle_timeout);
+    }
+    let cgroup_config = cgroup_config.build();
+
+    if config.pool_size > 0 && config.execution_backend == ExecutionBackendKind::Namespace {
+        // Warm the plain configuration and each environment; requests that
+        // add a workspace or mounts fall back to an inline setup.
+        let mut pool = ContainerPoolBuilder::default()
+            .with_size(config.pool_size)
+            .with_refill_rate(config.pool_refill_rate)
+            .with_shape(
+                ContainerConfigBuilder::new()
+                    .with_landlock(config.landlock)
+                    .with_output_spill(&config.output_spill_dir, config.inline_output_limit)
+                    .build(),
+                cgroup_config.clone(),
+            );
+        for environment in execution_config.environments.values() {
+            pool = pool.with_shape(
+                ContainerConfigBuilder::new()
+                    .with_environment(environment)
+                    .with_landlock(config.landlock)
+                    .with_output_spill(&config.output_spill_dir, config.inline_output_limit)
+                    .build(),
+                cgroup_config.clone(),
+            );
+        }
+        execution_config.pool = Some(pool.build());
+    }
+
+    if let Some(cpus) = &config.cpus {
+        if config.execution_backend != ExecutionBackendKind::Namespace {
+            return Err("FABER_CPUS requires the namespace backend".into());
+        }
+        let allocator = CpuAllocatorBuilder::default()
+            .with_cpu_list(cpus)?
+            .with_cores_per_execution(config.cores_per_execution)
+            .with_partition(config.cpuset_partition)
+            .build()?;
+        println!(
+            "🧮 Pinning executions to {} exclusive slot(s) of {} core(s)",
+            allocator.stats().slots,
+            config.cores_per_execution
+        );
+        execution_config.cpus = Some(allocator);
+    }
+
+    let gvisor = config
+        .runsc_path
+        .as_ref()
+        .map(|runsc| Arc::new(GvisorBackend::new(runsc)) as Arc<dyn ExecutionBackend>);
+    if let Some(gvisor) = &gvisor {
+        execution_config
+            .backends
+            .insert(gvisor.name().to_string(), gvisor.clone());
+    }
+
+    execution_config.backend = match config.execution_backend {
+        ExecutionBackendKind::Namespace => {
+            let mut backend = NamespaceBackend::default().with_cgroup_config(cgroup_config);
+            if let Some(pool) = &execution_config.pool {
+                backend = backend.with_pool(pool.clone());
+            }
+            if let Some(cpus) = &execution_config.cpus {
+                backend = backend.with_cpu_allocator(cpus.clone());
+            }
+            Arc::new(backend)
+        }
+        ExecutionBackendKind::Process => {
+            eprintln!("⚠️  FABER_BACKEND=process runs tasks WITHOUT ISOLATION; development only");
+            Arc::new(ProcessBackend::default())
+        }
+        ExecutionBackendKind::Gvisor => {
+            gvisor.ok_or("FABER_BACKEND=gvisor requires FABER_RUNSC to be set")?
+        }
+    };
+    execution_config.backends.insert(
+        execution_config.backend.name().to_string(),
+        execution_config.backend.clone(),
+    );
+
+    let router = build_router(
+        config.api_key.clone(),
+        config.cache_enabled,
+        file_store,
+        execution_config,
+    );
     let router = axum::Router::new().nest("/api/v1", router);
 
     let serve_config = ServeConfig {